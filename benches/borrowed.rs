@@ -0,0 +1,84 @@
+//! Reads, walks and writes a large document with the owned and the borrowed AST.
+//!
+//! ```shell
+//! cargo bench --bench borrowed
+//! ```
+
+use std::time::{Duration, Instant};
+use pandoc::builder::{doc, emph, header, link, para, text, Blocks};
+use pandoc::{borrowed, compat, definition};
+use pandoc::walkable::Walkable;
+
+const SECTIONS: usize = 2_000;
+const RUNS: usize = 10;
+
+/// A book of `SECTIONS` sections of a few paragraphs, with a link per paragraph, the first of
+/// each section to rewrite.
+fn book() -> String {
+    let mut blocks = Blocks::new();
+    for section in 0..SECTIONS {
+        blocks += header(2, text(&format!("Section {}", section)));
+        for paragraph in 0..5 {
+            let words = text("Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.");
+            let url = if paragraph == 0 { "http://example.com" } else { "https://example.com" };
+            blocks += para(words.clone() + emph(words) + link(url, "link"));
+        }
+    }
+    serde_json::to_string(&doc(blocks)).unwrap()
+}
+
+/// Median duration of `RUNS` runs of `f`.
+fn measure<F>(f: F) -> Duration where F: Fn() -> Vec<u8> {
+    let mut durations: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let output = f();
+            let duration = start.elapsed();
+            assert!(!output.is_empty());
+            duration
+        })
+        .collect();
+    durations.sort();
+    durations[RUNS / 2]
+}
+
+fn main() {
+    let json = book();
+    println!("document: {:.1} MB", json.len() as f64 / 1e6);
+
+    let via_value = measure(|| {
+        let document = compat::upgrade(serde_json::from_str(&json).unwrap()).unwrap();
+        let pandoc: definition::Pandoc = serde_json::from_value(document).unwrap();
+        let pandoc = pandoc.walk(&mut |inline: definition::Inline| match inline {
+            definition::Inline::Link(attr, ils, (url, title)) if url.starts_with("http:") => {
+                definition::Inline::Link(attr, ils, (url.replace("http:", "https:"), title))
+            },
+            _ => inline
+        });
+        serde_json::to_vec(&pandoc).unwrap()
+    });
+    let owned = measure(|| {
+        let pandoc: definition::Pandoc = serde_json::from_str(&json).unwrap();
+        let pandoc = pandoc.walk(&mut |inline: definition::Inline| match inline {
+            definition::Inline::Link(attr, ils, (url, title)) if url.starts_with("http:") => {
+                definition::Inline::Link(attr, ils, (url.replace("http:", "https:"), title))
+            },
+            _ => inline
+        });
+        serde_json::to_vec(&pandoc).unwrap()
+    });
+    let borrowed = measure(|| {
+        let pandoc = borrowed::Pandoc::from_json(&json).unwrap();
+        let pandoc = pandoc.walk(&mut |inline: borrowed::Inline| match inline {
+            borrowed::Inline::Link(attr, ils, (url, title)) if url.starts_with("http:") => {
+                borrowed::Inline::Link(attr, ils, (url.replace("http:", "https:").into(), title))
+            },
+            _ => inline
+        });
+        serde_json::to_vec(&pandoc).unwrap()
+    });
+
+    println!("owned, through serde_json::Value: {:?}", via_value);
+    println!("owned:                            {:?} ({:.2}x)", owned, via_value.as_secs_f64() / owned.as_secs_f64());
+    println!("borrowed:                         {:?} ({:.2}x)", borrowed, via_value.as_secs_f64() / borrowed.as_secs_f64());
+}
@@ -0,0 +1,221 @@
+//! `#[derive(Walkable, WalkableMut, Queryable)]` for the `pandoc` crate, re-exported next to the
+//! traits they implement.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, LitStr, Path, Result, Type};
+
+/// Implements `Walkable<T, U>` for every callback shape all the fields are walkable with, by
+/// walking the fields in order.
+///
+/// A field marked `#[walkable(skip)]` is kept as is, and `#[walkable(crate = "path")]` on the
+/// type gives the path of the `pandoc` crate when it is not `::pandoc`. `#[walkable(node)]` is
+/// for the nodes of `pandoc` itself, which walk their children before the callback sees them.
+#[proc_macro_derive(Walkable, attributes(walkable))]
+pub fn derive_walkable(input: TokenStream) -> TokenStream {
+    derive(Traversal::Walk, parse_macro_input!(input as DeriveInput))
+}
+
+/// Implements `WalkableMut<T, U>` for every callback shape all the fields are walkable with, by
+/// walking the fields in order. Takes the attributes of [`Walkable`](derive@Walkable).
+#[proc_macro_derive(WalkableMut, attributes(walkable))]
+pub fn derive_walkable_mut(input: TokenStream) -> TokenStream {
+    derive(Traversal::WalkMut, parse_macro_input!(input as DeriveInput))
+}
+
+/// Implements `Queryable<T>` for every `T` all the fields are queryable with, by appending the
+/// results of the fields in order. Takes the attributes of [`Walkable`](derive@Walkable).
+#[proc_macro_derive(Queryable, attributes(walkable))]
+pub fn derive_queryable(input: TokenStream) -> TokenStream {
+    derive(Traversal::Query, parse_macro_input!(input as DeriveInput))
+}
+
+#[derive(Clone, Copy)]
+enum Traversal {
+    Walk,
+    WalkMut,
+    Query,
+}
+
+/// The attributes of the derived type.
+struct Container {
+    krate: Path,
+    node: bool,
+}
+
+fn derive(traversal: Traversal, input: DeriveInput) -> TokenStream {
+    expand(traversal, input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(traversal: Traversal, input: DeriveInput) -> Result<TokenStream2> {
+    let Container { krate, node } = container(&input.attrs)?;
+    let field_trait = match traversal {
+        Traversal::Walk => quote!(#krate::walkable::Walkable<__T, __U>),
+        Traversal::WalkMut => quote!(#krate::walkable_mut::WalkableMut<__T, __U>),
+        Traversal::Query => quote!(#krate::queryable::Queryable<__T>),
+    };
+    // A node implements the traversal of its children, and the traversal of the node itself
+    // decides when the callback sees it.
+    let implemented = match (traversal, node) {
+        (Traversal::Walk, true) => quote!(#krate::walkable::WalkChildren<__T, __U>),
+        (Traversal::WalkMut, true) => quote!(#krate::walkable_mut::WalkChildrenMut<__T, __U>),
+        (Traversal::Query, true) => quote!(#krate::queryable::QueryChildren<__T>),
+        (_, false) => field_trait.clone(),
+    };
+
+    let mut walked = Vec::new();
+    let arms = match &input.data {
+        Data::Struct(data) => vec![arm(traversal, &krate, quote!(Self), &data.fields, &field_trait, &mut walked)?],
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let name = &variant.ident;
+                arms.push(arm(traversal, &krate, quote!(Self::#name), &variant.fields, &field_trait, &mut walked)?);
+            }
+            arms
+        },
+        Data::Union(data) => {
+            let name = match traversal {
+                Traversal::Walk => "Walkable",
+                Traversal::WalkMut => "WalkableMut",
+                Traversal::Query => "Queryable",
+            };
+            return Err(Error::new(data.union_token.span, format!("{} cannot be derived for unions", name)));
+        },
+    };
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    generics.params.push(parse_quote!(__T));
+    if let Traversal::Walk | Traversal::WalkMut = traversal {
+        generics.params.push(parse_quote!(__U));
+    }
+    let where_clause = generics.make_where_clause();
+    for ty in walked {
+        where_clause.predicates.push(parse_quote!(#ty: #field_trait));
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let method = match (traversal, node) {
+        (Traversal::Walk, false) => quote! {
+            fn try_walk<__F, __E>(self, f: &mut __F) -> ::std::result::Result<Self, __E> where __F: FnMut(__T) -> ::std::result::Result<__U, __E> {
+                ::std::result::Result::Ok(match self { #(#arms,)* })
+            }
+        },
+        (Traversal::Walk, true) => quote! {
+            fn try_walk_children<__F, __E>(self, f: &mut __F) -> ::std::result::Result<Self, __E> where __F: FnMut(__T) -> ::std::result::Result<__U, __E> {
+                ::std::result::Result::Ok(match self { #(#arms,)* })
+            }
+        },
+        (Traversal::WalkMut, false) => quote! {
+            fn walk_mut<__F>(&mut self, f: &mut __F) where __F: FnMut(&mut __T) -> __U {
+                match self { #(#arms,)* }
+            }
+        },
+        (Traversal::WalkMut, true) => quote! {
+            fn walk_children_mut<__F>(&mut self, f: &mut __F) where __F: FnMut(&mut __T) -> __U {
+                match self { #(#arms,)* }
+            }
+        },
+        (Traversal::Query, false) => quote! {
+            fn query<__F, __R>(&self, f: &mut __F) -> __R where __F: FnMut(&__T) -> __R, __R: #krate::queryable::Monoid {
+                match self { #(#arms,)* }
+            }
+        },
+        (Traversal::Query, true) => quote! {
+            fn query_children<__F, __R>(&self, f: &mut __F) -> __R where __F: FnMut(&__T) -> __R, __R: #krate::queryable::Monoid {
+                match self { #(#arms,)* }
+            }
+        },
+    };
+    Ok(quote! {
+        impl #impl_generics #implemented for #name #ty_generics #where_clause {
+            #method
+        }
+    })
+}
+
+/// The match arm traversing the fields of `path`, pushing the types of the walked fields to
+/// `walked`.
+fn arm(traversal: Traversal, krate: &Path, path: TokenStream2, fields: &Fields, field_trait: &TokenStream2, walked: &mut Vec<Type>) -> Result<TokenStream2> {
+    let mut bindings = Vec::new();
+    let mut values = Vec::new();
+    let mut walks = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let binding = format_ident!("__{}", i);
+        let ty = &field.ty;
+        if skipped(&field.attrs)? {
+            values.push(quote!(#binding));
+            // Only a rebuilt value needs the skipped fields.
+            bindings.push(match traversal {
+                Traversal::Walk => quote!(#binding),
+                Traversal::WalkMut | Traversal::Query => quote!(_),
+            });
+        } else {
+            let walk = match traversal {
+                Traversal::Walk => quote!(<#ty as #field_trait>::try_walk(#binding, f)?),
+                Traversal::WalkMut => quote!(<#ty as #field_trait>::walk_mut(#binding, f)),
+                Traversal::Query => quote!(<#ty as #field_trait>::query(#binding, f)),
+            };
+            walked.push(ty.clone());
+            values.push(walk.clone());
+            walks.push(walk);
+            bindings.push(quote!(#binding));
+        }
+    }
+    let (pattern, rebuilt) = match fields {
+        Fields::Named(named) => {
+            let names: Vec<_> = named.named.iter().map(|it| &it.ident).collect();
+            (quote!(#path { #(#names: #bindings),* }), quote!(#path { #(#names: #values),* }))
+        },
+        Fields::Unnamed(_) => (quote!(#path(#(#bindings),*)), quote!(#path(#(#values),*))),
+        Fields::Unit => (path.clone(), path),
+    };
+    Ok(match traversal {
+        Traversal::Walk => quote!(#pattern => #rebuilt),
+        Traversal::WalkMut => quote!(#pattern => { #(#walks;)* }),
+        Traversal::Query => {
+            let monoid = quote!(#krate::queryable::Monoid);
+            let result = walks.into_iter().fold(None, |acc, it| Some(match acc {
+                Some(acc) => quote!(#monoid::append(#acc, #it)),
+                None => it,
+            }));
+            let result = result.unwrap_or_else(|| quote!(<__R as #monoid>::empty()));
+            quote!(#pattern => #result)
+        },
+    })
+}
+
+fn container(attrs: &[Attribute]) -> Result<Container> {
+    let mut container = Container { krate: parse_quote!(::pandoc), node: false };
+    for attr in attrs.iter().filter(|it| it.path().is_ident("walkable")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                container.krate = meta.value()?.parse::<LitStr>()?.parse()?;
+                Ok(())
+            } else if meta.path.is_ident("node") {
+                container.node = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `crate = \"...\"` or `node`"))
+            }
+        })?;
+    }
+    Ok(container)
+}
+
+fn skipped(attrs: &[Attribute]) -> Result<bool> {
+    let mut skip = false;
+    for attr in attrs.iter().filter(|it| it.path().is_ident("walkable")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip`"))
+            }
+        })?;
+    }
+    Ok(skip)
+}
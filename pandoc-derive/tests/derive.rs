@@ -0,0 +1,142 @@
+use pandoc::definition::Inline;
+use pandoc::queryable::Queryable;
+use pandoc::walkable::Walkable;
+use pandoc::walkable_mut::{Splice, WalkableMut};
+
+/// Not walkable: compiles only because the fields of this type are skipped.
+#[derive(Clone, Debug, PartialEq)]
+struct Opaque(u32);
+
+#[derive(Clone, Debug, Walkable, WalkableMut, Queryable)]
+struct Named {
+    title: Vec<Inline>,
+    #[walkable(skip)]
+    opaque: Opaque,
+    note: Option<Vec<Inline>>,
+}
+
+#[derive(Clone, Debug, Walkable, WalkableMut, Queryable)]
+enum Shape {
+    Tuple(#[walkable(skip)] Opaque, Vec<Inline>),
+    Named { inlines: Vec<Inline>, #[walkable(skip)] opaque: Opaque },
+    Unit,
+}
+
+#[derive(Clone, Debug, Walkable, WalkableMut, Queryable)]
+struct Generic<W>(W, W);
+
+#[derive(Clone, Debug, Walkable, WalkableMut, Queryable)]
+struct Nested {
+    named: Vec<Named>,
+    shapes: Option<Vec<Vec<Shape>>>,
+}
+
+mod renamed {
+    use pandoc as other;
+    use other::definition::Inline;
+    use other::queryable::Queryable;
+    use other::walkable::Walkable;
+    use other::walkable_mut::WalkableMut;
+
+    #[derive(Walkable, WalkableMut, Queryable)]
+    #[walkable(crate = "other")]
+    pub struct Renamed(pub Vec<Inline>);
+}
+
+fn str(text: &str) -> Inline {
+    Inline::Str(text.into())
+}
+
+fn texts<Q>(value: &Q) -> Vec<String> where Q: Queryable<Inline> {
+    value.query(&mut |inline: &Inline| match inline {
+        Inline::Str(text) => vec![text.clone()],
+        _ => vec![],
+    })
+}
+
+fn upper(inline: Inline) -> Inline {
+    match inline {
+        Inline::Str(text) => Inline::Str(text.to_uppercase()),
+        _ => inline,
+    }
+}
+
+#[test]
+fn structs_walk_their_fields_in_order() {
+    let named = Named { title: vec![str("a"), str("b")], opaque: Opaque(1), note: Some(vec![str("c")]) };
+    assert_eq!(texts(&named), ["a", "b", "c"]);
+
+    let walked = named.clone().walk(&mut upper);
+    assert_eq!(texts(&walked), ["A", "B", "C"]);
+    assert_eq!(walked.opaque, Opaque(1));
+
+    let doubled = named.clone().walk(&mut |inline: Inline| vec![inline.clone(), inline]);
+    assert_eq!(texts(&doubled), ["a", "a", "b", "b", "c", "c"]);
+
+    let mut mutated = named;
+    mutated.walk_mut(&mut |inline: &mut Inline| -> Splice<Inline> {
+        match inline {
+            Inline::Str(text) if text == "b" => Some(vec![]),
+            _ => None,
+        }
+    });
+    assert_eq!(texts(&mutated), ["a", "c"]);
+}
+
+#[test]
+fn enums_walk_the_fields_of_their_variant() {
+    let shapes = vec![
+        Shape::Tuple(Opaque(1), vec![str("a")]),
+        Shape::Named { inlines: vec![str("b")], opaque: Opaque(2) },
+        Shape::Unit,
+    ];
+    assert_eq!(shapes.iter().flat_map(texts).collect::<Vec<_>>(), ["a", "b"]);
+    let walked: Vec<Shape> = shapes.into_iter().map(|it| it.walk(&mut upper)).collect();
+    assert_eq!(walked.iter().flat_map(texts).collect::<Vec<_>>(), ["A", "B"]);
+    assert!(matches!(&walked[1], Shape::Named { opaque, .. } if *opaque == Opaque(2)));
+
+    let mut shape = Shape::Tuple(Opaque(1), vec![str("a")]);
+    shape.walk_mut(&mut |inline: &mut Inline| *inline = str("z"));
+    assert_eq!(texts(&shape), ["z"]);
+}
+
+#[test]
+fn generic_fields_are_bounded_by_the_traversal() {
+    let pair = Generic(vec![str("a")], vec![str("b")]);
+    assert_eq!(texts(&pair), ["a", "b"]);
+    assert_eq!(texts(&pair.walk(&mut upper)), ["A", "B"]);
+}
+
+#[test]
+fn derived_types_are_walked_in_lists() {
+    let named = |text: &str| Named { title: vec![str(text)], opaque: Opaque(0), note: None };
+    let nested = Nested {
+        named: vec![named("a"), named("b")],
+        shapes: Some(vec![vec![Shape::Tuple(Opaque(1), vec![str("c")])], vec![Shape::Unit, Shape::Named { inlines: vec![str("d")], opaque: Opaque(2) }]]),
+    };
+    assert_eq!(texts(&nested), ["a", "b", "c", "d"]);
+    assert_eq!(texts(&nested.clone().walk(&mut upper)), ["A", "B", "C", "D"]);
+    assert_eq!(texts(&nested.clone().walk(&mut |inline: Inline| vec![inline.clone(), inline])), ["a", "a", "b", "b", "c", "c", "d", "d"]);
+
+    let mut mutated = nested;
+    mutated.walk_mut(&mut |inline: &mut Inline| -> Splice<Inline> {
+        match inline {
+            Inline::Str(text) if text == "c" => Some(vec![]),
+            _ => None,
+        }
+    });
+    assert_eq!(texts(&mutated), ["a", "b", "d"]);
+}
+
+#[test]
+fn the_crate_can_be_renamed() {
+    let mut renamed = renamed::Renamed(vec![str("a")]);
+    renamed.walk_mut(&mut |inline: &mut Inline| *inline = str("b"));
+    assert_eq!(texts(&renamed), ["b"]);
+    assert_eq!(texts(&renamed.walk(&mut upper)), ["B"]);
+}
+
+#[test]
+fn ui() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,7 @@
+use pandoc::walkable::Walkable;
+
+#[derive(Walkable)]
+#[walkable(crate = pandoc)]
+struct Unquoted(Vec<pandoc::definition::Inline>);
+
+fn main() {}
@@ -0,0 +1,11 @@
+use pandoc::queryable::Queryable;
+use pandoc::walkable::Walkable;
+use pandoc::walkable_mut::WalkableMut;
+
+#[derive(Walkable, WalkableMut, Queryable)]
+union Number {
+    integer: u32,
+    float: f32,
+}
+
+fn main() {}
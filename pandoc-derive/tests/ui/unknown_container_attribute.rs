@@ -0,0 +1,7 @@
+use pandoc::walkable::Walkable;
+
+#[derive(Walkable)]
+#[walkable(rename = "other")]
+struct Renamed(Vec<pandoc::definition::Inline>);
+
+fn main() {}
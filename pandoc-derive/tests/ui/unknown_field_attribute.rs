@@ -0,0 +1,6 @@
+use pandoc::walkable::Walkable;
+
+#[derive(Walkable)]
+struct Flattened(#[walkable(flatten)] Vec<pandoc::definition::Inline>);
+
+fn main() {}
@@ -0,0 +1,9 @@
+use pandoc::definition::Inline;
+use pandoc::walkable::Walkable;
+
+#[derive(Walkable)]
+struct Counted(Vec<Inline>, u32);
+
+fn main() {
+    Counted(vec![], 0).walk(&mut |inline: Inline| inline);
+}
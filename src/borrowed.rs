@@ -0,0 +1,512 @@
+//! Documents borrowing their text from the JSON they are parsed from.
+//!
+//! The types of [`definition`](crate::definition) own their strings, so reading a document copies
+//! all of its text. The types of this module mirror them with [`Text`] in place of `String`: a
+//! string without escape sequences borrows from the JSON, and only the elements a filter builds
+//! or changes own their text. They serialize to the same JSON, and convert to the owned types
+//! with `From`.
+//!
+//! ```
+//! use pandoc::borrowed::{Inline, Pandoc, Text};
+//! use pandoc::walkable::Walkable;
+//!
+//! let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[{"t":"Para","c":[{"t":"Str","c":"hello"}]}]}"#;
+//! let pandoc = Pandoc::from_json(json)?.walk(&mut |inline: Inline| match inline {
+//!     Inline::Str(text) => Inline::Str(Text::from(text.to_uppercase())),
+//!     _ => inline,
+//! });
+//! assert_eq!(serde_json::to_string(&pandoc)?, json.replace("hello", "HELLO"));
+//! # Ok::<_, pandoc::Error>(())
+//! ```
+
+use std::borrow::{Borrow, Cow};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Deref;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Visitor;
+use crate::definition::{self, Alignment, CitationMode, ColSpec, ListAttributes, MathType, QuoteType, RowHeadColumns, RowSpan, ColSpan};
+use crate::traversal::Walk;
+use crate::walkable::{walk_across, walk_leaf, walk_node, walk_splice, Walkable};
+
+/// A string of the document, borrowed from the JSON unless it had to be unescaped or was built
+/// by the filter.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Text<'a>(pub Cow<'a, str>);
+
+impl<'a> Text<'a> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_owned(self) -> String {
+        self.0.into_owned()
+    }
+}
+
+impl Deref for Text<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Text<'_> {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a str> for Text<'a> {
+    fn from(text: &'a str) -> Self {
+        Text(Cow::Borrowed(text))
+    }
+}
+
+impl From<String> for Text<'_> {
+    fn from(text: String) -> Self {
+        Text(Cow::Owned(text))
+    }
+}
+
+impl<'a> From<Text<'a>> for String {
+    fn from(text: Text<'a>) -> Self {
+        text.into_owned()
+    }
+}
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Text<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Text<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        struct TextVisitor;
+
+        impl<'de> Visitor<'de> for TextVisitor {
+            type Value = Text<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, text: &'de str) -> Result<Self::Value, E> where E: serde::de::Error {
+                Ok(Text::from(text))
+            }
+
+            fn visit_str<E>(self, text: &str) -> Result<Self::Value, E> where E: serde::de::Error {
+                Ok(Text::from(text.to_string()))
+            }
+
+            fn visit_string<E>(self, text: String) -> Result<Self::Value, E> where E: serde::de::Error {
+                Ok(Text::from(text))
+            }
+        }
+
+        deserializer.deserialize_str(TextVisitor)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub struct Pandoc<'a> {
+    #[serde(rename = "pandoc-api-version")]
+    #[walkable(skip)]
+    pub version: Vec<u32>,
+    pub meta: Meta<'a>,
+    pub blocks: Vec<Block<'a>>,
+}
+
+impl<'a> Pandoc<'a> {
+    /// Parses a document of the [`API_VERSION`](crate::compat::API_VERSION). It borrows from
+    /// `json`, so older documents are not upgraded here: pass them through
+    /// [`compat::upgrade_json`](crate::compat::upgrade_json) first.
+    pub fn from_json(json: &'a str) -> crate::Result<Self> {
+        // Tracking the path slows the parsing down, so it is only done to report an error.
+        serde_json::from_str(json).or_else(|_| Ok(serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(json))?))
+    }
+}
+
+pub type Meta<'a> = BTreeMap<Text<'a>, MetaValue<'a>>;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(tag = "t", content = "c", bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub enum MetaValue<'a> {
+    MetaMap(Meta<'a>),
+    MetaList(Vec<MetaValue<'a>>),
+    MetaBool(#[walkable(skip)] bool),
+    MetaString(#[walkable(skip)] Text<'a>),
+    MetaInlines(Vec<Inline<'a>>),
+    MetaBlocks(Vec<Block<'a>>),
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(tag = "t", content = "c", bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub enum Block<'a> {
+    Plain(Vec<Inline<'a>>),
+    Para(Vec<Inline<'a>>),
+    LineBlock(Vec<Vec<Inline<'a>>>),
+    CodeBlock(Attr<'a>, #[walkable(skip)] Text<'a>),
+    RawBlock(#[walkable(skip)] Format<'a>, #[walkable(skip)] Text<'a>),
+    BlockQuote(Vec<Block<'a>>),
+    OrderedList(#[walkable(skip)] ListAttributes, Vec<Vec<Block<'a>>>),
+    BulletList(Vec<Vec<Block<'a>>>),
+    DefinitionList(Vec<Definition<'a>>),
+    Header(#[walkable(skip)] u8, Attr<'a>, Vec<Inline<'a>>),
+    HorizontalRule,
+    Table(Attr<'a>, Caption<'a>, #[walkable(skip)] Vec<ColSpec>, TableHead<'a>, Vec<TableBody<'a>>, TableFoot<'a>),
+    Figure(Attr<'a>, Caption<'a>, Vec<Block<'a>>),
+    Div(Attr<'a>, Vec<Block<'a>>)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate")]
+pub struct Definition<'a>(pub Vec<Inline<'a>>, pub Vec<Vec<Block<'a>>>);
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(tag = "t", content = "c", bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub enum Inline<'a> {
+    Str(#[walkable(skip)] Text<'a>),
+    Emph(Vec<Inline<'a>>),
+    Underline(Vec<Inline<'a>>),
+    Strong(Vec<Inline<'a>>),
+    Strikeout(Vec<Inline<'a>>),
+    Superscript(Vec<Inline<'a>>),
+    Subscript(Vec<Inline<'a>>),
+    SmallCaps(Vec<Inline<'a>>),
+    Quoted(#[walkable(skip)] QuoteType, Vec<Inline<'a>>),
+    Cite(Vec<Citation<'a>>, Vec<Inline<'a>>),
+    Code(Attr<'a>, #[walkable(skip)] Text<'a>),
+    Space,
+    SoftBreak,
+    LineBreak,
+    Math(#[walkable(skip)] MathType, #[walkable(skip)] Text<'a>),
+    RawInline(#[walkable(skip)] Format<'a>, #[walkable(skip)] Text<'a>),
+    Link(Attr<'a>, Vec<Inline<'a>>, Target<'a>),
+    Image(Attr<'a>, Vec<Inline<'a>>, Target<'a>),
+    Note(Vec<Block<'a>>),
+    Span(Attr<'a>, Vec<Inline<'a>>),
+}
+
+/// Serialized as the `[id, classes, attributes]` triple of pandoc.
+#[derive(Clone, Debug, Default)]
+pub struct Attr<'a> {
+    pub id: Text<'a>,
+    pub classes: Vec<Text<'a>>,
+    pub attributes: Vec<(Text<'a>, Text<'a>)>,
+}
+
+impl Serialize for Attr<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        (&self.id, &self.classes, &self.attributes).serialize(serializer)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Attr<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let (id, classes, attributes) = Deserialize::deserialize(deserializer)?;
+        Ok(Attr { id, classes, attributes })
+    }
+}
+
+pub type Target<'a> = (Text<'a>, Text<'a>);
+
+pub type Format<'a> = Text<'a>;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub struct Caption<'a>(pub Option<Vec<Inline<'a>>>, pub Vec<Block<'a>>);
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub struct Citation<'a> {
+    #[serde(rename = "citationId")]
+    #[walkable(skip)]
+    pub citation_id: Text<'a>,
+    #[serde(rename = "citationPrefix")]
+    pub citation_prefix: Vec<Inline<'a>>,
+    #[serde(rename = "citationSuffix")]
+    pub citation_suffix: Vec<Inline<'a>>,
+    #[serde(rename = "citationMode")]
+    #[walkable(skip)]
+    pub citation_mode: CitationMode,
+    #[serde(rename = "citationNoteNum")]
+    #[walkable(skip)]
+    pub citation_note_num: u64,
+    #[serde(rename = "citationHash")]
+    #[walkable(skip)]
+    pub citation_hash: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate")]
+pub struct TableHead<'a>(pub Attr<'a>, pub Vec<Row<'a>>);
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub struct Row<'a>(pub Attr<'a>, pub Vec<Cell<'a>>);
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate", node)]
+pub struct Cell<'a>(pub Attr<'a>, #[walkable(skip)] pub Alignment, #[walkable(skip)] pub RowSpan, #[walkable(skip)] pub ColSpan, pub Vec<Block<'a>>);
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate")]
+pub struct TableBody<'a>(pub Attr<'a>, #[walkable(skip)] pub RowHeadColumns, pub Vec<Row<'a>>, pub Vec<Row<'a>>);
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable)]
+#[serde(bound(deserialize = "'de: 'a"))]
+#[walkable(crate = "crate")]
+pub struct TableFoot<'a>(pub Attr<'a>, pub Vec<Row<'a>>);
+
+// Traversal, with the callbacks of the owned types.
+
+type Inlines<'a> = Vec<Inline<'a>>;
+type Blocks<'a> = Vec<Block<'a>>;
+
+walk_leaf!(['a] Attr<'a>, Target<'a>);
+walk_node!(['a] Block<'a>, Inline<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_splice!(['a] Block<'a>, Inline<'a>);
+
+walk_across!(['a] Inline<'a>, Inline<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Inline<'a>, Inlines<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Block<'a>, Block<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Inline<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Block<'a>, Blocks<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Inline<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Inline<'a>, Walk<Inlines<'a>>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Block<'a>, Walk<Blocks<'a>>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Inline<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Attr<'a>, Attr<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Inline<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Target<'a>, Target<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Inline<'a>, Attr<'a>, Caption<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Caption<'a>, Caption<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Inline<'a>, Attr<'a>, Target<'a>, Row<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Row<'a>, Row<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Inline<'a>, Attr<'a>, Target<'a>, Caption<'a>, Cell<'a>, Citation<'a>);
+walk_across!(['a] Cell<'a>, Cell<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Inline<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Citation<'a>);
+walk_across!(['a] Citation<'a>, Citation<'a>; Pandoc<'a>, Meta<'a>, MetaValue<'a>, Block<'a>, Inline<'a>, Attr<'a>, Target<'a>, Caption<'a>, Row<'a>, Cell<'a>);
+
+// Conversions into the owned types.
+
+impl<'a> From<Pandoc<'a>> for definition::Pandoc {
+    fn from(pandoc: Pandoc<'a>) -> Self {
+        definition::Pandoc { version: pandoc.version, meta: meta(pandoc.meta), blocks: owned(pandoc.blocks) }
+    }
+}
+
+impl<'a> From<MetaValue<'a>> for definition::MetaValue {
+    fn from(value: MetaValue<'a>) -> Self {
+        use definition::MetaValue::*;
+        match value {
+            MetaValue::MetaMap(map) => MetaMap(meta(map)),
+            MetaValue::MetaList(values) => MetaList(owned(values)),
+            MetaValue::MetaBool(value) => MetaBool(value),
+            MetaValue::MetaString(text) => MetaString(text.into()),
+            MetaValue::MetaInlines(ils) => MetaInlines(owned(ils)),
+            MetaValue::MetaBlocks(blks) => MetaBlocks(owned(blks)),
+        }
+    }
+}
+
+impl<'a> From<Block<'a>> for definition::Block {
+    fn from(block: Block<'a>) -> Self {
+        use definition::Block::*;
+        match block {
+            Block::Plain(ils) => Plain(owned(ils)),
+            Block::Para(ils) => Para(owned(ils)),
+            Block::LineBlock(ilss) => LineBlock(ilss.into_iter().map(owned).collect()),
+            Block::CodeBlock(attr, code) => CodeBlock(attr.into(), code.into()),
+            Block::RawBlock(format, text) => RawBlock(format.into(), text.into()),
+            Block::BlockQuote(blks) => BlockQuote(owned(blks)),
+            Block::OrderedList(list_attributes, blkss) => OrderedList(list_attributes, blkss.into_iter().map(owned).collect()),
+            Block::BulletList(blkss) => BulletList(blkss.into_iter().map(owned).collect()),
+            Block::DefinitionList(definitions) => DefinitionList(owned(definitions)),
+            Block::Header(lvl, attr, ils) => Header(lvl, attr.into(), owned(ils)),
+            Block::HorizontalRule => HorizontalRule,
+            Block::Table(attr, caption, specs, t_head, t_bodies, t_foot) => {
+                Table(attr.into(), caption.into(), specs, t_head.into(), owned(t_bodies), t_foot.into())
+            },
+            Block::Figure(attr, caption, blks) => Figure(attr.into(), caption.into(), owned(blks)),
+            Block::Div(attr, blks) => Div(attr.into(), owned(blks)),
+        }
+    }
+}
+
+impl<'a> From<Definition<'a>> for definition::Definition {
+    fn from(definition: Definition<'a>) -> Self {
+        definition::Definition(owned(definition.0), definition.1.into_iter().map(owned).collect())
+    }
+}
+
+impl<'a> From<Inline<'a>> for definition::Inline {
+    fn from(inline: Inline<'a>) -> Self {
+        use definition::Inline::*;
+        match inline {
+            Inline::Str(text) => Str(text.into()),
+            Inline::Emph(ils) => Emph(owned(ils)),
+            Inline::Underline(ils) => Underline(owned(ils)),
+            Inline::Strong(ils) => Strong(owned(ils)),
+            Inline::Strikeout(ils) => Strikeout(owned(ils)),
+            Inline::Superscript(ils) => Superscript(owned(ils)),
+            Inline::Subscript(ils) => Subscript(owned(ils)),
+            Inline::SmallCaps(ils) => SmallCaps(owned(ils)),
+            Inline::Quoted(t, ils) => Quoted(t, owned(ils)),
+            Inline::Cite(citations, ils) => Cite(owned(citations), owned(ils)),
+            Inline::Code(attr, code) => Code(attr.into(), code.into()),
+            Inline::Space => Space,
+            Inline::SoftBreak => SoftBreak,
+            Inline::LineBreak => LineBreak,
+            Inline::Math(t, math) => Math(t, math.into()),
+            Inline::RawInline(format, text) => RawInline(format.into(), text.into()),
+            Inline::Link(attr, ils, target) => Link(attr.into(), owned(ils), (target.0.into(), target.1.into())),
+            Inline::Image(attr, ils, target) => Image(attr.into(), owned(ils), (target.0.into(), target.1.into())),
+            Inline::Note(blks) => Note(owned(blks)),
+            Inline::Span(attr, ils) => Span(attr.into(), owned(ils)),
+        }
+    }
+}
+
+impl<'a> From<Attr<'a>> for definition::Attr {
+    fn from(attr: Attr<'a>) -> Self {
+        definition::Attr {
+            id: attr.id.into(),
+            classes: owned(attr.classes),
+            attributes: attr.attributes.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+        }
+    }
+}
+
+impl<'a> From<Caption<'a>> for definition::Caption {
+    fn from(caption: Caption<'a>) -> Self {
+        definition::Caption(caption.0.map(owned), owned(caption.1))
+    }
+}
+
+impl<'a> From<Citation<'a>> for definition::Citation {
+    fn from(citation: Citation<'a>) -> Self {
+        definition::Citation {
+            citation_id: citation.citation_id.into(),
+            citation_prefix: owned(citation.citation_prefix),
+            citation_suffix: owned(citation.citation_suffix),
+            citation_mode: citation.citation_mode,
+            citation_note_num: citation.citation_note_num,
+            citation_hash: citation.citation_hash,
+        }
+    }
+}
+
+impl<'a> From<TableHead<'a>> for definition::TableHead {
+    fn from(t_head: TableHead<'a>) -> Self {
+        definition::TableHead(t_head.0.into(), owned(t_head.1))
+    }
+}
+
+impl<'a> From<Row<'a>> for definition::Row {
+    fn from(row: Row<'a>) -> Self {
+        definition::Row(row.0.into(), owned(row.1))
+    }
+}
+
+impl<'a> From<Cell<'a>> for definition::Cell {
+    fn from(cell: Cell<'a>) -> Self {
+        definition::Cell(cell.0.into(), cell.1, cell.2, cell.3, owned(cell.4))
+    }
+}
+
+impl<'a> From<TableBody<'a>> for definition::TableBody {
+    fn from(t_body: TableBody<'a>) -> Self {
+        definition::TableBody(t_body.0.into(), t_body.1, owned(t_body.2), owned(t_body.3))
+    }
+}
+
+impl<'a> From<TableFoot<'a>> for definition::TableFoot {
+    fn from(t_foot: TableFoot<'a>) -> Self {
+        definition::TableFoot(t_foot.0.into(), owned(t_foot.1))
+    }
+}
+
+fn owned<B, O>(values: Vec<B>) -> Vec<O> where O: From<B> {
+    values.into_iter().map(O::from).collect()
+}
+
+fn meta(meta: Meta) -> definition::Meta {
+    meta.into_iter().map(|(k, v)| (k.into(), v.into())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition;
+    use crate::walkable::tests::{block_name, document, inline_name, names, other_targets, samples};
+    use crate::walkable::Walkable;
+    use super::{Attr, Block, Caption, Cell, Citation, Inline, Pandoc, Row, Target, Text};
+
+    type Blocks<'a> = Vec<Block<'a>>;
+
+    fn visits<'a, T>(blocks: &Blocks<'a>) -> usize where Blocks<'a>: Walkable<T> {
+        let mut visits = 0;
+        blocks.clone().walk(&mut |it: T| {
+            visits += 1;
+            it
+        });
+        visits
+    }
+
+    #[test]
+    fn every_variant_is_walked_like_the_owned_one() {
+        for (name, sample) in samples() {
+            let json = serde_json::to_string(&sample).unwrap();
+            let blocks: Blocks = serde_json::from_str(&json).unwrap();
+
+            let mut inlines = Vec::new();
+            let walked = blocks.clone().walk(&mut |inline: Inline| {
+                inlines.push(inline_name(&inline.clone().into()));
+                match inline {
+                    Inline::Str(text) => vec![Inline::Str(Text::from(text.to_uppercase()))],
+                    _ => vec![inline],
+                }
+            });
+            assert_eq!(inlines, names(&sample, true, true), "{}", name);
+            assert_eq!(serde_json::to_string(&walked).unwrap(), json.replace(r#""c":"x""#, r#""c":"X""#).replace(r#""c":"y""#, r#""c":"Y""#).replace(r#""c":"str""#, r#""c":"STR""#), "{}", name);
+
+            let mut blocks_visited = Vec::new();
+            blocks.clone().walk(&mut |block: Block| {
+                blocks_visited.push(block_name(&block.clone().into()));
+                block
+            });
+            assert_eq!(blocks_visited, names(&sample, false, true), "{}", name);
+
+            let visited = [visits::<Attr>(&blocks), visits::<Target>(&blocks), visits::<Caption>(&blocks), visits::<Row>(&blocks), visits::<Cell>(&blocks), visits::<Citation>(&blocks)];
+            assert_eq!(visited, other_targets(&sample), "{}", name);
+        }
+    }
+
+    #[test]
+    fn link_targets_are_walked() {
+        let json = serde_json::to_string(&document()).unwrap();
+        let pandoc = Pandoc::from_json(&json).unwrap().walk(&mut |(url, title): Target| (Text::from(url.replace("url", "https://example.com")), title));
+        let pandoc = definition::Pandoc::from(pandoc);
+        let mut urls = Vec::new();
+        pandoc.walk(&mut |target: definition::Target| {
+            urls.push(target.0.clone());
+            target
+        });
+        assert!(!urls.is_empty());
+        assert!(urls.iter().all(|it| it == "https://example.com"));
+    }
+}
@@ -0,0 +1,750 @@
+//! Concise construction of documents, in the manner of pandoc's `Text.Pandoc.Builder`.
+//!
+//! [`Inlines`] and [`Blocks`] are sequences joined with `+`; the functions take anything
+//! convertible into them, text being split into words.
+//!
+//! ```
+//! use pandoc::attr;
+//! use pandoc::builder::{doc, emph, header_with, para, text};
+//!
+//! let pandoc = doc(
+//!     header_with(attr!("intro"; "unnumbered"), 2, "Introduction")
+//!     + para(text("Some ") + emph("emphasized") + " text."),
+//! );
+//! assert_eq!(pandoc::writers::markdown::write_blocks(&pandoc.blocks), "## Introduction {#intro .unnumbered}\n\nSome *emphasized* text.");
+//! ```
+
+use std::iter::FromIterator;
+use std::ops::{Add, AddAssign};
+use crate::compat::API_VERSION;
+use crate::definition::{Alignment, Attr, Block, Caption, Cell, Citation, ColSpec, ColWidth, Definition, Format, Inline, ListAttributes, ListNumberDelim, ListNumberStyle, MathType, Meta, MetaValue, Pandoc, QuoteType, Row, RowHeadColumns, TableBody, TableFoot, TableHead};
+
+/// Builds an [`Attr`]: `attr!("id")`, `attr!("id"; "class", ...)` or
+/// `attr!("id"; "class", ...; "key" => "value", ...)`, an empty identifier omitting it.
+#[macro_export]
+macro_rules! attr {
+    () => {
+        $crate::definition::Attr::default()
+    };
+    ($id:expr) => {
+        $crate::definition::Attr { id: ($id).into(), classes: Vec::new(), attributes: Vec::new() }
+    };
+    ($id:expr; $($class:expr),* $(,)?) => {
+        $crate::definition::Attr { id: ($id).into(), classes: vec![$(($class).into()),*], attributes: Vec::new() }
+    };
+    ($id:expr; $($class:expr),*; $($key:expr => $value:expr),* $(,)?) => {
+        $crate::definition::Attr { id: ($id).into(), classes: vec![$(($class).into()),*], attributes: vec![$((($key).into(), ($value).into())),*] }
+    };
+}
+
+/// A sequence of inlines; joining two merges the `Str` and the `Space` at the junction.
+#[derive(Clone, Debug, Default)]
+pub struct Inlines(pub Vec<Inline>);
+
+/// A sequence of blocks.
+#[derive(Clone, Debug, Default)]
+pub struct Blocks(pub Vec<Block>);
+
+impl Inlines {
+    pub fn new() -> Self {
+        Inlines::default()
+    }
+
+    pub fn push(&mut self, inline: Inline) {
+        match (self.0.last_mut(), inline) {
+            (Some(Inline::Str(last)), Inline::Str(text)) => last.push_str(&text),
+            (Some(Inline::Space), Inline::Space) => {},
+            (Some(last @ Inline::Space), Inline::SoftBreak) => *last = Inline::SoftBreak,
+            (Some(Inline::SoftBreak), Inline::Space) => {},
+            (_, inline) => self.0.push(inline),
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<Inline> {
+        self.0
+    }
+}
+
+impl Blocks {
+    pub fn new() -> Self {
+        Blocks::default()
+    }
+
+    pub fn push(&mut self, block: Block) {
+        self.0.push(block);
+    }
+
+    pub fn into_vec(self) -> Vec<Block> {
+        self.0
+    }
+}
+
+impl From<Inline> for Inlines {
+    fn from(inline: Inline) -> Self {
+        Inlines(vec![inline])
+    }
+}
+
+impl From<Vec<Inline>> for Inlines {
+    fn from(inlines: Vec<Inline>) -> Self {
+        Inlines(inlines)
+    }
+}
+
+impl From<&str> for Inlines {
+    fn from(value: &str) -> Self {
+        text(value)
+    }
+}
+
+impl From<String> for Inlines {
+    fn from(value: String) -> Self {
+        text(&value)
+    }
+}
+
+impl From<Inlines> for Vec<Inline> {
+    fn from(inlines: Inlines) -> Self {
+        inlines.0
+    }
+}
+
+impl From<Inlines> for MetaValue {
+    fn from(inlines: Inlines) -> Self {
+        MetaValue::MetaInlines(inlines.0)
+    }
+}
+
+impl From<Block> for Blocks {
+    fn from(block: Block) -> Self {
+        Blocks(vec![block])
+    }
+}
+
+impl From<Vec<Block>> for Blocks {
+    fn from(blocks: Vec<Block>) -> Self {
+        Blocks(blocks)
+    }
+}
+
+/// Inlines become a paragraph.
+impl From<Inlines> for Blocks {
+    fn from(inlines: Inlines) -> Self {
+        para(inlines)
+    }
+}
+
+impl From<&str> for Blocks {
+    fn from(value: &str) -> Self {
+        para(value)
+    }
+}
+
+impl From<Blocks> for Vec<Block> {
+    fn from(blocks: Blocks) -> Self {
+        blocks.0
+    }
+}
+
+impl From<Blocks> for MetaValue {
+    fn from(blocks: Blocks) -> Self {
+        MetaValue::MetaBlocks(blocks.0)
+    }
+}
+
+impl Extend<Inline> for Inlines {
+    fn extend<T>(&mut self, inlines: T) where T: IntoIterator<Item = Inline> {
+        inlines.into_iter().for_each(|it| self.push(it));
+    }
+}
+
+impl Extend<Inlines> for Inlines {
+    fn extend<T>(&mut self, inlines: T) where T: IntoIterator<Item = Inlines> {
+        inlines.into_iter().for_each(|it| self.extend(it.0));
+    }
+}
+
+impl Extend<Block> for Blocks {
+    fn extend<T>(&mut self, blocks: T) where T: IntoIterator<Item = Block> {
+        self.0.extend(blocks);
+    }
+}
+
+impl Extend<Blocks> for Blocks {
+    fn extend<T>(&mut self, blocks: T) where T: IntoIterator<Item = Blocks> {
+        blocks.into_iter().for_each(|it| self.0.extend(it.0));
+    }
+}
+
+impl FromIterator<Inline> for Inlines {
+    fn from_iter<T>(inlines: T) -> Self where T: IntoIterator<Item = Inline> {
+        let mut result = Inlines::new();
+        result.extend(inlines);
+        result
+    }
+}
+
+impl FromIterator<Inlines> for Inlines {
+    fn from_iter<T>(inlines: T) -> Self where T: IntoIterator<Item = Inlines> {
+        let mut result = Inlines::new();
+        result.extend(inlines);
+        result
+    }
+}
+
+impl FromIterator<Block> for Blocks {
+    fn from_iter<T>(blocks: T) -> Self where T: IntoIterator<Item = Block> {
+        Blocks(blocks.into_iter().collect())
+    }
+}
+
+impl FromIterator<Blocks> for Blocks {
+    fn from_iter<T>(blocks: T) -> Self where T: IntoIterator<Item = Blocks> {
+        Blocks(blocks.into_iter().flat_map(|it| it.0).collect())
+    }
+}
+
+impl IntoIterator for Inlines {
+    type Item = Inline;
+    type IntoIter = std::vec::IntoIter<Inline>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl IntoIterator for Blocks {
+    type Item = Block;
+    type IntoIter = std::vec::IntoIter<Block>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<T> Add<T> for Inlines where T: Into<Inlines> {
+    type Output = Inlines;
+
+    fn add(mut self, other: T) -> Inlines {
+        self += other;
+        self
+    }
+}
+
+impl<T> AddAssign<T> for Inlines where T: Into<Inlines> {
+    fn add_assign(&mut self, other: T) {
+        self.extend(other.into().0);
+    }
+}
+
+impl<T> Add<T> for Blocks where T: Into<Blocks> {
+    type Output = Blocks;
+
+    fn add(mut self, other: T) -> Blocks {
+        self += other;
+        self
+    }
+}
+
+impl<T> AddAssign<T> for Blocks where T: Into<Blocks> {
+    fn add_assign(&mut self, other: T) {
+        self.0.extend(other.into().0);
+    }
+}
+
+/// A document with the current API version and no metadata.
+pub fn doc<B>(blocks: B) -> Pandoc where B: Into<Blocks> {
+    Pandoc { version: API_VERSION.to_vec(), meta: Meta::new(), blocks: blocks.into().0 }
+}
+
+/// Splits `text` into `Str`, `Space` and, at line breaks, `SoftBreak`.
+pub fn text(text: &str) -> Inlines {
+    let mut result = Inlines::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                result.push(Inline::Str(std::mem::take(&mut word)));
+            }
+            result.push(if c == '\n' { Inline::SoftBreak } else { Inline::Space });
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        result.push(Inline::Str(word));
+    }
+    result
+}
+
+/// A single `Str`, spaces included.
+pub fn str<S>(text: S) -> Inlines where S: Into<String> {
+    Inline::Str(text.into()).into()
+}
+
+pub fn emph<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Emph(inlines.into().0).into()
+}
+
+pub fn underline<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Underline(inlines.into().0).into()
+}
+
+pub fn strong<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Strong(inlines.into().0).into()
+}
+
+pub fn strikeout<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Strikeout(inlines.into().0).into()
+}
+
+pub fn superscript<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Superscript(inlines.into().0).into()
+}
+
+pub fn subscript<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Subscript(inlines.into().0).into()
+}
+
+pub fn small_caps<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::SmallCaps(inlines.into().0).into()
+}
+
+pub fn single_quoted<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Quoted(QuoteType::SingleQuote, inlines.into().0).into()
+}
+
+pub fn double_quoted<I>(inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Quoted(QuoteType::DoubleQuote, inlines.into().0).into()
+}
+
+pub fn cite<I>(citations: Vec<Citation>, inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Cite(citations, inlines.into().0).into()
+}
+
+pub fn code<S>(code: S) -> Inlines where S: Into<String> {
+    code_with(Attr::default(), code)
+}
+
+pub fn code_with<S>(attr: Attr, code: S) -> Inlines where S: Into<String> {
+    Inline::Code(attr, code.into()).into()
+}
+
+pub fn space() -> Inlines {
+    Inline::Space.into()
+}
+
+pub fn soft_break() -> Inlines {
+    Inline::SoftBreak.into()
+}
+
+pub fn line_break() -> Inlines {
+    Inline::LineBreak.into()
+}
+
+pub fn math<S>(tex: S) -> Inlines where S: Into<String> {
+    Inline::Math(MathType::InlineMath, tex.into()).into()
+}
+
+pub fn display_math<S>(tex: S) -> Inlines where S: Into<String> {
+    Inline::Math(MathType::DisplayMath, tex.into()).into()
+}
+
+pub fn raw_inline<F, S>(format: F, content: S) -> Inlines where F: Into<Format>, S: Into<String> {
+    Inline::RawInline(format.into(), content.into()).into()
+}
+
+pub fn link<U, I>(url: U, inlines: I) -> Inlines where U: Into<String>, I: Into<Inlines> {
+    link_with(Attr::default(), url, "", inlines)
+}
+
+pub fn link_with<U, T, I>(attr: Attr, url: U, title: T, inlines: I) -> Inlines where U: Into<String>, T: Into<String>, I: Into<Inlines> {
+    Inline::Link(attr, inlines.into().0, (url.into(), title.into())).into()
+}
+
+pub fn image<U, I>(url: U, inlines: I) -> Inlines where U: Into<String>, I: Into<Inlines> {
+    image_with(Attr::default(), url, "", inlines)
+}
+
+pub fn image_with<U, T, I>(attr: Attr, url: U, title: T, inlines: I) -> Inlines where U: Into<String>, T: Into<String>, I: Into<Inlines> {
+    Inline::Image(attr, inlines.into().0, (url.into(), title.into())).into()
+}
+
+pub fn note<B>(blocks: B) -> Inlines where B: Into<Blocks> {
+    Inline::Note(blocks.into().0).into()
+}
+
+pub fn span<I>(attr: Attr, inlines: I) -> Inlines where I: Into<Inlines> {
+    Inline::Span(attr, inlines.into().0).into()
+}
+
+pub fn plain<I>(inlines: I) -> Blocks where I: Into<Inlines> {
+    Block::Plain(inlines.into().0).into()
+}
+
+pub fn para<I>(inlines: I) -> Blocks where I: Into<Inlines> {
+    Block::Para(inlines.into().0).into()
+}
+
+pub fn line_block<L, I>(lines: L) -> Blocks where L: IntoIterator<Item = I>, I: Into<Inlines> {
+    Block::LineBlock(lines.into_iter().map(|it| it.into().0).collect()).into()
+}
+
+pub fn code_block<S>(code: S) -> Blocks where S: Into<String> {
+    code_block_with(Attr::default(), code)
+}
+
+pub fn code_block_with<S>(attr: Attr, code: S) -> Blocks where S: Into<String> {
+    Block::CodeBlock(attr, code.into()).into()
+}
+
+pub fn raw_block<F, S>(format: F, content: S) -> Blocks where F: Into<Format>, S: Into<String> {
+    Block::RawBlock(format.into(), content.into()).into()
+}
+
+pub fn block_quote<B>(blocks: B) -> Blocks where B: Into<Blocks> {
+    Block::BlockQuote(blocks.into().0).into()
+}
+
+/// A list numbered `1.`, `2.`...
+pub fn ordered_list<L, B>(items: L) -> Blocks where L: IntoIterator<Item = B>, B: Into<Blocks> {
+    ordered_list_with((1, ListNumberStyle::Decimal, ListNumberDelim::Period), items)
+}
+
+pub fn ordered_list_with<L, B>(attributes: ListAttributes, items: L) -> Blocks where L: IntoIterator<Item = B>, B: Into<Blocks> {
+    Block::OrderedList(attributes, items.into_iter().map(|it| it.into().0).collect()).into()
+}
+
+pub fn bullet_list<L, B>(items: L) -> Blocks where L: IntoIterator<Item = B>, B: Into<Blocks> {
+    Block::BulletList(items.into_iter().map(|it| it.into().0).collect()).into()
+}
+
+/// A definition list from terms and their definitions.
+pub fn definition_list<L, I, D, B>(items: L) -> Blocks where L: IntoIterator<Item = (I, D)>, I: Into<Inlines>, D: IntoIterator<Item = B>, B: Into<Blocks> {
+    Block::DefinitionList(items.into_iter()
+        .map(|(term, definitions)| Definition(term.into().0, definitions.into_iter().map(|it| it.into().0).collect()))
+        .collect()).into()
+}
+
+pub fn header<I>(level: u8, inlines: I) -> Blocks where I: Into<Inlines> {
+    header_with(Attr::default(), level, inlines)
+}
+
+pub fn header_with<I>(attr: Attr, level: u8, inlines: I) -> Blocks where I: Into<Inlines> {
+    Block::Header(level, attr, inlines.into().0).into()
+}
+
+pub fn horizontal_rule() -> Blocks {
+    Block::HorizontalRule.into()
+}
+
+pub fn figure<B>(caption: Caption, blocks: B) -> Blocks where B: Into<Blocks> {
+    figure_with(Attr::default(), caption, blocks)
+}
+
+pub fn figure_with<B>(attr: Attr, caption: Caption, blocks: B) -> Blocks where B: Into<Blocks> {
+    Block::Figure(attr, caption, blocks.into().0).into()
+}
+
+pub fn div<B>(attr: Attr, blocks: B) -> Blocks where B: Into<Blocks> {
+    Block::Div(attr, blocks.into().0).into()
+}
+
+pub fn caption<B>(blocks: B) -> Caption where B: Into<Blocks> {
+    Caption(None, blocks.into().0)
+}
+
+/// A caption made of a single `Plain`.
+pub fn simple_caption<I>(inlines: I) -> Caption where I: Into<Inlines> {
+    caption(plain(inlines))
+}
+
+pub fn empty_caption() -> Caption {
+    Caption(None, Vec::new())
+}
+
+/// A column of default width, or of `width`, a fraction of the text width.
+pub fn col_spec(alignment: Alignment, width: Option<f64>) -> ColSpec {
+    ColSpec(alignment, width.map_or(ColWidth::ColWidthDefault, ColWidth::ColWidth))
+}
+
+/// A cell spanning one row and one column, with the alignment of its column.
+pub fn cell<B>(blocks: B) -> Cell where B: Into<Blocks> {
+    Cell(Attr::default(), Alignment::AlignDefault, 1, 1, blocks.into().0)
+}
+
+/// A cell of inlines, wrapped in a `Plain` like the cells pandoc reads.
+pub fn simple_cell<I>(inlines: I) -> Cell where I: Into<Inlines> {
+    cell(plain(inlines))
+}
+
+impl Cell {
+    pub fn with_attr(mut self, attr: Attr) -> Self {
+        self.0 = attr;
+        self
+    }
+
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.1 = alignment;
+        self
+    }
+
+    pub fn with_span(mut self, rows: u32, columns: u32) -> Self {
+        self.2 = rows;
+        self.3 = columns;
+        self
+    }
+}
+
+pub fn row<C>(cells: C) -> Row where C: IntoIterator<Item = Cell> {
+    Row(Attr::default(), cells.into_iter().collect())
+}
+
+/// Table construction; the columns default to as many as the widest row, with default
+/// alignment and width.
+///
+/// ```
+/// use pandoc::builder::{row, simple_caption, simple_cell, TableBuilder};
+///
+/// let table = TableBuilder::new()
+///     .caption(simple_caption("Sizes"))
+///     .head(row(vec![simple_cell("Name"), simple_cell("Size")]))
+///     .row(row(vec![simple_cell("a"), simple_cell("1")]))
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct TableBuilder {
+    attr: Attr,
+    caption: Caption,
+    columns: Vec<ColSpec>,
+    head: TableHead,
+    bodies: Vec<TableBody>,
+    foot: TableFoot,
+}
+
+impl Default for TableBuilder {
+    fn default() -> Self {
+        TableBuilder {
+            attr: Attr::default(),
+            caption: empty_caption(),
+            columns: Vec::new(),
+            head: TableHead(Attr::default(), Vec::new()),
+            bodies: Vec::new(),
+            foot: TableFoot(Attr::default(), Vec::new()),
+        }
+    }
+}
+
+impl TableBuilder {
+    pub fn new() -> Self {
+        TableBuilder::default()
+    }
+
+    pub fn attr(mut self, attr: Attr) -> Self {
+        self.attr = attr;
+        self
+    }
+
+    pub fn caption(mut self, caption: Caption) -> Self {
+        self.caption = caption;
+        self
+    }
+
+    pub fn column(mut self, alignment: Alignment, width: Option<f64>) -> Self {
+        self.columns.push(col_spec(alignment, width));
+        self
+    }
+
+    pub fn columns<C>(mut self, columns: C) -> Self where C: IntoIterator<Item = ColSpec> {
+        self.columns.extend(columns);
+        self
+    }
+
+    /// Adds a row to the table head.
+    pub fn head(mut self, row: Row) -> Self {
+        self.head.1.push(row);
+        self
+    }
+
+    /// Adds a row to the last body, starting one if needed.
+    pub fn row(mut self, row: Row) -> Self {
+        match self.bodies.last_mut() {
+            Some(body) => body.3.push(row),
+            None => self.bodies.push(TableBody(Attr::default(), 0, Vec::new(), vec![row])),
+        }
+        self
+    }
+
+    pub fn rows<R>(self, rows: R) -> Self where R: IntoIterator<Item = Row> {
+        rows.into_iter().fold(self, TableBuilder::row)
+    }
+
+    /// Starts a body whose first `row_head_columns` columns are row headers.
+    pub fn body<R>(mut self, row_head_columns: RowHeadColumns, rows: R) -> Self where R: IntoIterator<Item = Row> {
+        self.bodies.push(TableBody(Attr::default(), row_head_columns, Vec::new(), rows.into_iter().collect()));
+        self
+    }
+
+    /// Adds a row to the table foot.
+    pub fn foot(mut self, row: Row) -> Self {
+        self.foot.1.push(row);
+        self
+    }
+
+    pub fn build(mut self) -> Blocks {
+        if self.columns.is_empty() {
+            let rows = self.head.1.iter()
+                .chain(self.bodies.iter().flat_map(|it| it.2.iter().chain(&it.3)))
+                .chain(&self.foot.1);
+            let width = rows.map(|Row(_, cells)| cells.iter().map(|it| it.3 as usize).sum()).max().unwrap_or_default();
+            self.columns = vec![col_spec(Alignment::AlignDefault, None); width];
+        }
+        Block::Table(self.attr, self.caption, self.columns, self.head, self.bodies, self.foot).into()
+    }
+}
+
+/// A table with a header row, if `headers` is not empty, and rows of cells of blocks.
+pub fn simple_table<H, B, R, C>(headers: H, rows: R) -> Blocks where H: IntoIterator<Item = B>, B: Into<Blocks>, R: IntoIterator<Item = C>, C: IntoIterator<Item = B> {
+    let headers: Vec<Cell> = headers.into_iter().map(cell).collect();
+    let mut table = TableBuilder::new();
+    if !headers.is_empty() {
+        table = table.head(row(headers));
+    }
+    table.rows(rows.into_iter().map(|it| row(it.into_iter().map(cell)))).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Alignment, Attr, Block, ColSpec, ColWidth, Inline, MetaValue, Row, TableBody};
+    use crate::definition::Inline::{SoftBreak, Space, Str};
+    use super::{bullet_list, cell, col_spec, doc, emph, header, para, plain, row, simple_cell, simple_table, space, str, text, Blocks, Inlines, TableBuilder};
+
+    fn json<T>(value: &T) -> serde_json::Value where T: serde::Serialize {
+        serde_json::to_value(value).unwrap()
+    }
+
+    fn word(text: &str) -> Inline {
+        Str(text.into())
+    }
+
+    #[test]
+    fn text_is_split_into_words() {
+        assert_eq!(json(&text("a b").0), json(&vec![word("a"), Space, word("b")]));
+        assert_eq!(json(&text("  a  \t b ").0), json(&vec![Space, word("a"), Space, word("b"), Space]));
+        assert_eq!(json(&text("a\nb").0), json(&vec![word("a"), SoftBreak, word("b")]));
+        assert_eq!(json(&text("a \n b").0), json(&vec![word("a"), SoftBreak, word("b")]));
+        assert_eq!(json(&text("").0), json(&Vec::<Inline>::new()));
+        assert_eq!(json(&str("a  b").0), json(&vec![word("a  b")]));
+    }
+
+    #[test]
+    fn inlines_merge_at_their_junction() {
+        let joined = text("a ") + " b" + str("c") + emph("d") + Inline::SoftBreak + space();
+        assert_eq!(json(&joined.0), json(&vec![word("a"), Space, word("bc"), Inline::Emph(vec![word("d")]), SoftBreak]));
+
+        let mut extended = Inlines::new();
+        extended.extend(vec![word("a"), word("b"), Space, Space]);
+        extended.extend(vec![text("c"), text(" d")]);
+        extended += "e";
+        assert_eq!(json(&extended.0), json(&vec![word("ab"), Space, word("c"), Space, word("de")]));
+
+        let collected: Inlines = vec![text("a"), space(), text("b")].into_iter().collect();
+        let vec: Vec<Inline> = collected.into();
+        assert_eq!(json(&vec), json(&vec![word("a"), Space, word("b")]));
+        assert_eq!(json(&Inlines::from(String::from("x y")).0), json(&text("x y").0));
+        assert_eq!(json(&Inlines::from(vec![word("x"), word("y")]).0), json(&vec![word("x"), word("y")]));
+        assert!(matches!(MetaValue::from(text("m")), MetaValue::MetaInlines(ils) if ils.len() == 1));
+    }
+
+    #[test]
+    fn blocks_are_joined_and_converted() {
+        let blocks = para("a") + header(1, "b") + "c" + text("d");
+        assert_eq!(json(&blocks.0), json(&vec![
+            Block::Para(vec![word("a")]),
+            Block::Header(1, Attr::default(), vec![word("b")]),
+            Block::Para(vec![word("c")]),
+            Block::Para(vec![word("d")]),
+        ]));
+
+        let mut extended = Blocks::new();
+        extended.extend(vec![Block::HorizontalRule]);
+        extended.extend(vec![plain("a"), plain("b")]);
+        extended += Block::HorizontalRule;
+        assert_eq!(extended.0.len(), 4);
+        let collected: Blocks = vec![para("a"), para("b")].into_iter().collect();
+        assert_eq!(Vec::<Block>::from(collected).len(), 2);
+        let collected: Blocks = vec![Block::HorizontalRule, Block::HorizontalRule].into_iter().collect();
+        assert_eq!(collected.0.len(), 2);
+        assert!(matches!(MetaValue::from(para("m")), MetaValue::MetaBlocks(blks) if blks.len() == 1));
+        assert_eq!(doc(bullet_list(vec!["a", "b"])).blocks.len(), 1);
+    }
+
+    #[test]
+    fn attr_takes_an_id_classes_and_attributes() {
+        let attr = |attr: Attr| (attr.id, attr.classes, attr.attributes);
+        assert_eq!(attr(crate::attr!()), attr(Attr::default()));
+        assert_eq!(attr(crate::attr!("id")), ("id".to_string(), vec![], vec![]));
+        assert_eq!(attr(crate::attr!("id"; "a", "b")), ("id".to_string(), vec!["a".to_string(), "b".to_string()], vec![]));
+        assert_eq!(attr(crate::attr!(""; "a"; "k" => "v", "l" => String::from("w"))), (String::new(), vec!["a".to_string()], vec![("k".to_string(), "v".to_string()), ("l".to_string(), "w".to_string())]));
+    }
+
+    fn spans(rows: &[Row]) -> Vec<Vec<(u32, u32)>> {
+        rows.iter().map(|Row(_, cells)| cells.iter().map(|it| (it.2, it.3)).collect()).collect()
+    }
+
+    #[test]
+    fn tables_count_their_columns_from_the_widest_row() {
+        let table = TableBuilder::new()
+            .head(row(vec![simple_cell("a").with_span(1, 2), simple_cell("b")]))
+            .row(row(vec![simple_cell("c")]))
+            .body(1, vec![row(vec![simple_cell("d").with_span(2, 1), simple_cell("e")]), row(vec![simple_cell("f")])])
+            .rows(vec![row(vec![simple_cell("g").with_span(1, 4)])])
+            .foot(row(vec![simple_cell("h").with_alignment(Alignment::AlignRight).with_attr(crate::attr!("foot"))]))
+            .build();
+        match table.0.as_slice() {
+            [Block::Table(_, _, columns, head, bodies, foot)] => {
+                assert_eq!(json(columns), json(&vec![ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault); 4]));
+                assert_eq!(spans(&head.1), [vec![(1, 2), (1, 1)]]);
+                assert_eq!(bodies.iter().map(|TableBody(_, heads, _, _)| *heads).collect::<Vec<_>>(), [0, 1]);
+                assert_eq!(spans(&bodies[0].3), [vec![(1, 1)]]);
+                assert_eq!(spans(&bodies[1].3), [vec![(2, 1), (1, 1)], vec![(1, 1)], vec![(1, 4)]]);
+                assert!(matches!(foot.1.as_slice(), [Row(_, cells)] if cells[0].0.id == "foot" && matches!(cells[0].1, Alignment::AlignRight)));
+            },
+            _ => panic!("expected a table"),
+        }
+    }
+
+    #[test]
+    fn tables_keep_their_given_columns() {
+        let table = TableBuilder::new()
+            .attr(crate::attr!("tbl"))
+            .column(Alignment::AlignLeft, Some(0.25))
+            .columns(vec![col_spec(Alignment::AlignCenter, None)])
+            .row(row(vec![cell("a"), cell("b"), cell("c")]))
+            .build();
+        match table.0.as_slice() {
+            [Block::Table(attr, _, columns, ..)] => {
+                assert_eq!(attr.id, "tbl");
+                assert_eq!(json(columns), json(&vec![ColSpec(Alignment::AlignLeft, ColWidth::ColWidth(0.25)), ColSpec(Alignment::AlignCenter, ColWidth::ColWidthDefault)]));
+            },
+            _ => panic!("expected a table"),
+        }
+    }
+
+    #[test]
+    fn simple_tables_have_a_head_when_given_headers() {
+        let table = simple_table(vec!["x", "y"], vec![vec!["1", "2"], vec!["3", "4"]]);
+        match table.0.as_slice() {
+            [Block::Table(_, _, columns, head, bodies, foot)] => {
+                assert_eq!(columns.len(), 2);
+                assert_eq!(spans(&head.1), [vec![(1, 1), (1, 1)]]);
+                assert_eq!(bodies.len(), 1);
+                assert_eq!(spans(&bodies[0].3).len(), 2);
+                assert!(foot.1.is_empty());
+            },
+            _ => panic!("expected a table"),
+        }
+        let headless = simple_table(Vec::<&str>::new(), vec![vec!["1"]]);
+        assert!(matches!(headless.0.as_slice(), [Block::Table(_, _, _, head, ..)] if head.1.is_empty()));
+    }
+}
@@ -0,0 +1,539 @@
+//! Bibliographic entries, read from CSL-JSON or BibTeX.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use crate::definition::MetaValue;
+use crate::definition::MetaValue::{MetaBlocks, MetaBool, MetaInlines, MetaList, MetaMap, MetaString};
+use crate::utils::stringify;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Name {
+    pub family: String,
+    pub given: String,
+    /// A name not split into parts, e.g. of an institution.
+    pub literal: Option<String>,
+}
+
+impl Name {
+    /// The family name, or the literal name.
+    pub fn short(&self) -> &str {
+        self.literal.as_deref().unwrap_or(&self.family)
+    }
+}
+
+/// A bibliographic entry, with the CSL variables the styles use.
+#[derive(Clone, Debug, Default)]
+pub struct Entry {
+    pub id: String,
+    /// CSL type, e.g. `article-journal`, `book` or `chapter`.
+    pub kind: String,
+    pub author: Vec<Name>,
+    pub editor: Vec<Name>,
+    pub title: Option<String>,
+    pub container_title: Option<String>,
+    pub publisher: Option<String>,
+    pub publisher_place: Option<String>,
+    pub year: Option<i32>,
+    pub volume: Option<String>,
+    pub issue: Option<String>,
+    pub page: Option<String>,
+    pub url: Option<String>,
+    pub doi: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Bibliography {
+    pub entries: Vec<Entry>,
+}
+
+impl Bibliography {
+    pub fn get(&self, id: &str) -> Option<&Entry> {
+        self.entries.iter().find(|it| it.id == id)
+    }
+
+    pub fn extend(&mut self, other: Bibliography) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Reads a CSL-JSON (`.json`) or BibTeX (`.bib`) file.
+    pub fn load<P>(path: P) -> crate::Result<Self> where P: AsRef<Path> {
+        let content = fs::read_to_string(&path)?;
+        match path.as_ref().extension().and_then(|it| it.to_str()) {
+            Some("json") => Bibliography::from_csl_json(&content),
+            _ => Ok(Bibliography::from_bibtex(&content)),
+        }
+    }
+
+    /// Parses an array of CSL-JSON items.
+    pub fn from_csl_json(json: &str) -> crate::Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        Ok(Bibliography::from_csl_value(&value))
+    }
+
+    /// Reads the `references` metadata field, CSL items written in YAML.
+    pub fn from_meta(references: &MetaValue) -> Self {
+        Bibliography::from_csl_value(&meta_to_json(references))
+    }
+
+    fn from_csl_value(value: &Value) -> Self {
+        let items = match value {
+            Value::Array(items) => items.iter().collect(),
+            Value::Object(_) => vec![value],
+            _ => vec![],
+        };
+        Bibliography { entries: items.into_iter().filter_map(csl_entry).collect() }
+    }
+
+    /// Parses BibTeX, skipping what it cannot read.
+    pub fn from_bibtex(bibtex: &str) -> Self {
+        BibTeX { input: bibtex, position: 0, strings: HashMap::new() }.entries()
+    }
+}
+
+fn csl_entry(item: &Value) -> Option<Entry> {
+    let text = |key: &str| match item.get(key)? {
+        Value::String(text) if !text.is_empty() => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    };
+    let names = |key: &str| match item.get(key) {
+        Some(Value::Array(names)) => names.iter().map(|name| Name {
+            family: name.get("family").and_then(Value::as_str).unwrap_or_default().to_string(),
+            given: name.get("given").and_then(Value::as_str).unwrap_or_default().to_string(),
+            literal: name.get("literal").and_then(Value::as_str).map(str::to_string),
+        }).collect(),
+        _ => vec![],
+    };
+    Some(Entry {
+        id: text("id")?,
+        kind: text("type").unwrap_or_else(|| "document".to_string()),
+        author: names("author"),
+        editor: names("editor"),
+        title: text("title"),
+        container_title: text("container-title"),
+        publisher: text("publisher"),
+        publisher_place: text("publisher-place"),
+        year: item.get("issued").and_then(csl_year),
+        volume: text("volume"),
+        issue: text("issue"),
+        page: text("page").map(|it| it.replace("--", "–").replace('-', "–")),
+        url: text("URL"),
+        doi: text("DOI"),
+    })
+}
+
+/// Reads the year of a CSL date: `{"date-parts": [[2020, 5]]}`, `{"raw": "2020-05"}`, or a
+/// bare year.
+fn csl_year(date: &Value) -> Option<i32> {
+    match date {
+        Value::Number(year) => year.as_i64().map(|it| it as i32),
+        Value::String(text) => leading_year(text),
+        Value::Object(fields) => {
+            if let Some(year) = fields.get("date-parts").and_then(|it| it.get(0)).and_then(|it| it.get(0)) {
+                return csl_year(year);
+            }
+            fields.get("raw").or_else(|| fields.get("literal")).and_then(csl_year)
+        },
+        _ => None,
+    }
+}
+
+fn leading_year(text: &str) -> Option<i32> {
+    let digits: String = text.trim().chars().take_while(|it| it.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+fn meta_to_json(value: &MetaValue) -> Value {
+    match value {
+        MetaMap(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), meta_to_json(v))).collect()),
+        MetaList(values) => Value::Array(values.iter().map(meta_to_json).collect()),
+        MetaBool(value) => Value::Bool(*value),
+        MetaString(_) | MetaInlines(_) | MetaBlocks(_) => Value::String(stringify(value)),
+    }
+}
+
+struct BibTeX<'a> {
+    input: &'a str,
+    position: usize,
+    /// Values of the `@string` abbreviations.
+    strings: HashMap<String, String>,
+}
+
+impl<'a> BibTeX<'a> {
+    fn entries(mut self) -> Bibliography {
+        let mut entries = Vec::new();
+        while let Some(at) = self.rest().find('@') {
+            self.position += at + 1;
+            let kind = self.identifier().to_lowercase();
+            self.skip_whitespace();
+            let (open, close) = match self.peek() {
+                Some('{') => ('{', '}'),
+                Some('(') => ('(', ')'),
+                _ => continue,
+            };
+            if kind == "comment" || kind == "preamble" {
+                self.group(open, close);
+                continue;
+            }
+            self.position += 1;
+            match kind.as_str() {
+                "string" => {
+                    let fields = self.fields(close);
+                    self.strings.extend(fields);
+                },
+                _ => {
+                    let key = self.until(&[',', close]).trim().to_string();
+                    let fields: HashMap<String, String> = self.fields(close).into_iter().collect();
+                    if !key.is_empty() {
+                        entries.push(bibtex_entry(key, &kind, fields));
+                    }
+                },
+            }
+        }
+        Bibliography { entries }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        let rest = self.rest();
+        let length = rest.find(|c: char| !(c.is_alphanumeric() || "_-:.+/".contains(c))).unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn until(&mut self, ends: &[char]) -> &'a str {
+        let rest = self.rest();
+        let length = rest.find(ends).unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    /// Reads the `name = value` fields up to `close`, with lowercase names.
+    fn fields(&mut self, close: char) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some(',') => self.position += 1,
+                Some(c) if c == close => {
+                    self.position += 1;
+                    break;
+                },
+                Some(_) => {
+                    let name = self.identifier().to_lowercase();
+                    self.skip_whitespace();
+                    if name.is_empty() || self.peek() != Some('=') {
+                        // Not a field: skip to the next one.
+                        self.until(&[',', close]);
+                        continue;
+                    }
+                    self.position += 1;
+                    fields.push((name, self.value(close)));
+                },
+            }
+        }
+        fields
+    }
+
+    /// Reads a value made of `{...}`, `"..."`, numbers and abbreviations joined by `#`.
+    fn value(&mut self, close: char) -> String {
+        let mut value = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => value.push_str(self.group('{', '}')),
+                Some('"') => {
+                    self.position += 1;
+                    let start = self.position;
+                    let mut depth = 0;
+                    while let Some(c) = self.peek() {
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            '"' if depth == 0 => break,
+                            _ => {}
+                        }
+                        self.position += c.len_utf8();
+                    }
+                    value.push_str(&self.input[start..self.position]);
+                    self.position += 1;
+                },
+                _ => {
+                    let word = self.until(&[',', '#', close]).trim();
+                    value.push_str(self.strings.get(&word.to_lowercase()).map_or(word, String::as_str));
+                },
+            }
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        value
+    }
+
+    /// Reads a group balanced on `open` and `close`, e.g. `{...}`, and returns its content; the
+    /// other delimiters are text, as the parentheses of `{a) b}`.
+    fn group(&mut self, open: char, close: char) -> &'a str {
+        let start = self.position + 1;
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.position += c.len_utf8();
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return &self.input[start..self.position - 1];
+                }
+            }
+        }
+        &self.input[start.min(self.input.len())..]
+    }
+}
+
+fn bibtex_entry(id: String, kind: &str, fields: HashMap<String, String>) -> Entry {
+    let field = |name: &str| fields.get(name).map(|it| latex_to_text(it)).filter(|it| !it.is_empty());
+    let (kind, container) = match kind {
+        "article" => ("article-journal", field("journal").or_else(|| field("journaltitle"))),
+        "book" => ("book", None),
+        "inproceedings" | "conference" => ("paper-conference", field("booktitle")),
+        "incollection" | "inbook" => ("chapter", field("booktitle")),
+        "phdthesis" | "mastersthesis" | "thesis" => ("thesis", None),
+        "techreport" | "report" => ("report", None),
+        "online" | "electronic" | "www" => ("webpage", None),
+        _ => ("document", None),
+    };
+    Entry {
+        id,
+        kind: kind.to_string(),
+        author: fields.get("author").map(|it| bibtex_names(it)).unwrap_or_default(),
+        editor: fields.get("editor").map(|it| bibtex_names(it)).unwrap_or_default(),
+        title: field("title"),
+        container_title: container,
+        publisher: field("publisher").or_else(|| field("school")).or_else(|| field("institution")).or_else(|| field("organization")),
+        publisher_place: field("address").or_else(|| field("location")),
+        year: field("year").or_else(|| field("date")).and_then(|it| leading_year(&it)),
+        volume: field("volume"),
+        issue: field("number").or_else(|| field("issue")),
+        page: field("pages"),
+        url: field("url"),
+        doi: field("doi"),
+    }
+}
+
+/// Splits a BibTeX name list on the ` and ` outside braces.
+fn bibtex_names(names: &str) -> Vec<Name> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in names.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if depth == 0 && names[i..].starts_with(" and ") => {
+                result.push(bibtex_name(&names[start..i]));
+                start = i + 5;
+            },
+            _ => {}
+        }
+    }
+    result.push(bibtex_name(&names[start..]));
+    result.into_iter().filter(|it| !it.short().is_empty()).collect()
+}
+
+/// Reads `Family, Given`, `Given Family` or `{Literal Name}`.
+fn bibtex_name(name: &str) -> Name {
+    let name = name.trim();
+    if name.starts_with('{') && name.ends_with('}') && !name[1..name.len() - 1].contains(['{', '}']) {
+        return Name { literal: Some(latex_to_text(name)), ..Name::default() };
+    }
+    let parts: Vec<&str> = name.split(',').map(str::trim).collect();
+    match parts.as_slice() {
+        [family, .., given] if parts.len() > 1 => Name { family: latex_to_text(family), given: latex_to_text(given), literal: None },
+        _ => {
+            let words: Vec<&str> = name.split_whitespace().collect();
+            match words.split_last() {
+                Some((family, given)) => Name { family: latex_to_text(family), given: latex_to_text(&given.join(" ")), literal: None },
+                None => Name::default(),
+            }
+        },
+    }
+}
+
+/// Strips the braces and turns the common LaTeX escapes and accents into text.
+fn latex_to_text(latex: &str) -> String {
+    let mut result = String::with_capacity(latex.len());
+    let mut chars = latex.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {},
+            '~' => result.push('\u{a0}'),
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                if chars.peek() == Some(&'-') {
+                    chars.next();
+                    result.push('—');
+                } else {
+                    result.push('–');
+                }
+            },
+            '\\' => match chars.next() {
+                Some(accent @ ('\'' | '`' | '"' | '^' | '~' | 'c')) => {
+                    while chars.peek().is_some_and(|it| *it == '{' || *it == ' ' || *it == '\\') {
+                        // `\i` is a dotless i, accented as an i.
+                        chars.next();
+                    }
+                    if let Some(letter) = chars.next() {
+                        result.push(accented(accent, letter).unwrap_or(letter));
+                    }
+                },
+                Some(escaped @ ('&' | '%' | '$' | '#' | '_' | '{' | '}')) => result.push(escaped),
+                Some(first) => {
+                    let mut command = first.to_string();
+                    while let Some(c) = chars.next_if(|it| it.is_alphabetic()) {
+                        command.push(c);
+                    }
+                    // Logos are kept, other commands are dropped but not their argument.
+                    if matches!(command.as_str(), "TeX" | "LaTeX" | "BibTeX") {
+                        result.push_str(&command);
+                    }
+                },
+                None => {},
+            },
+            _ if c.is_whitespace() => {
+                if !result.ends_with(' ') {
+                    result.push(' ');
+                }
+            },
+            _ => result.push(c),
+        }
+    }
+    result.trim().to_string()
+}
+
+fn accented(accent: char, letter: char) -> Option<char> {
+    let (letters, accented) = match accent {
+        '\'' => ("aeiouyAEIOUYcnszCNSZ", "áéíóúýÁÉÍÓÚÝćńśźĆŃŚŹ"),
+        '`' => ("aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+        '"' => ("aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+        '^' => ("aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+        '~' => ("anoANO", "ãñõÃÑÕ"),
+        'c' => ("csCS", "çşÇŞ"),
+        _ => return None,
+    };
+    letters.chars().position(|it| it == letter).and_then(|i| accented.chars().nth(i))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::MetaValue::{MetaInlines, MetaList, MetaMap, MetaString};
+    use crate::definition::Inline::Str;
+    use super::{Bibliography, Name};
+
+    fn name(family: &str, given: &str) -> Name {
+        Name { family: family.into(), given: given.into(), literal: None }
+    }
+
+    #[test]
+    fn bibtex_fields() {
+        let bibliography = Bibliography::from_bibtex(r#"
+            @string{acm = "ACM"}
+            @comment(a comment with ) an unbalanced {parenthesis)
+            @Article{doe2020,
+              title = {Why (not) a) b {Nested {Braces}}},
+              Journal = acm # " Journal",
+              year = 2020,
+              pages = "1--10",
+              author = {Doe, Jane and John {van} Roe and {Barnes and Noble}},
+            }
+            @book(roe, title = {Closing) early}, publisher = {P}, date = {2019-05})
+        "#);
+        let doe = bibliography.get("doe2020").unwrap();
+        assert_eq!(doe.kind, "article-journal");
+        assert_eq!(doe.title.as_deref(), Some("Why (not) a) b Nested Braces"));
+        assert_eq!(doe.container_title.as_deref(), Some("ACM Journal"));
+        assert_eq!(doe.year, Some(2020));
+        assert_eq!(doe.page.as_deref(), Some("1–10"));
+        assert_eq!(doe.author, vec![
+            name("Doe", "Jane"),
+            name("Roe", "John van"),
+            Name { literal: Some("Barnes and Noble".into()), ..Name::default() },
+        ]);
+        let roe = bibliography.get("roe").unwrap();
+        assert_eq!(roe.kind, "book");
+        assert_eq!(roe.title.as_deref(), Some("Closing) early"));
+        assert_eq!(roe.publisher.as_deref(), Some("P"));
+        assert_eq!(roe.year, Some(2019));
+        assert_eq!(bibliography.entries.len(), 2);
+    }
+
+    #[test]
+    fn bibtex_latex() {
+        let bibliography = Bibliography::from_bibtex(r#"@misc{k, title = {Caf\'e na\"{\i}ve \& {\LaTeX}~text---\emph{here}}}"#);
+        assert_eq!(bibliography.get("k").unwrap().title.as_deref(), Some("Café naïve & LaTeX\u{a0}text—here"));
+    }
+
+    #[test]
+    fn bibtex_skips_what_it_cannot_read() {
+        let bibliography = Bibliography::from_bibtex("mail@example.com\n@misc{, title = {no key}}\n@misc{ok, junk, title = {T}}\n@misc{cut, title = {never closed");
+        let ids: Vec<&str> = bibliography.entries.iter().map(|it| it.id.as_str()).collect();
+        assert_eq!(ids, ["ok", "cut"]);
+        assert_eq!(bibliography.get("ok").unwrap().title.as_deref(), Some("T"));
+        assert_eq!(bibliography.get("cut").unwrap().title.as_deref(), Some("never closed"));
+    }
+
+    #[test]
+    fn csl_json() {
+        let bibliography = Bibliography::from_csl_json(r#"[
+            {"id": "a", "type": "book", "title": "A (b) {c}", "author": [{"family": "Doe", "given": "Jane"}, {"literal": "ACME"}],
+             "issued": {"date-parts": [[2020, 5]]}, "page": "3-4", "volume": 2},
+            {"id": "b", "issued": {"raw": "1999-01"}},
+            {"id": "c", "issued": "2001"},
+            {"title": "no id"}
+        ]"#).unwrap();
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(a.kind, "book");
+        assert_eq!(a.title.as_deref(), Some("A (b) {c}"));
+        assert_eq!(a.author, vec![name("Doe", "Jane"), Name { literal: Some("ACME".into()), ..Name::default() }]);
+        assert_eq!(a.author[1].short(), "ACME");
+        assert_eq!(a.year, Some(2020));
+        assert_eq!(a.page.as_deref(), Some("3–4"));
+        assert_eq!(a.volume.as_deref(), Some("2"));
+        let b = bibliography.get("b").unwrap();
+        assert_eq!((b.kind.as_str(), b.year), ("document", Some(1999)));
+        assert_eq!(bibliography.get("c").unwrap().year, Some(2001));
+        assert_eq!(bibliography.entries.len(), 3);
+
+        assert_eq!(Bibliography::from_csl_json(r#"{"id": "single"}"#).unwrap().entries.len(), 1);
+        assert!(Bibliography::from_csl_json("[").is_err());
+    }
+
+    #[test]
+    fn meta_references() {
+        let item = MetaMap(vec![
+            ("id".to_string(), MetaString("m".into())),
+            ("title".to_string(), MetaInlines(vec![Str("Title".into())])),
+            ("issued".to_string(), MetaString("2010".into())),
+        ].into_iter().collect());
+        let bibliography = Bibliography::from_meta(&MetaList(vec![item]));
+        let m = bibliography.get("m").unwrap();
+        assert_eq!((m.title.as_deref(), m.year), (Some("Title"), Some(2010)));
+    }
+}
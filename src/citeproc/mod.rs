@@ -0,0 +1,806 @@
+//! Rendering of citations and of the bibliography, in the manner of pandoc's `--citeproc`.
+//!
+//! Citations keep their `Cite` element, whose inlines are replaced by the rendered citation.
+//! The bibliography fills the `Div` with identifier `refs` if there is one, and is appended to
+//! the document otherwise. Citations of cross-reference labels (`@fig:plot`) are left alone.
+//!
+//! The metadata configure the output, with pandoc's names:
+//!
+//! | Key | Default |
+//! |-----|---------|
+//! | `bibliography` | CSL-JSON (`.json`) or BibTeX files, one or a list |
+//! | `references` | entries written in the metadata, as CSL items |
+//! | `citation-style` | `author-date`; also `numeric` and `note`, or the name of a well-known CSL style |
+//! | `link-citations` | `false`; links citations to their bibliography entry |
+//! | `reference-section-title` | none; title of a header added above the appended bibliography |
+//! | `suppress-bibliography` | `false` |
+//! | `nocite` | entries listed without being cited; `@*` lists them all |
+//!
+//! ```no_run
+//! use pandoc::citeproc::citeproc;
+//! use pandoc::definition::Pandoc;
+//! use pandoc::pipeline::Pipeline;
+//!
+//! Pipeline::new(())
+//!     .try_pass(|pandoc: Pandoc, _| citeproc(pandoc))
+//!     .to_json_filter()
+//!     .unwrap();
+//! ```
+
+pub mod bibliography;
+
+use std::cmp::Ordering;
+use crate::context::{self, NodeKind};
+use crate::crossref::Kind;
+use crate::definition::{Attr, Block, Citation, CitationMode, Inline, InlineKind, Meta, MetaValue, Pandoc, QuoteType};
+use crate::definition::Block::{Div, Header, Para};
+use crate::definition::Inline::{Cite, Emph, Link, Note, Quoted, Space, Str, Strong};
+use crate::definition::MetaValue::{MetaBool, MetaInlines, MetaList, MetaString};
+use crate::queryable::Queryable;
+use crate::traversal::Traversal;
+use crate::utils::stringify;
+
+pub use bibliography::{Bibliography, Entry, Name};
+
+/// Renders the citations of the document and adds its bibliography, as configured by its
+/// metadata.
+pub fn citeproc(pandoc: Pandoc) -> crate::Result<Pandoc> {
+    let mut citeproc = Citeproc::from_meta(&pandoc.meta)?;
+    Ok(citeproc.process(pandoc))
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    /// `(Doe 2020, 33)`, the bibliography sorted by author.
+    #[default]
+    AuthorDate,
+    /// `[1]`, the bibliography numbered in the order of the first citations.
+    Numeric,
+    /// Citations in footnotes, `Ibid.` repeating the previous one.
+    Note,
+}
+
+impl Style {
+    /// Reads `author-date`, `numeric` or `note`, or guesses the kind of a CSL style from its name.
+    pub fn from_name(name: &str) -> Option<Style> {
+        let name = name.trim().trim_end_matches(".csl").to_lowercase();
+        match name.as_str() {
+            "author-date" | "apa" | "harvard" | "mla" => Some(Style::AuthorDate),
+            "numeric" | "ieee" | "vancouver" | "nature" | "acm" | "ama" => Some(Style::Numeric),
+            "note" => Some(Style::Note),
+            _ if name.contains("author-date") => Some(Style::AuthorDate),
+            _ if name.contains("note") => Some(Style::Note),
+            _ if name.contains("numeric") => Some(Style::Numeric),
+            _ => None,
+        }
+    }
+}
+
+pub struct Citeproc {
+    pub bibliography: Bibliography,
+    pub style: Style,
+    pub link_citations: bool,
+    /// Title of the header added above an appended bibliography.
+    pub reference_section_title: Option<Vec<Inline>>,
+    pub suppress_bibliography: bool,
+    /// Ids listed in the bibliography without being cited; `*` lists every entry.
+    pub nocite: Vec<String>,
+    /// Ids in the order of their first citation.
+    cited: Vec<String>,
+    /// Id and note number of the previous citation, for `Ibid.`.
+    previous: Option<(String, u64)>,
+}
+
+impl Citeproc {
+    pub fn new(bibliography: Bibliography, style: Style) -> Self {
+        Citeproc {
+            bibliography,
+            style,
+            link_citations: false,
+            reference_section_title: None,
+            suppress_bibliography: false,
+            nocite: Vec::new(),
+            cited: Vec::new(),
+            previous: None,
+        }
+    }
+
+    /// Reads the settings and loads the bibliography files named in `meta`.
+    pub fn from_meta(meta: &Meta) -> crate::Result<Self> {
+        let mut bibliography = Bibliography::default();
+        match meta.get("bibliography") {
+            Some(MetaList(files)) => for file in files {
+                bibliography.extend(Bibliography::load(stringify(file))?);
+            },
+            Some(file) => bibliography.extend(Bibliography::load(stringify(file))?),
+            None => {},
+        }
+        if let Some(references) = meta.get("references") {
+            bibliography.extend(Bibliography::from_meta(references));
+        }
+        let style = meta.get("citation-style").and_then(|it| Style::from_name(&stringify(it))).unwrap_or_default();
+        let mut citeproc = Citeproc::new(bibliography, style);
+        citeproc.link_citations = meta.get("link-citations").is_some_and(flag);
+        citeproc.suppress_bibliography = meta.get("suppress-bibliography").is_some_and(flag);
+        citeproc.reference_section_title = meta.get("reference-section-title").and_then(|it| match it {
+            MetaInlines(ils) => Some(ils.clone()),
+            MetaString(title) => Some(text(title)),
+            _ => None,
+        });
+        if let Some(nocite) = meta.get("nocite") {
+            citeproc.nocite = match nocite {
+                MetaString(ids) => ids.split([' ', ',', ';']).map(|it| it.trim_start_matches('@').to_string()).filter(|it| !it.is_empty()).collect(),
+                _ => nocite.query(&mut |inline: &Inline| match inline {
+                    Cite(citations, _) => citations.iter().map(|it| it.citation_id.clone()).collect(),
+                    _ => vec![],
+                }),
+            };
+        }
+        Ok(citeproc)
+    }
+
+    /// Number of the entry in a numeric style, once cited.
+    pub fn number(&self, id: &str) -> Option<usize> {
+        self.cited.iter().position(|it| it == id).map(|it| it + 1)
+    }
+
+    /// Renders the citations in document order and adds the bibliography.
+    pub fn process(&mut self, mut pandoc: Pandoc) -> Pandoc {
+        context::walk_inlines(&mut pandoc.blocks, Traversal::TopDown, &mut |inline: &mut Inline, context| {
+            if let Cite(citations, ils) = inline {
+                if citations.iter().all(|it| Kind::of(&it.citation_id).is_none()) {
+                    *ils = self.cite(citations, context.within(NodeKind::Inline(InlineKind::Note)));
+                }
+            }
+        });
+        if !self.suppress_bibliography {
+            let references = self.references();
+            if !fill_refs(&mut pandoc.blocks, &references) && !references.is_empty() {
+                if let Some(title) = &self.reference_section_title {
+                    pandoc.blocks.push(Header(1, Attr { id: "bibliography".to_string(), classes: vec!["unnumbered".to_string()], ..Attr::default() }, title.clone()));
+                }
+                pandoc.blocks.push(refs_div(references));
+            }
+        }
+        pandoc
+    }
+
+    /// Renders one `Cite`, `in_note` telling whether it is already in a footnote.
+    pub fn cite(&mut self, citations: &[Citation], in_note: bool) -> Vec<Inline> {
+        match self.style {
+            Style::AuthorDate => self.cite_with(citations, ("(", "; ", ")"), |this, entry| {
+                let year = year(entry);
+                this.link(entry, text(&year))
+            }),
+            Style::Numeric => self.cite_with(citations, ("[", ", ", "]"), |this, entry| {
+                let number = this.number(&entry.id).unwrap_or_default().to_string();
+                this.link(entry, vec![Str(number)])
+            }),
+            Style::Note => {
+                let mut result = self.note_citation(citations);
+                if in_note {
+                    result
+                } else {
+                    capitalize(&mut result);
+                    vec![Note(vec![Para(result)])]
+                }
+            },
+        }
+    }
+
+    /// Entries of the bibliography, in the order of the style.
+    pub fn entries(&self) -> Vec<&Entry> {
+        let all = self.nocite.iter().any(|it| it == "*");
+        let mut entries: Vec<&Entry> = self.cited.iter()
+            .chain(&self.nocite)
+            .filter_map(|id| self.bibliography.get(id))
+            .chain(self.bibliography.entries.iter().filter(|_| all))
+            .collect();
+        let mut seen = Vec::new();
+        entries.retain(|entry| if seen.contains(&&entry.id) { false } else { seen.push(&entry.id); true });
+        if self.style != Style::Numeric {
+            entries.sort_by(|a, b| compare(a, b));
+        }
+        entries
+    }
+
+    /// The bibliography entries, each a `Div` with identifier `ref-<id>`.
+    pub fn references(&self) -> Vec<Block> {
+        self.entries().into_iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let inlines = match self.style {
+                    Style::Numeric => {
+                        let mut inlines = vec![Str(format!("[{}]", i + 1)), Space];
+                        inlines.extend(numeric_entry(entry));
+                        inlines
+                    },
+                    _ => author_date_entry(entry, self.style == Style::AuthorDate),
+                };
+                Div(Attr { id: format!("ref-{}", entry.id), classes: vec!["csl-entry".to_string()], ..Attr::default() }, vec![Para(inlines)])
+            })
+            .collect()
+    }
+
+    /// Renders the citations of a parenthetical style: `(see Doe 2020, 33; Smith 2019)`, or
+    /// `Doe (2020, 33)` for an author-in-text citation.
+    fn cite_with<F>(&mut self, citations: &[Citation], (open, separator, close): (&str, &str, &str), mut locator: F) -> Vec<Inline> where F: FnMut(&Self, &Entry) -> Vec<Inline> {
+        let mut result = Vec::new();
+        let mut items = Vec::new();
+        for (i, citation) in citations.iter().enumerate() {
+            self.mark(&citation.citation_id);
+            let entry = match self.bibliography.get(&citation.citation_id) {
+                Some(entry) => entry,
+                None => {
+                    items.push(vec![missing(citation)]);
+                    continue;
+                },
+            };
+            let mut item = Vec::new();
+            if i == 0 && matches!(citation.citation_mode, CitationMode::AuthorInText) {
+                result.extend(text(&short_names(entry)));
+                result.push(Space);
+            } else {
+                append(&mut item, prefix(&citation.citation_prefix));
+                if matches!(citation.citation_mode, CitationMode::NormalCitation) && self.style == Style::AuthorDate {
+                    append(&mut item, text(&short_names(entry)));
+                    item.push(Space);
+                }
+            }
+            let locator = locator(self, entry);
+            append(&mut item, locator);
+            append(&mut item, suffix(&citation.citation_suffix));
+            items.push(item);
+        }
+        append(&mut result, vec![Str(open.to_string())]);
+        for (i, item) in items.into_iter().enumerate() {
+            if i > 0 {
+                append(&mut result, text(separator));
+            }
+            append(&mut result, item);
+        }
+        append(&mut result, vec![Str(close.to_string())]);
+        result
+    }
+
+    /// Renders the citations of the note style: the full reference first, then the author and
+    /// the title, or `Ibid.` for the work cited just before.
+    fn note_citation(&mut self, citations: &[Citation]) -> Vec<Inline> {
+        let mut result = Vec::new();
+        for (i, citation) in citations.iter().enumerate() {
+            if i > 0 {
+                append(&mut result, text("; "));
+            }
+            let id = &citation.citation_id;
+            let first = self.mark(id);
+            let note = citation.citation_note_num;
+            let ibid = matches!(&self.previous, Some((previous, previous_note)) if previous == id && (note == *previous_note || note == previous_note + 1));
+            self.previous = Some((id.clone(), note));
+            append(&mut result, prefix(&citation.citation_prefix));
+            let entry = match self.bibliography.get(id) {
+                Some(entry) => entry,
+                None => {
+                    result.push(missing(citation));
+                    continue;
+                },
+            };
+            let mut item = if ibid {
+                text("Ibid.")
+            } else if first {
+                full_note(entry)
+            } else {
+                let mut item = text(&short_names(entry));
+                if let Some(title) = &entry.title {
+                    append(&mut item, text(", "));
+                    item.push(title_inline(entry, title));
+                }
+                item
+            };
+            if matches!(citation.citation_mode, CitationMode::SuppressAuthor) && !ibid {
+                item = entry.title.as_ref().map(|title| vec![title_inline(entry, title)]).unwrap_or(item);
+            }
+            append(&mut result, self.link(entry, item));
+            append(&mut result, suffix(&citation.citation_suffix));
+        }
+        sentence(result)
+    }
+
+    /// Records a citation of `id`, returning whether it is the first one.
+    fn mark(&mut self, id: &str) -> bool {
+        if self.cited.iter().any(|it| it == id) || self.bibliography.get(id).is_none() {
+            return false;
+        }
+        self.cited.push(id.to_string());
+        true
+    }
+
+    fn link(&self, entry: &Entry, inlines: Vec<Inline>) -> Vec<Inline> {
+        if self.link_citations {
+            vec![Link(Attr::default(), inlines, (format!("#ref-{}", entry.id), String::new()))]
+        } else {
+            inlines
+        }
+    }
+}
+
+fn flag(value: &MetaValue) -> bool {
+    match value {
+        MetaBool(value) => *value,
+        _ => matches!(stringify(value).as_str(), "true" | "yes"),
+    }
+}
+
+/// Replaces the content of the first `Div#refs` found; returns whether there was one.
+fn fill_refs(blocks: &mut [Block], references: &[Block]) -> bool {
+    for block in blocks {
+        if let Div(attr, blks) = block {
+            if attr.id == "refs" {
+                *blks = references.to_vec();
+                if !attr.classes.iter().any(|it| it == "references") {
+                    attr.classes.push("references".to_string());
+                }
+                return true;
+            }
+            if fill_refs(blks, references) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn refs_div(references: Vec<Block>) -> Block {
+    Div(Attr { id: "refs".to_string(), classes: vec!["references".to_string(), "csl-bib-body".to_string()], ..Attr::default() }, references)
+}
+
+fn compare(a: &Entry, b: &Entry) -> Ordering {
+    let key = |entry: &Entry| (sort_names(entry).to_lowercase(), entry.year, entry.title.clone().unwrap_or_default().to_lowercase());
+    key(a).cmp(&key(b))
+}
+
+fn names(entry: &Entry) -> &[Name] {
+    if entry.author.is_empty() { &entry.editor } else { &entry.author }
+}
+
+fn sort_names(entry: &Entry) -> String {
+    match names(entry) {
+        [] => entry.title.clone().unwrap_or_default(),
+        names => names.iter().map(|it| format!("{} {}", it.short(), it.given)).collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// `Doe`, `Doe and Smith` or `Doe et al.`; the title when there is no author.
+fn short_names(entry: &Entry) -> String {
+    match names(entry) {
+        [] => entry.title.clone().unwrap_or_else(|| entry.id.clone()),
+        [one] => one.short().to_string(),
+        [one, two] => format!("{} and {}", one.short(), two.short()),
+        [one, ..] => format!("{} et al.", one.short()),
+    }
+}
+
+fn year(entry: &Entry) -> String {
+    entry.year.map_or_else(|| "n.d.".to_string(), |it| it.to_string())
+}
+
+/// Joins `A`, `A and B`, or `A, B, and C`; `serial` also puts a comma between two names.
+fn join(names: Vec<String>, serial: bool) -> String {
+    match names.as_slice() {
+        [] => String::new(),
+        [one] => one.clone(),
+        [one, two] if !serial => format!("{} and {}", one, two),
+        [init @ .., last] => format!("{}, and {}", init.join(", "), last),
+    }
+}
+
+fn full_name(name: &Name) -> String {
+    match &name.literal {
+        Some(literal) => literal.clone(),
+        None if name.given.is_empty() => name.family.clone(),
+        None => format!("{} {}", name.given, name.family),
+    }
+}
+
+fn inverted_name(name: &Name) -> String {
+    match &name.literal {
+        Some(literal) => literal.clone(),
+        None if name.given.is_empty() => name.family.clone(),
+        None => format!("{}, {}", name.family, name.given),
+    }
+}
+
+fn initials_name(name: &Name) -> String {
+    match &name.literal {
+        Some(literal) => literal.clone(),
+        None => {
+            let initials: Vec<String> = name.given.split([' ', '-', '.'])
+                .filter_map(|it| it.chars().next())
+                .map(|it| format!("{}.", it))
+                .collect();
+            if initials.is_empty() { name.family.clone() } else { format!("{} {}", initials.join(" "), name.family) }
+        },
+    }
+}
+
+/// Whether the title is set in italics rather than in quotes.
+fn is_book(entry: &Entry) -> bool {
+    !matches!(entry.kind.as_str(), "article" | "article-journal" | "article-magazine" | "article-newspaper" | "chapter" | "paper-conference" | "entry-encyclopedia" | "webpage" | "post-weblog")
+}
+
+fn title_inline(entry: &Entry, title: &str) -> Inline {
+    if is_book(entry) { Emph(text(title)) } else { Quoted(QuoteType::DoubleQuote, text(title)) }
+}
+
+fn editors_suffix(entry: &Entry) -> &'static str {
+    match (entry.author.is_empty(), entry.editor.len()) {
+        (true, 1) => ", ed.",
+        (true, n) if n > 1 => ", eds.",
+        _ => "",
+    }
+}
+
+/// `Doe, John, and Jane Smith. 2020. “Title.” *Journal* 12 (3): 45–67.`, the year after the
+/// publisher when not `author_date`.
+fn author_date_entry(entry: &Entry, author_date: bool) -> Vec<Inline> {
+    let mut sentences = Vec::new();
+    let names = names(entry);
+    if !names.is_empty() {
+        let mut inverted: Vec<String> = names.iter().map(full_name).collect();
+        inverted[0] = inverted_name(&names[0]);
+        sentences.push(text(&format!("{}{}", join(inverted, true), editors_suffix(entry))));
+    }
+    if author_date {
+        sentences.push(text(&year(entry)));
+    }
+    if let Some(title) = &entry.title {
+        if is_book(entry) {
+            sentences.push(vec![Emph(text(title))]);
+        } else {
+            sentences.push(vec![Quoted(QuoteType::DoubleQuote, sentence(text(title)))]);
+        }
+    }
+    if let Some(container) = &entry.container_title {
+        let mut container = vec![Emph(text(container))];
+        if entry.kind == "article-journal" || entry.kind == "article" {
+            if let Some(volume) = &entry.volume {
+                append(&mut container, text(&format!(" {}", volume)));
+            }
+            if let Some(issue) = &entry.issue {
+                append(&mut container, text(&format!(" ({})", issue)));
+            }
+            if let Some(page) = &entry.page {
+                append(&mut container, text(&format!(": {}", page)));
+            }
+        } else {
+            container.insert(0, Space);
+            container.insert(0, Str("In".to_string()));
+            if let Some(page) = &entry.page {
+                append(&mut container, text(&format!(", {}", page)));
+            }
+        }
+        sentences.push(container);
+    }
+    if let Some(publisher) = publisher(entry) {
+        sentences.push(text(&publisher));
+    }
+    if !author_date {
+        sentences.push(text(&year(entry)));
+    }
+    if let Some(link) = web_link(entry) {
+        sentences.push(vec![link]);
+    }
+    let mut result = Vec::new();
+    for (i, item) in sentences.into_iter().enumerate() {
+        if i > 0 {
+            result.push(Space);
+        }
+        append(&mut result, sentence(item));
+    }
+    result
+}
+
+/// `J. Doe and J. Smith, “Title,” *Journal*, vol. 12, no. 3, pp. 45–67, 2020.`
+fn numeric_entry(entry: &Entry) -> Vec<Inline> {
+    let mut result = Vec::new();
+    let mut parts = Vec::new();
+    let names = names(entry);
+    if !names.is_empty() {
+        parts.push(text(&format!("{}{}", join(names.iter().map(initials_name).collect(), false), editors_suffix(entry))));
+    }
+    let mut quoted_title = None;
+    if let Some(title) = &entry.title {
+        if is_book(entry) {
+            parts.push(vec![Emph(text(title))]);
+        } else {
+            quoted_title = Some(title);
+        }
+    }
+    for (i, part) in parts.drain(..).enumerate() {
+        if i > 0 {
+            append(&mut result, text(", "));
+        }
+        append(&mut result, part);
+    }
+    if let Some(container) = &entry.container_title {
+        let mut container = vec![Emph(text(container))];
+        if !matches!(entry.kind.as_str(), "article-journal" | "article") {
+            container.insert(0, Space);
+            container.insert(0, Str("in".to_string()));
+        }
+        parts.push(container);
+    }
+    if let Some(volume) = &entry.volume {
+        parts.push(text(&format!("vol. {}", volume)));
+    }
+    if let Some(issue) = &entry.issue {
+        parts.push(text(&format!("no. {}", issue)));
+    }
+    if let Some(publisher) = publisher(entry) {
+        parts.push(text(&publisher));
+    }
+    if let Some(page) = &entry.page {
+        parts.push(text(&format!("{} {}", if page.contains('–') { "pp." } else { "p." }, page)));
+    }
+    parts.push(text(&year(entry)));
+    if let Some(title) = quoted_title {
+        if !result.is_empty() {
+            append(&mut result, text(", "));
+        }
+        let mut title = text(title);
+        append(&mut title, vec![Str(",".to_string())]);
+        result.push(Quoted(QuoteType::DoubleQuote, title));
+    }
+    for part in parts {
+        match result.last() {
+            None => {},
+            Some(Quoted(..)) => result.push(Space),
+            Some(_) => append(&mut result, text(", ")),
+        }
+        append(&mut result, part);
+    }
+    let mut result = sentence(result);
+    if let Some(link) = web_link(entry) {
+        result.push(Space);
+        result.push(link);
+    }
+    result
+}
+
+/// `John Doe and Jane Smith, “Title,” *Journal* 12, no. 3 (2020): 45–67`, or
+/// `John Doe, *Title* (Place: Publisher, 2020)`.
+fn full_note(entry: &Entry) -> Vec<Inline> {
+    let mut result = text(&format!("{}{}", join(names(entry).iter().map(full_name).collect(), false), editors_suffix(entry)));
+    if let Some(title) = &entry.title {
+        if !result.is_empty() {
+            append(&mut result, text(", "));
+        }
+        if is_book(entry) {
+            result.push(Emph(text(title)));
+        } else {
+            let mut title = text(title);
+            append(&mut title, vec![Str(",".to_string())]);
+            result.push(Quoted(QuoteType::DoubleQuote, title));
+        }
+    }
+    if let Some(container) = &entry.container_title {
+        result.push(Space);
+        if !matches!(entry.kind.as_str(), "article-journal" | "article") {
+            append(&mut result, text("in "));
+        }
+        result.push(Emph(text(container)));
+        if let Some(volume) = &entry.volume {
+            append(&mut result, text(&format!(" {}", volume)));
+        }
+        if let Some(issue) = &entry.issue {
+            append(&mut result, text(&format!(", no. {}", issue)));
+        }
+    }
+    let publication = match publisher(entry) {
+        Some(publisher) => format!("{}, {}", publisher, year(entry)),
+        None => year(entry),
+    };
+    append(&mut result, text(&format!(" ({})", publication)));
+    if let Some(page) = &entry.page {
+        append(&mut result, text(&format!(": {}", page)));
+    }
+    result
+}
+
+/// `Place: Publisher`.
+fn publisher(entry: &Entry) -> Option<String> {
+    let publisher = entry.publisher.as_ref()?;
+    Some(match &entry.publisher_place {
+        Some(place) => format!("{}: {}", place, publisher),
+        None => publisher.clone(),
+    })
+}
+
+fn web_link(entry: &Entry) -> Option<Inline> {
+    let url = match (&entry.doi, &entry.url) {
+        (Some(doi), _) => format!("https://doi.org/{}", doi),
+        (None, Some(url)) => url.clone(),
+        (None, None) => return None,
+    };
+    Some(Link(Attr::default(), vec![Str(url.clone())], (url, String::new())))
+}
+
+/// Rendering of a citation of an unknown entry.
+fn missing(citation: &Citation) -> Inline {
+    Strong(vec![Str(format!("{}?", citation.citation_id))])
+}
+
+fn prefix(prefix: &[Inline]) -> Vec<Inline> {
+    let mut result = prefix.to_vec();
+    if !result.is_empty() && !matches!(result.last(), Some(Space)) {
+        result.push(Space);
+    }
+    result
+}
+
+/// The suffix, separated by a comma unless it starts with a punctuation: `[@doe p. 33]` and
+/// `[@doe, p. 33]` both give `Doe 2020, p. 33`.
+fn suffix(suffix: &[Inline]) -> Vec<Inline> {
+    let suffix = match suffix {
+        [Space, rest @ ..] => rest,
+        _ => suffix,
+    };
+    match suffix.first() {
+        None => vec![],
+        Some(Str(first)) if first.starts_with(|it: char| it.is_ascii_punctuation() && it != '(' && it != '[') => suffix.to_vec(),
+        Some(_) => {
+            let mut result = vec![Str(",".to_string()), Space];
+            result.extend(suffix.iter().cloned());
+            result
+        },
+    }
+}
+
+/// Ends `inlines` with a period unless it already ends with a punctuation or a quote.
+fn sentence(mut inlines: Vec<Inline>) -> Vec<Inline> {
+    match inlines.last() {
+        None | Some(Quoted(..)) | Some(Link(..)) => {},
+        Some(Str(last)) if last.ends_with(['.', '?', '!']) => {},
+        _ => append(&mut inlines, vec![Str(".".to_string())]),
+    }
+    inlines
+}
+
+/// Appends `more` to `inlines`, merging the `Str` at the junction.
+fn append(inlines: &mut Vec<Inline>, more: Vec<Inline>) {
+    let mut more = more.into_iter();
+    match (inlines.last_mut(), more.next()) {
+        (Some(Str(last)), Some(Str(first))) => last.push_str(&first),
+        (_, Some(first)) => inlines.push(first),
+        (_, None) => {},
+    }
+    inlines.extend(more);
+}
+
+fn text(text: &str) -> Vec<Inline> {
+    let mut result = Vec::new();
+    for (i, word) in text.split(' ').enumerate() {
+        if i > 0 {
+            result.push(Space);
+        }
+        if !word.is_empty() {
+            append(&mut result, vec![Str(word.to_string())]);
+        }
+    }
+    result
+}
+
+fn capitalize(inlines: &mut [Inline]) {
+    if let Some(Str(text)) = inlines.first_mut() {
+        let mut chars = text.chars();
+        if let Some(first) = chars.next() {
+            *text = first.to_uppercase().chain(chars).collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Attr, Block, Citation, CitationMode, Meta, Pandoc};
+    use crate::definition::Block::{Div, Para};
+    use crate::definition::Inline::{Cite, Note, Str};
+    use crate::definition::MetaValue::{MetaBool, MetaString};
+    use crate::utils::stringify;
+    use super::{text, Bibliography, Citeproc, Style};
+
+    const BIBTEX: &str = r#"
+        @article{doe, author = {Doe, Jane}, title = {On Things}, journal = {Journal}, year = 2020, volume = 3, pages = {1--9}}
+        @book{roe, author = {Roe, Richard and Moe, Mary}, title = {A Book}, publisher = {Press}, address = {Paris}, year = 2019}
+    "#;
+
+    fn citation(id: &str, mode: CitationMode, suffix: &str, note: u64) -> Citation {
+        Citation { citation_id: id.into(), citation_prefix: vec![], citation_suffix: text(suffix), citation_mode: mode, citation_note_num: note, citation_hash: 0 }
+    }
+
+    fn cite(citations: Vec<Citation>) -> Block {
+        Para(vec![Cite(citations, vec![])])
+    }
+
+    /// Processes `blocks` and returns their text, with the content of the notes.
+    fn process(style: Style, blocks: Vec<Block>) -> Vec<String> {
+        let pandoc = Pandoc { version: crate::compat::API_VERSION.to_vec(), meta: Meta::new(), blocks };
+        let pandoc = Citeproc::new(Bibliography::from_bibtex(BIBTEX), style).process(pandoc);
+        pandoc.blocks.iter().map(|block| match block {
+            Para(ils) => ils.iter().map(|inline| match inline {
+                Cite(_, ils) => ils.iter().map(|inline| match inline {
+                    Note(blks) => format!("[{}]", stringify(blks)),
+                    _ => stringify(inline),
+                }).collect(),
+                _ => stringify(inline),
+            }).collect(),
+            _ => stringify(block),
+        }).collect()
+    }
+
+    #[test]
+    fn author_date() {
+        let blocks = process(Style::AuthorDate, vec![
+            cite(vec![citation("doe", CitationMode::NormalCitation, "p. 3", 0), citation("roe", CitationMode::SuppressAuthor, "", 0)]),
+            cite(vec![citation("roe", CitationMode::AuthorInText, "", 0)]),
+            cite(vec![citation("nobody", CitationMode::NormalCitation, "", 0)]),
+            cite(vec![citation("fig:plot", CitationMode::NormalCitation, "", 0)]),
+        ]);
+        assert_eq!(blocks, [
+            "(Doe 2020, p. 3; 2019)",
+            "Roe and Moe (2019)",
+            "(nobody?)",
+            "",
+            "Doe, Jane. 2020. “On Things.” Journal 3: 1–9. Roe, Richard, and Mary Moe. 2019. A Book. Paris: Press.",
+        ]);
+    }
+
+    #[test]
+    fn numeric() {
+        let blocks = process(Style::Numeric, vec![
+            cite(vec![citation("roe", CitationMode::NormalCitation, "", 0)]),
+            cite(vec![citation("doe", CitationMode::NormalCitation, "", 0), citation("roe", CitationMode::NormalCitation, "", 0)]),
+            Div(Attr { id: "refs".into(), ..Attr::default() }, vec![]),
+            Para(vec![Str("end".into())]),
+        ]);
+        assert_eq!(blocks, [
+            "[1]",
+            "[2, 1]",
+            "[1] R. Roe and M. Moe, A Book, Paris: Press, 2019. [2] J. Doe, “On Things,” Journal, vol. 3, pp. 1–9, 2020.",
+            "end",
+        ]);
+    }
+
+    #[test]
+    fn note() {
+        let blocks = process(Style::Note, vec![
+            cite(vec![citation("doe", CitationMode::NormalCitation, "3", 1)]),
+            cite(vec![citation("doe", CitationMode::NormalCitation, "", 2)]),
+            cite(vec![citation("roe", CitationMode::NormalCitation, "", 3)]),
+            cite(vec![citation("doe", CitationMode::NormalCitation, "", 4)]),
+        ]);
+        assert_eq!(&blocks[..4], [
+            "[Jane Doe, “On Things,” Journal 3 (2020): 1–9, 3.]",
+            "[Ibid.]",
+            "[Richard Roe and Mary Moe, A Book (Paris: Press, 2019).]",
+            "[Doe, “On Things”]",
+        ]);
+    }
+
+    #[test]
+    fn settings_from_meta() {
+        let mut meta = Meta::new();
+        meta.insert("citation-style".into(), MetaString("ieee.csl".into()));
+        meta.insert("link-citations".into(), MetaBool(true));
+        meta.insert("nocite".into(), MetaString("@doe".into()));
+        meta.insert("reference-section-title".into(), MetaString("References".into()));
+        let mut citeproc = Citeproc::from_meta(&meta).unwrap();
+        assert_eq!(citeproc.style, Style::Numeric);
+        assert!(citeproc.link_citations);
+        assert_eq!(citeproc.nocite, ["doe"]);
+        citeproc.bibliography = Bibliography::from_bibtex(BIBTEX);
+        let pandoc = citeproc.process(Pandoc { version: crate::compat::API_VERSION.to_vec(), meta: Meta::new(), blocks: vec![] });
+        let blocks = serde_json::to_value(&pandoc.blocks).unwrap();
+        assert_eq!(blocks[0]["t"], "Header");
+        assert_eq!(blocks[1]["c"][0][0], "refs");
+        assert_eq!(blocks[1]["c"][1][0]["c"][0][0], "ref-doe");
+    }
+}
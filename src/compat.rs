@@ -0,0 +1,577 @@
+//! Conversions between the JSON representations of the successive pandoc API versions.
+//!
+//! The types of [`definition`](crate::definition) follow [`API_VERSION`]. Documents emitted by
+//! pandoc with an older API are upgraded to it when read, and downgraded back to their original
+//! version (kept in [`Pandoc::version`](crate::definition::Pandoc::version)) when written, so a
+//! filter built with this crate works with every pandoc emitting an API in
+//! [`MIN_API_VERSION`]..=[`MAX_API_VERSION`].
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use serde::de::{Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use crate::definition::{Block, Meta, Pandoc};
+
+/// Major and minor numbers of the pandoc API modelled by this crate.
+pub const API_VERSION: [u32; 2] = [1, 23];
+
+/// Oldest API (pandoc 2.8) that can be converted to [`API_VERSION`].
+pub const MIN_API_VERSION: [u32; 2] = [1, 20];
+
+/// Newest API that can be converted to [`API_VERSION`].
+pub const MAX_API_VERSION: [u32; 2] = API_VERSION;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedVersion(pub Vec<u32>);
+
+impl fmt::Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = self.0.iter().map(|it| it.to_string()).collect::<Vec<_>>().join(".");
+        write!(
+            f,
+            "unsupported pandoc API version {} (supported: {}.{} to {}.{})",
+            if version.is_empty() { "<none>" } else { version.as_str() },
+            MIN_API_VERSION[0], MIN_API_VERSION[1], MAX_API_VERSION[0], MAX_API_VERSION[1]
+        )
+    }
+}
+
+impl Error for UnsupportedVersion {}
+
+impl From<UnsupportedVersion> for io::Error {
+    fn from(error: UnsupportedVersion) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Returns the `pandoc-api-version` of a JSON document.
+pub fn api_version(document: &Value) -> Option<Vec<u32>> {
+    document.get("pandoc-api-version")?
+        .as_array()?
+        .iter()
+        .map(|it| it.as_u64().map(|it| it as u32))
+        .collect()
+}
+
+pub fn check(version: &[u32]) -> Result<(), UnsupportedVersion> {
+    if version.len() >= 2 && MIN_API_VERSION[..] <= version[..2] && version[..2] <= MAX_API_VERSION[..] {
+        Ok(())
+    } else {
+        Err(UnsupportedVersion(version.to_vec()))
+    }
+}
+
+/// Converts a JSON document of a supported API into the [`API_VERSION`] representation.
+pub fn upgrade(mut document: Value) -> Result<Value, UnsupportedVersion> {
+    let version = api_version(&document).unwrap_or_default();
+    check(&version)?;
+    if version[1] < 21 {
+        document = map_nodes(document, &mut upgrade_from_1_20);
+    }
+    if version[1] < 23 {
+        document = map_nodes(document, &mut upgrade_from_1_22);
+    }
+    Ok(document)
+}
+
+/// Same as [`upgrade`] on the text of a document, returned unchanged when it already follows
+/// [`API_VERSION`].
+pub fn upgrade_json(json: String) -> crate::Result<String> {
+    #[derive(Deserialize)]
+    struct Header {
+        #[serde(rename = "pandoc-api-version", default)]
+        version: Vec<u32>,
+    }
+
+    let header: Header = serde_json::from_str(&json)?;
+    if header.version.len() >= 2 && header.version[..2] == API_VERSION {
+        return Ok(json);
+    }
+    let document = upgrade(serde_json::from_str(&json)?)?;
+    Ok(serde_json::to_string(&document)?)
+}
+
+/// A document deserialized without going through a [`Value`] when it follows [`API_VERSION`]:
+/// `meta` and `blocks` are read as typed values once the version read before them is the
+/// current one, and kept as JSON to be upgraded otherwise.
+pub(crate) struct Document {
+    version: Option<Vec<u32>>,
+    meta: Option<Part<Meta>>,
+    blocks: Option<Part<Vec<Block>>>,
+}
+
+enum Part<T> {
+    Typed(T),
+    Json(Value),
+}
+
+impl Document {
+    /// The document as read, or upgraded from its API.
+    pub(crate) fn into_pandoc(self) -> crate::Result<Pandoc> {
+        match self {
+            Document { version: Some(version), meta: Some(Part::Typed(meta)), blocks: Some(Part::Typed(blocks)) } => {
+                Ok(Pandoc { version, meta, blocks })
+            },
+            Document { version, meta, blocks } => {
+                let mut document = Map::new();
+                if let Some(version) = version {
+                    document.insert("pandoc-api-version".to_string(), json!(version));
+                }
+                if let Some(meta) = meta {
+                    document.insert("meta".to_string(), meta.into_json()?);
+                }
+                if let Some(blocks) = blocks {
+                    document.insert("blocks".to_string(), blocks.into_json()?);
+                }
+                Ok(serde_path_to_error::deserialize(upgrade(Value::Object(document))?)?)
+            },
+        }
+    }
+}
+
+impl<T> Part<T> where T: serde::Serialize {
+    fn into_json(self) -> serde_json::Result<Value> {
+        match self {
+            Part::Typed(value) => serde_json::to_value(value),
+            Part::Json(value) => Ok(value),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_map(DocumentVisitor)
+    }
+}
+
+struct DocumentVisitor;
+
+impl<'de> Visitor<'de> for DocumentVisitor {
+    type Value = Document;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a pandoc document")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Document, A::Error> where A: MapAccess<'de> {
+        let mut document = Document { version: None, meta: None, blocks: None };
+        while let Some(key) = map.next_key::<String>()? {
+            let current = document.version.as_ref().is_some_and(|it| it.len() >= 2 && it[..2] == API_VERSION);
+            match key.as_str() {
+                "pandoc-api-version" => document.version = Some(map.next_value()?),
+                "meta" if current => document.meta = Some(Part::Typed(map.next_value()?)),
+                "meta" => document.meta = Some(Part::Json(map.next_value()?)),
+                "blocks" if current => document.blocks = Some(Part::Typed(map.next_value()?)),
+                "blocks" => document.blocks = Some(Part::Json(map.next_value()?)),
+                _ => { map.next_value::<IgnoredAny>()?; },
+            }
+        }
+        Ok(document)
+    }
+}
+
+/// Converts a JSON document of the [`API_VERSION`] representation into the `version` one.
+pub fn downgrade(mut document: Value, version: &[u32]) -> Result<Value, UnsupportedVersion> {
+    check(version)?;
+    if version[1] < 23 {
+        document = map_nodes(document, &mut downgrade_to_1_22);
+    }
+    if version[1] < 21 {
+        document = map_nodes(document, &mut downgrade_to_1_20);
+    }
+    if let Some(field) = document.get_mut("pandoc-api-version") {
+        *field = json!(version);
+    }
+    Ok(document)
+}
+
+/// Rebuilds `value` bottom-up, applying `f` to every AST node (object with a `t` tag).
+/// The nodes for which `f` returns `None` are removed from their list.
+fn map_nodes<F>(value: Value, f: &mut F) -> Value where F: FnMut(String, Option<Value>) -> Option<Value> {
+    map_node(value, f).unwrap_or_default()
+}
+
+fn map_node<F>(value: Value, f: &mut F) -> Option<Value> where F: FnMut(String, Option<Value>) -> Option<Value> {
+    match value {
+        Value::Array(values) => Some(Value::Array(values.into_iter().filter_map(|it| map_node(it, f)).collect())),
+        Value::Object(mut fields) => match fields.get("t") {
+            Some(Value::String(tag)) => {
+                let tag = tag.clone();
+                let content = fields.remove("c").map(|it| map_nodes(it, f));
+                f(tag, content)
+            }
+            _ => Some(Value::Object(fields.into_iter().map(|(k, v)| (k, map_nodes(v, f))).collect()))
+        },
+        _ => Some(value)
+    }
+}
+
+fn node(tag: &str, content: Option<Value>) -> Value {
+    match content {
+        Some(content) => json!({ "t": tag, "c": content }),
+        None => json!({ "t": tag })
+    }
+}
+
+fn null_attr() -> Value {
+    json!(["", [], []])
+}
+
+fn upgrade_from_1_20(tag: String, content: Option<Value>) -> Option<Value> {
+    match (tag.as_str(), content) {
+        ("Table", Some(Value::Array(fields))) if fields.len() == 5 => {
+            let mut fields = fields.into_iter();
+            let caption = fields.next().unwrap_or_default();
+            let aligns = into_array(fields.next());
+            let widths = into_array(fields.next());
+            let headers = into_array(fields.next());
+            let rows = into_array(fields.next());
+
+            let caption = match caption {
+                Value::Array(ils) if !ils.is_empty() => json!([null, [node("Plain", Some(Value::Array(ils)))]]),
+                _ => json!([null, []])
+            };
+            let col_specs: Vec<Value> = aligns.into_iter()
+                .zip(widths.into_iter().chain(std::iter::repeat(json!(0))))
+                .map(|(align, width)| match width.as_f64() {
+                    Some(width) if width > 0.0 => json!([align, { "t": "ColWidth", "c": width }]),
+                    _ => json!([align, { "t": "ColWidthDefault" }])
+                }).collect();
+            let cell = |blocks: Value| json!([null_attr(), { "t": "AlignDefault" }, 1, 1, blocks]);
+            let head_rows = if headers.iter().all(|it| it.as_array().is_none_or(|it| it.is_empty())) {
+                vec![]
+            } else {
+                vec![json!([null_attr(), headers.into_iter().map(cell).collect::<Vec<_>>()])]
+            };
+            let body_rows: Vec<Value> = rows.into_iter()
+                .map(|row| json!([null_attr(), into_array(Some(row)).into_iter().map(cell).collect::<Vec<_>>()]))
+                .collect();
+            Some(node("Table", Some(json!([
+                null_attr(),
+                caption,
+                col_specs,
+                [null_attr(), head_rows],
+                [[null_attr(), 0, [], body_rows]],
+                [null_attr(), []]
+            ]))))
+        }
+        (_, content) => Some(node(&tag, content))
+    }
+}
+
+fn downgrade_to_1_20(tag: String, content: Option<Value>) -> Option<Value> {
+    match (tag.as_str(), content) {
+        ("Underline", Some(ils)) => Some(node("Span", Some(json!([["", ["underline"], []], ils])))),
+        ("Table", Some(Value::Array(fields))) if fields.len() == 6 => {
+            let mut fields = fields.into_iter().skip(1);
+            let caption = into_array(fields.next());
+            let col_specs = into_array(fields.next());
+            let head = into_array(fields.next());
+            let bodies = into_array(fields.next());
+            let foot = into_array(fields.next());
+
+            let caption = blocks_to_inlines(into_array(caption.into_iter().nth(1)));
+            let aligns: Vec<Value> = col_specs.iter().map(|it| it[0].clone()).collect();
+            let widths: Vec<Value> = col_specs.iter().map(|it| json!(it[1]["c"].as_f64().unwrap_or(0.0))).collect();
+            let columns = col_specs.len();
+            let cells = |row: Value| -> Vec<Value> {
+                let mut cells: Vec<Value> = into_array(row.get(1).cloned()).into_iter()
+                    .map(|cell| cell.get(4).cloned().unwrap_or_else(|| json!([])))
+                    .collect();
+                cells.resize(columns.max(cells.len()), json!([]));
+                cells
+            };
+            let mut head_rows = into_array(head.into_iter().nth(1)).into_iter();
+            let headers = match head_rows.next() {
+                Some(row) => cells(row),
+                None => vec![json!([]); columns]
+            };
+            let mut rows: Vec<Value> = head_rows.map(|row| json!(cells(row))).collect();
+            for body in bodies {
+                let mut body = into_array(Some(body)).into_iter().skip(2);
+                let intermediate_head = into_array(body.next());
+                let body_rows = into_array(body.next());
+                rows.extend(intermediate_head.into_iter().chain(body_rows).map(|row| json!(cells(row))));
+            }
+            rows.extend(into_array(foot.into_iter().nth(1)).into_iter().map(|row| json!(cells(row))));
+            Some(node("Table", Some(json!([caption, aligns, widths, headers, rows]))))
+        }
+        (_, content) => Some(node(&tag, content))
+    }
+}
+
+fn upgrade_from_1_22(tag: String, content: Option<Value>) -> Option<Value> {
+    match (tag.as_str(), content) {
+        ("Null", _) => None,
+        ("Para", Some(Value::Array(ils))) if is_implicit_figure(&ils) => {
+            let image = into_array(ils.into_iter().next().and_then(|mut it| it.get_mut("c").map(Value::take)));
+            let mut image = image.into_iter();
+            let mut attr = image.next().unwrap_or_else(null_attr);
+            let alt = image.next().unwrap_or_else(|| json!([]));
+            let target = into_array(image.next());
+            let src = target.first().cloned().unwrap_or_else(|| json!(""));
+            let title = target.get(1).and_then(Value::as_str).unwrap_or("fig:");
+            let id = attr.get_mut(0).map(Value::take).unwrap_or_else(|| json!(""));
+            if let Some(image_id) = attr.get_mut(0) {
+                *image_id = json!("");
+            }
+            let caption = if alt.as_array().is_none_or(|it| it.is_empty()) { json!([]) } else { json!([node("Plain", Some(alt.clone()))]) };
+            let image = node("Image", Some(json!([attr, alt, [src, title.trim_start_matches("fig:")]])));
+            Some(node("Figure", Some(json!([[id, [], []], [null, caption], [node("Plain", Some(json!([image])))]]))))
+        }
+        (_, content) => Some(node(&tag, content))
+    }
+}
+
+fn downgrade_to_1_22(tag: String, content: Option<Value>) -> Option<Value> {
+    match (tag.as_str(), content) {
+        ("Figure", Some(Value::Array(fields))) if fields.len() == 3 => {
+            let mut fields = fields.into_iter();
+            let mut attr = fields.next().unwrap_or_else(null_attr);
+            let caption = into_array(fields.next());
+            let content = into_array(fields.next());
+            let caption_blocks = into_array(caption.into_iter().nth(1));
+            if let Some(mut image) = single_image(&content) {
+                let mut image_fields = into_array(image.get_mut("c").map(Value::take));
+                if let (Some(image_attr), Some(id)) = (image_fields.get_mut(0), attr.get(0)) {
+                    if image_attr[0].as_str().is_none_or(str::is_empty) {
+                        image_attr[0] = id.clone();
+                    }
+                }
+                let caption = blocks_to_inlines(caption_blocks);
+                if !caption.is_empty() && image_fields.len() == 3 {
+                    image_fields[1] = Value::Array(caption);
+                }
+                if let Some(title) = image_fields.get_mut(2).and_then(|it| it.get_mut(1)) {
+                    *title = json!(format!("fig:{}", title.as_str().unwrap_or_default()));
+                }
+                return Some(node("Para", Some(json!([node("Image", Some(Value::Array(image_fields)))]))));
+            }
+            if let Some(Value::Array(classes)) = attr.get_mut(1) {
+                classes.insert(0, json!("figure"));
+            }
+            let mut blocks = content;
+            blocks.extend(caption_blocks);
+            Some(node("Div", Some(json!([attr, blocks]))))
+        }
+        (_, content) => Some(node(&tag, content))
+    }
+}
+
+fn is_implicit_figure(ils: &[Value]) -> bool {
+    match ils {
+        [image] if image["t"] == "Image" => image["c"][2][1].as_str().is_some_and(|it| it.starts_with("fig:")),
+        _ => false
+    }
+}
+
+fn single_image(blocks: &[Value]) -> Option<Value> {
+    match blocks {
+        [block] if block["t"] == "Plain" || block["t"] == "Para" => match block["c"].as_array()?.as_slice() {
+            [image] if image["t"] == "Image" => Some(image.clone()),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+fn blocks_to_inlines(blocks: Vec<Value>) -> Vec<Value> {
+    let mut inlines = Vec::new();
+    for block in blocks {
+        if block["t"] == "Plain" || block["t"] == "Para" {
+            if !inlines.is_empty() {
+                inlines.push(json!({ "t": "Space" }));
+            }
+            inlines.extend(into_array(block.get("c").cloned()));
+        }
+    }
+    inlines
+}
+
+fn into_array(value: Option<Value>) -> Vec<Value> {
+    match value {
+        Some(Value::Array(values)) => values,
+        _ => vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use crate::definition::Pandoc;
+    use super::{downgrade, upgrade, upgrade_json, Document, Part, UnsupportedVersion};
+
+    /// Reads `json` as the filters do, returning whether `meta` and `blocks` were typed as they
+    /// were read, and the document.
+    fn read(json: &str) -> (bool, bool, Value) {
+        let document: Document = serde_json::from_str(json).unwrap();
+        let typed = (matches!(document.meta, Some(Part::Typed(_))), matches!(document.blocks, Some(Part::Typed(_))));
+        (typed.0, typed.1, serde_json::to_value(document.into_pandoc().unwrap()).unwrap())
+    }
+
+    fn document(version: &[u32], blocks: Value) -> Value {
+        json!({ "pandoc-api-version": version, "meta": {}, "blocks": blocks })
+    }
+
+    /// Upgrades `document`, checks it reads as the current AST, and downgrades it back.
+    fn round_trip(document: &Value, version: &[u32]) -> Value {
+        let upgraded = upgrade(document.clone()).unwrap();
+        let pandoc: Pandoc = serde_json::from_value(upgraded.clone()).unwrap();
+        downgrade(serde_json::to_value(pandoc).unwrap(), version).unwrap()
+    }
+
+    fn str(text: &str) -> Value {
+        json!({ "t": "Str", "c": text })
+    }
+
+    fn null_attr() -> Value {
+        json!(["", [], []])
+    }
+
+    #[test]
+    fn table_1_20() {
+        let table = json!({ "t": "Table", "c": [
+            [str("Caption")],
+            [{ "t": "AlignLeft" }, { "t": "AlignDefault" }],
+            [0.5, 0.0],
+            [[{ "t": "Plain", "c": [str("a")] }], [{ "t": "Plain", "c": [str("b")] }]],
+            [[[{ "t": "Plain", "c": [str("1")] }], [{ "t": "Plain", "c": [str("2")] }]]]
+        ] });
+        let original = document(&[1, 20], json!([table]));
+
+        let upgraded = upgrade(original.clone()).unwrap();
+        let cell = |text: &str| json!([null_attr(), { "t": "AlignDefault" }, 1, 1, [{ "t": "Plain", "c": [str(text)] }]]);
+        assert_eq!(upgraded["blocks"][0], json!({ "t": "Table", "c": [
+            null_attr(),
+            [null, [{ "t": "Plain", "c": [str("Caption")] }]],
+            [[{ "t": "AlignLeft" }, { "t": "ColWidth", "c": 0.5 }], [{ "t": "AlignDefault" }, { "t": "ColWidthDefault" }]],
+            [null_attr(), [[null_attr(), [cell("a"), cell("b")]]]],
+            [[null_attr(), 0, [], [[null_attr(), [cell("1"), cell("2")]]]]],
+            [null_attr(), []]
+        ] }));
+        assert_eq!(round_trip(&original, &[1, 20]), original);
+    }
+
+    #[test]
+    fn table_1_20_without_headers() {
+        let table = json!({ "t": "Table", "c": [
+            [],
+            [{ "t": "AlignDefault" }],
+            [0.0],
+            [[]],
+            [[[{ "t": "Plain", "c": [str("1")] }]]]
+        ] });
+        let original = document(&[1, 20], json!([table]));
+
+        let upgraded = upgrade(original.clone()).unwrap();
+        assert_eq!(upgraded["blocks"][0]["c"][1], json!([null, []]));
+        assert_eq!(upgraded["blocks"][0]["c"][3], json!([null_attr(), []]));
+        assert_eq!(round_trip(&original, &[1, 20]), original);
+    }
+
+    #[test]
+    fn implicit_figure_1_22() {
+        let image = json!({ "t": "Image", "c": [["fig1", [], [["width", "50%"]]], [str("Caption")], ["image.png", "fig:Title"]] });
+        let original = document(&[1, 22, 1], json!([{ "t": "Para", "c": [image] }]));
+
+        let upgraded = upgrade(original.clone()).unwrap();
+        assert_eq!(upgraded["blocks"][0], json!({ "t": "Figure", "c": [
+            ["fig1", [], []],
+            [null, [{ "t": "Plain", "c": [str("Caption")] }]],
+            [{ "t": "Plain", "c": [{ "t": "Image", "c": [["", [], [["width", "50%"]]], [str("Caption")], ["image.png", "Title"]] }] }]
+        ] }));
+        assert_eq!(round_trip(&original, &[1, 22, 1]), original);
+    }
+
+    #[test]
+    fn image_without_fig_title_is_not_a_figure() {
+        let image = json!({ "t": "Image", "c": [null_attr(), [], ["image.png", ""]] });
+        let original = document(&[1, 22], json!([{ "t": "Para", "c": [image] }]));
+        assert_eq!(upgrade(original.clone()).unwrap(), original);
+    }
+
+    #[test]
+    fn null_blocks_are_removed() {
+        let original = document(&[1, 22], json!([{ "t": "Null" }, { "t": "Para", "c": [str("a")] }, { "t": "Div", "c": [null_attr(), [{ "t": "Null" }]] }]));
+        let upgraded = upgrade(original).unwrap();
+        assert_eq!(upgraded["blocks"], json!([{ "t": "Para", "c": [str("a")] }, { "t": "Div", "c": [null_attr(), []] }]));
+    }
+
+    #[test]
+    fn figure_without_single_image_becomes_div() {
+        let figure = json!({ "t": "Figure", "c": [
+            ["fig", ["wide"], []],
+            [null, [{ "t": "Plain", "c": [str("Caption")] }]],
+            [{ "t": "Para", "c": [str("content")] }]
+        ] });
+        let downgraded = downgrade(document(&[1, 23], json!([figure])), &[1, 22]).unwrap();
+        assert_eq!(downgraded, document(&[1, 22], json!([{ "t": "Div", "c": [
+            ["fig", ["figure", "wide"], []],
+            [{ "t": "Para", "c": [str("content")] }, { "t": "Plain", "c": [str("Caption")] }]
+        ] }])));
+    }
+
+    #[test]
+    fn underline_1_20() {
+        let underline = json!({ "t": "Underline", "c": [str("a")] });
+        let downgraded = downgrade(document(&[1, 23], json!([{ "t": "Para", "c": [underline] }])), &[1, 20]).unwrap();
+        let span = json!({ "t": "Span", "c": [["", ["underline"], []], [str("a")]] });
+        assert_eq!(downgraded, document(&[1, 20], json!([{ "t": "Para", "c": [span] }])));
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let original = document(&[1, 23, 1], json!([{ "t": "Para", "c": [{ "t": "Underline", "c": [str("a")] }] }]));
+        assert_eq!(round_trip(&original, &[1, 23, 1]), original);
+        let json = original.to_string();
+        assert_eq!(upgrade_json(json.clone()).unwrap(), json);
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        assert_eq!(upgrade(document(&[1, 19], json!([]))).unwrap_err(), UnsupportedVersion(vec![1, 19]));
+        assert_eq!(upgrade(document(&[1, 24], json!([]))).unwrap_err(), UnsupportedVersion(vec![1, 24]));
+        assert_eq!(upgrade(json!({ "meta": {}, "blocks": [] })).unwrap_err(), UnsupportedVersion(vec![]));
+        assert!(downgrade(document(&[1, 23], json!([])), &[2, 0]).is_err());
+    }
+
+    #[test]
+    fn current_documents_are_read_typed() {
+        // As written by pandoc, the version first.
+        let json = r#"{"pandoc-api-version":[1,23,1],"meta":{"title":{"t":"MetaInlines","c":[{"t":"Str","c":"a"}]}},"blocks":[{"t":"Para","c":[{"t":"Str","c":"b"}]}]}"#;
+        assert_eq!(read(json), (true, true, serde_json::from_str(json).unwrap()));
+    }
+
+    #[test]
+    fn older_documents_are_read_as_json_and_upgraded() {
+        let image = json!({ "t": "Image", "c": [null_attr(), [str("caption")], ["image.png", "fig:"]] });
+        let original = document(&[1, 22], json!([{ "t": "Para", "c": [image] }]));
+        let json = format!(r#"{{"pandoc-api-version":[1,22],"meta":{{}},"blocks":{}}}"#, original["blocks"]);
+        let (meta, blocks, pandoc) = read(&json);
+        assert_eq!((meta, blocks), (false, false));
+        assert_eq!(pandoc, upgrade(original).unwrap());
+        assert_eq!(pandoc["blocks"][0]["t"], "Figure");
+    }
+
+    #[test]
+    fn parts_read_before_the_version_are_read_as_json() {
+        let json = r#"{"meta":{"title":{"t":"MetaString","c":"a"}},"pandoc-api-version":[1,23],"blocks":[{"t":"Para","c":[{"t":"Str","c":"b"}]}]}"#;
+        let (meta, blocks, pandoc) = read(json);
+        assert_eq!((meta, blocks), (false, true));
+        assert_eq!(pandoc, serde_json::from_str::<Value>(json).unwrap());
+
+        let json = r#"{"blocks":[{"t":"Para","c":[{"t":"Underline","c":[]}]}],"meta":{},"pandoc-api-version":[1,20]}"#;
+        let (meta, blocks, pandoc) = read(json);
+        assert_eq!((meta, blocks), (false, false));
+        assert_eq!(pandoc["blocks"][0]["c"][0]["t"], "Underline");
+        assert_eq!(pandoc["pandoc-api-version"], json!([1, 20]));
+    }
+
+    #[test]
+    fn documents_without_a_version_are_unsupported() {
+        let document: Document = serde_json::from_str(r#"{"meta":{},"blocks":[]}"#).unwrap();
+        assert!(matches!(document.into_pandoc().unwrap_err(), crate::Error::UnsupportedApiVersion(_)));
+    }
+}
@@ -0,0 +1,402 @@
+//! Walks giving the callback the position of each element in the document.
+//!
+//! ```
+//! use pandoc::context::{self, NodeKind};
+//! use pandoc::definition::{Block, Inline, InlineKind};
+//! use pandoc::definition::Inline::{Link, Space, Str};
+//! use pandoc::traversal::Traversal;
+//!
+//! let mut blocks = vec![Block::Para(vec![
+//!     Str("see".into()),
+//!     Space,
+//!     Link(Default::default(), vec![Str("here".into())], ("#a".into(), String::new())),
+//! ])];
+//! context::walk_inlines(&mut blocks, Traversal::TopDown, &mut |inline: &mut Inline, context| {
+//!     if let Str(text) = inline {
+//!         if context.within(NodeKind::Inline(InlineKind::Link)) {
+//!             *text = text.to_uppercase();
+//!         }
+//!     }
+//! });
+//! assert_eq!(pandoc::utils::stringify(&blocks), "see HERE");
+//! ```
+
+use std::mem;
+use crate::definition::{Attr, Block, BlockKind, Caption, Cell, Inline, InlineKind, Row, TableBody};
+use crate::definition::Block::{BlockQuote, BulletList, CodeBlock, DefinitionList, Div, Figure, Header, HorizontalRule, LineBlock, OrderedList, Para, Plain, RawBlock, Table};
+use crate::definition::Inline::{Cite, Code, Emph, Image, LineBreak, Link, Math, Note, Quoted, RawInline, SmallCaps, SoftBreak, Space, Span, Str, Strikeout, Strong, Subscript, Superscript, Underline};
+use crate::traversal::Traversal;
+
+/// Kind of an element enclosing the visited one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Block(BlockKind),
+    Inline(InlineKind),
+    /// Caption of a `Table` or a `Figure`.
+    Caption,
+    /// Cell of a `Table`.
+    Cell,
+}
+
+#[derive(Clone, Debug)]
+pub struct Ancestor {
+    pub kind: NodeKind,
+    /// Attributes of the elements having some.
+    pub attr: Option<Attr>,
+    /// Index of the element in its parent list.
+    pub index: usize,
+}
+
+/// Position of the visited element of type `T`.
+pub struct Context<'a, T> {
+    /// Enclosing elements, outermost first.
+    pub ancestors: &'a [Ancestor],
+    /// Level of the last header met before the element, if any; a header opens its own section.
+    /// A header nested in another block, e.g. in a `Div` or a `Note`, only sets the level up to
+    /// the end of that block.
+    pub section_level: Option<u8>,
+    /// Index of the element in its parent list.
+    pub index: usize,
+    /// Elements before the visited one in its parent list, already walked.
+    pub previous: &'a [T],
+    /// Elements after the visited one in its parent list, not walked yet.
+    pub next: &'a [T],
+}
+
+impl<'a, T> Context<'a, T> {
+    pub fn parent(&self) -> Option<&'a Ancestor> {
+        self.ancestors.last()
+    }
+
+    /// Whether the element is enclosed in an element of the given kind.
+    pub fn within(&self, kind: NodeKind) -> bool {
+        self.ancestors.iter().any(|it| it.kind == kind)
+    }
+
+    pub fn previous_sibling(&self) -> Option<&'a T> {
+        self.previous.last()
+    }
+
+    pub fn next_sibling(&self) -> Option<&'a T> {
+        self.next.first()
+    }
+}
+
+/// Calls `f` on every block in `blocks`, in the `traversal` order, with its context.
+pub fn walk_blocks<F>(blocks: &mut [Block], traversal: Traversal, f: &mut F) where F: FnMut(&mut Block, &Context<Block>) {
+    let mut walker = Walker { traversal, ancestors: Vec::new(), section_level: None, block: Some(f), inline: None };
+    walker.blocks(blocks);
+}
+
+/// Calls `f` on every inline in `blocks`, in the `traversal` order, with its context.
+pub fn walk_inlines<F>(blocks: &mut [Block], traversal: Traversal, f: &mut F) where F: FnMut(&mut Inline, &Context<Inline>) {
+    let mut walker = Walker { traversal, ancestors: Vec::new(), section_level: None, block: None, inline: Some(f) };
+    walker.blocks(blocks);
+}
+
+type BlockCallback<'f> = &'f mut dyn FnMut(&mut Block, &Context<Block>);
+type InlineCallback<'f> = &'f mut dyn FnMut(&mut Inline, &Context<Inline>);
+
+/// The walk of the functions above. It is written out rather than built on the derived walks,
+/// which give their callback an element alone: the context needs the list around the element for
+/// its siblings, and the nodes entered on the way down to it, captions and cells included, that
+/// the derived walks go through without a callback. The matches list every variant, so that a
+/// variant added to the definitions has to be handled here.
+struct Walker<'f> {
+    traversal: Traversal,
+    ancestors: Vec<Ancestor>,
+    section_level: Option<u8>,
+    block: Option<BlockCallback<'f>>,
+    inline: Option<InlineCallback<'f>>,
+}
+
+impl<'f> Walker<'f> {
+    fn blocks(&mut self, blocks: &mut [Block]) {
+        for index in 0..blocks.len() {
+            let (previous, rest) = blocks.split_at_mut(index);
+            let (block, next) = rest.split_first_mut().unwrap();
+            if let Header(level, ..) = block {
+                self.section_level = Some(*level);
+            }
+            if self.traversal == Traversal::TopDown {
+                self.visit_block(block, index, previous, next);
+            }
+            let attr = block_attr(block).map(mem::take);
+            self.ancestors.push(Ancestor { kind: NodeKind::Block(block.kind()), attr, index });
+            let section_level = self.section_level;
+            self.block_children(block);
+            self.section_level = section_level;
+            restore(block_attr(block), self.ancestors.pop());
+            if self.traversal == Traversal::BottomUp {
+                self.visit_block(block, index, previous, next);
+            }
+        }
+    }
+
+    fn visit_block(&mut self, block: &mut Block, index: usize, previous: &[Block], next: &[Block]) {
+        if let Some(f) = &mut self.block {
+            f(block, &Context { ancestors: &self.ancestors, section_level: self.section_level, index, previous, next });
+        }
+    }
+
+    fn block_children(&mut self, block: &mut Block) {
+        match block {
+            Plain(ils) | Para(ils) | Header(_, _, ils) => self.inlines(ils),
+            LineBlock(ilss) => ilss.iter_mut().for_each(|it| self.inlines(it)),
+            BlockQuote(blks) | Div(_, blks) => self.blocks(blks),
+            OrderedList(_, items) | BulletList(items) => items.iter_mut().for_each(|it| self.blocks(it)),
+            DefinitionList(definitions) => for definition in definitions {
+                self.inlines(&mut definition.0);
+                definition.1.iter_mut().for_each(|it| self.blocks(it));
+            },
+            Table(_, caption, _, t_head, t_bodies, t_foot) => {
+                self.caption(caption);
+                self.rows(&mut t_head.1);
+                for TableBody(_, _, head, body) in t_bodies {
+                    self.rows(head);
+                    self.rows(body);
+                }
+                self.rows(&mut t_foot.1);
+            },
+            Figure(_, caption, blks) => {
+                self.caption(caption);
+                self.blocks(blks);
+            },
+            CodeBlock(..) | RawBlock(..) | HorizontalRule => {}
+        }
+    }
+
+    fn caption(&mut self, caption: &mut Caption) {
+        self.ancestors.push(Ancestor { kind: NodeKind::Caption, attr: None, index: 0 });
+        if let Some(ils) = &mut caption.0 {
+            self.inlines(ils);
+        }
+        self.blocks(&mut caption.1);
+        self.ancestors.pop();
+    }
+
+    fn rows(&mut self, rows: &mut [Row]) {
+        for Row(_, cells) in rows {
+            for (index, Cell(attr, _, _, _, blks)) in cells.iter_mut().enumerate() {
+                self.ancestors.push(Ancestor { kind: NodeKind::Cell, attr: Some(mem::take(attr)), index });
+                self.blocks(blks);
+                restore(Some(attr), self.ancestors.pop());
+            }
+        }
+    }
+
+    fn inlines(&mut self, inlines: &mut [Inline]) {
+        for index in 0..inlines.len() {
+            let (previous, rest) = inlines.split_at_mut(index);
+            let (inline, next) = rest.split_first_mut().unwrap();
+            if self.traversal == Traversal::TopDown {
+                self.visit_inline(inline, index, previous, next);
+            }
+            let attr = inline_attr(inline).map(mem::take);
+            self.ancestors.push(Ancestor { kind: NodeKind::Inline(inline.kind()), attr, index });
+            self.inline_children(inline);
+            restore(inline_attr(inline), self.ancestors.pop());
+            if self.traversal == Traversal::BottomUp {
+                self.visit_inline(inline, index, previous, next);
+            }
+        }
+    }
+
+    fn visit_inline(&mut self, inline: &mut Inline, index: usize, previous: &[Inline], next: &[Inline]) {
+        if let Some(f) = &mut self.inline {
+            f(inline, &Context { ancestors: &self.ancestors, section_level: self.section_level, index, previous, next });
+        }
+    }
+
+    fn inline_children(&mut self, inline: &mut Inline) {
+        match inline {
+            Emph(ils) | Underline(ils) | Strong(ils) | Strikeout(ils) | Superscript(ils) | Subscript(ils) | SmallCaps(ils)
+            | Quoted(_, ils) | Link(_, ils, _) | Image(_, ils, _) | Span(_, ils) => self.inlines(ils),
+            Cite(citations, ils) => {
+                for citation in citations {
+                    self.inlines(&mut citation.citation_prefix);
+                    self.inlines(&mut citation.citation_suffix);
+                }
+                self.inlines(ils);
+            },
+            Note(blks) => self.blocks(blks),
+            Str(_) | Code(..) | Space | SoftBreak | LineBreak | Math(..) | RawInline(..) => {}
+        }
+    }
+}
+
+/// The attributes of a block having some, moved to its [`Ancestor`] while its children are walked.
+fn block_attr(block: &mut Block) -> Option<&mut Attr> {
+    match block {
+        CodeBlock(attr, _) | Header(_, attr, _) | Table(attr, ..) | Figure(attr, ..) | Div(attr, _) => Some(attr),
+        Plain(_) | Para(_) | LineBlock(_) | RawBlock(..) | BlockQuote(_) | OrderedList(..) | BulletList(_) | DefinitionList(_) | HorizontalRule => None
+    }
+}
+
+fn inline_attr(inline: &mut Inline) -> Option<&mut Attr> {
+    match inline {
+        Code(attr, _) | Link(attr, ..) | Image(attr, ..) | Span(attr, _) => Some(attr),
+        Str(_) | Emph(_) | Underline(_) | Strong(_) | Strikeout(_) | Superscript(_) | Subscript(_) | SmallCaps(_) | Quoted(..) | Cite(..)
+        | Space | SoftBreak | LineBreak | Math(..) | RawInline(..) | Note(_) => None
+    }
+}
+
+/// Gives back to a node the attributes moved to its ancestor.
+fn restore(slot: Option<&mut Attr>, ancestor: Option<Ancestor>) {
+    if let (Some(slot), Some(Ancestor { attr: Some(attr), .. })) = (slot, ancestor) {
+        *slot = attr;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Alignment, Attr, Block, BlockKind, Caption, Cell, InlineKind, Row, TableBody, TableFoot, TableHead};
+    use crate::definition::Block::{BulletList, Div, Figure, Header, Para, Plain, Table};
+    use crate::definition::Inline::{self, Note, Space, Span, Str};
+    use crate::traversal::Traversal;
+    use crate::utils::stringify;
+    use super::{walk_blocks, walk_inlines, Ancestor, NodeKind};
+
+    fn para(text: &str) -> Block {
+        Para(vec![Inline::Str(text.into())])
+    }
+
+    fn note(blocks: Vec<Block>) -> Block {
+        Para(vec![Inline::Note(blocks)])
+    }
+
+    #[test]
+    fn nested_headers_do_not_leak_their_level() {
+        let mut blocks = vec![
+            para("before"),
+            Header(1, Attr::default(), vec![]),
+            Div(Attr::default(), vec![Header(3, Attr::default(), vec![]), para("in div")]),
+            note(vec![Header(4, Attr::default(), vec![]), para("in note")]),
+            para("after"),
+            Header(2, Attr::default(), vec![]),
+            para("last"),
+        ];
+        for traversal in [Traversal::TopDown, Traversal::BottomUp] {
+            let mut levels = Vec::new();
+            walk_blocks(&mut blocks, traversal, &mut |block: &mut Block, context| {
+                if let Para(ils) = block {
+                    if let [Inline::Str(text)] = ils.as_slice() {
+                        levels.push((text.clone(), context.section_level));
+                    }
+                }
+            });
+            let expected = [("before", None), ("in div", Some(3)), ("in note", Some(4)), ("after", Some(1)), ("last", Some(2))];
+            assert_eq!(levels, expected.iter().map(|(text, level)| (text.to_string(), *level)).collect::<Vec<_>>());
+        }
+    }
+
+    fn attr(id: &str) -> Attr {
+        Attr { id: id.into(), classes: vec!["c".into()], attributes: vec![] }
+    }
+
+    fn str(text: &str) -> Inline {
+        Str(text.into())
+    }
+
+    /// The kind, id and index of an ancestor.
+    type Link = (NodeKind, Option<String>, usize);
+
+    /// The ancestors of each `Str`, by its text.
+    fn ancestors(blocks: &mut [Block], traversal: Traversal) -> Vec<(String, Vec<Link>)> {
+        let mut ancestors = Vec::new();
+        walk_inlines(blocks, traversal, &mut |inline: &mut Inline, context| {
+            if let Str(text) = inline {
+                let chain = context.ancestors.iter().map(|Ancestor { kind, attr, index }| (*kind, attr.as_ref().map(|it| it.id.clone()), *index)).collect();
+                ancestors.push((text.clone(), chain));
+            }
+        });
+        ancestors
+    }
+
+    #[test]
+    fn ancestors_are_given_with_their_attributes() {
+        let link = Inline::Link(attr("link"), vec![str("x")], ("#x".into(), String::new()));
+        let mut blocks = vec![para("first"), Div(attr("div"), vec![BulletList(vec![vec![], vec![Para(vec![str("y"), Span(attr("span"), vec![link])])]])])];
+        for traversal in [Traversal::TopDown, Traversal::BottomUp] {
+            let chain = vec![
+                (NodeKind::Block(BlockKind::Div), Some("div".to_string()), 1),
+                (NodeKind::Block(BlockKind::BulletList), None, 0),
+                (NodeKind::Block(BlockKind::Para), None, 0),
+                (NodeKind::Inline(InlineKind::Span), Some("span".to_string()), 1),
+                (NodeKind::Inline(InlineKind::Link), Some("link".to_string()), 0),
+            ];
+            assert_eq!(ancestors(&mut blocks, traversal), [
+                ("first".to_string(), vec![(NodeKind::Block(BlockKind::Para), None, 0)]),
+                ("y".to_string(), chain[..3].to_vec()),
+                ("x".to_string(), chain),
+            ]);
+            // The attributes are back in place after the walk.
+            assert!(matches!(&blocks[1], Div(attr, _) if attr.id == "div" && attr.classes == ["c"]));
+        }
+    }
+
+    #[test]
+    fn siblings_are_the_walked_and_unwalked_elements_around() {
+        let mut blocks = vec![Para(vec![str("a"), Space, str("b"), Space, str("c")])];
+        let mut seen = Vec::new();
+        walk_inlines(&mut blocks, Traversal::TopDown, &mut |inline: &mut Inline, context| {
+            if let Str(text) = inline {
+                let previous = context.previous_sibling().map(stringify);
+                let next = context.next_sibling().map(stringify);
+                seen.push((context.index, stringify(context.previous), previous, next));
+                *text = text.to_uppercase();
+            }
+        });
+        assert_eq!(seen, [
+            (0, "".to_string(), None, Some(" ".to_string())),
+            (2, "A ".to_string(), Some(" ".to_string()), Some(" ".to_string())),
+            (4, "A B ".to_string(), Some(" ".to_string()), None),
+        ]);
+
+        let mut blocks = vec![para("a"), para("b"), para("c")];
+        let mut seen = Vec::new();
+        walk_blocks(&mut blocks, Traversal::BottomUp, &mut |block: &mut Block, context| {
+            seen.push((context.index, context.previous_sibling().map(stringify), context.next_sibling().map(stringify), context.parent().is_none()));
+            *block = para("z");
+        });
+        assert_eq!(seen, [
+            (0, None, Some("b".to_string()), true),
+            (1, Some("z".to_string()), Some("c".to_string()), true),
+            (2, Some("z".to_string()), None, true),
+        ]);
+    }
+
+    #[test]
+    fn captions_cells_and_notes_are_ancestors() {
+        let cell = |id: &str, text: &str| Cell(attr(id), Alignment::AlignDefault, 1, 1, vec![Plain(vec![str(text)])]);
+        let table = Table(
+            attr("table"),
+            Caption(None, vec![para("caption")]),
+            vec![],
+            TableHead(Attr::default(), vec![Row(Attr::default(), vec![cell("h1", "head"), cell("h2", "second")])]),
+            vec![TableBody(Attr::default(), 0, vec![], vec![Row(Attr::default(), vec![cell("b1", "body")])])],
+            TableFoot(Attr::default(), vec![]),
+        );
+        let figure = Figure(attr("figure"), Caption(Some(vec![str("short")]), vec![]), vec![para("image")]);
+        let mut blocks = vec![table, figure, Para(vec![Note(vec![para("note")])])];
+        let kinds = ancestors(&mut blocks, Traversal::TopDown);
+        let table = (NodeKind::Block(BlockKind::Table), Some("table".to_string()), 0);
+        let figure = (NodeKind::Block(BlockKind::Figure), Some("figure".to_string()), 1);
+        let plain = (NodeKind::Block(BlockKind::Plain), None, 0);
+        let cell = |id: &str, index| (NodeKind::Cell, Some(id.to_string()), index);
+        assert_eq!(kinds, [
+            ("caption".to_string(), vec![table.clone(), (NodeKind::Caption, None, 0), (NodeKind::Block(BlockKind::Para), None, 0)]),
+            ("head".to_string(), vec![table.clone(), cell("h1", 0), plain.clone()]),
+            ("second".to_string(), vec![table.clone(), cell("h2", 1), plain.clone()]),
+            ("body".to_string(), vec![table, cell("b1", 0), plain]),
+            ("short".to_string(), vec![figure.clone(), (NodeKind::Caption, None, 0)]),
+            ("image".to_string(), vec![figure, (NodeKind::Block(BlockKind::Para), None, 0)]),
+            ("note".to_string(), vec![
+                (NodeKind::Block(BlockKind::Para), None, 2),
+                (NodeKind::Inline(InlineKind::Note), None, 0),
+                (NodeKind::Block(BlockKind::Para), None, 0),
+            ]),
+        ]);
+        assert!(matches!(&blocks[0], Table(_, _, _, TableHead(_, rows), ..) if rows[0].1[1].0.id == "h2"));
+    }
+}
@@ -0,0 +1,610 @@
+//! Numbering of and references to figures, tables, equations and sections, in the manner of
+//! pandoc-crossref.
+//!
+//! Elements are labelled through their identifier: `fig:` for a `Figure`, `tbl:` for a `Table`,
+//! `sec:` for a `Header`, and `eq:` for a display math either wrapped in a `Span`
+//! (`[$$E = mc^2$$]{#eq:energy}`) or followed by its label (`$$E = mc^2$$ {#eq:energy}`).
+//! Citations of labels, like `@fig:plot` or `[@tbl:a; @tbl:b]`, become links; `-@fig:plot`
+//! omits the prefix and `@Fig:plot` capitalizes it.
+//!
+//! The metadata configure the output, with pandoc-crossref's names:
+//!
+//! | Key | Default |
+//! |-----|---------|
+//! | `figPrefix`, `tblPrefix`, `eqnPrefix`, `secPrefix` | `fig.`, `tbl.`, `eq.`, `sec.` (a list gives the singular and plural forms) |
+//! | `figureTitle`, `tableTitle` | `Figure`, `Table` |
+//! | `titleDelim` | `:` |
+//! | `lofTitle`, `lotTitle` | `List of Figures`, `List of Tables` |
+//! | `chapters` | `false`; numbers figures, tables and equations per level 1 section |
+//!
+//! ```no_run
+//! use pandoc::crossref::crossref;
+//! use pandoc::definition::Pandoc;
+//! use pandoc::to_json_filter;
+//!
+//! to_json_filter(&mut |pandoc: Pandoc| crossref(pandoc)).unwrap();
+//! ```
+
+use std::collections::HashMap;
+use crate::context;
+use crate::definition::{Attr, Block, Caption, Citation, CitationMode, Inline, MathType, Meta, MetaValue, Pandoc};
+use crate::definition::Block::{Div, Figure, Header, Para, Plain, RawBlock, Table};
+use crate::definition::Inline::{Cite, Link, Math, Space, Span, Str};
+use crate::definition::MetaValue::{MetaBool, MetaInlines, MetaList, MetaString};
+use crate::traversal::{self, Traversal};
+use crate::utils::stringify;
+
+/// Numbers the labelled elements of the document then resolves the references to them.
+pub fn crossref(pandoc: Pandoc) -> Pandoc {
+    let mut crossref = Crossref::new(&pandoc.meta);
+    let pandoc = crossref.number(pandoc);
+    crossref.resolve(pandoc)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Figure,
+    Table,
+    Equation,
+    Section,
+}
+
+impl Kind {
+    /// Kind of the element labelled `label`.
+    pub fn of(label: &str) -> Option<Kind> {
+        let (prefix, _) = label.split_once(':')?;
+        match prefix.to_lowercase().as_str() {
+            "fig" => Some(Kind::Figure),
+            "tbl" => Some(Kind::Table),
+            "eq" => Some(Kind::Equation),
+            "sec" => Some(Kind::Section),
+            _ => None,
+        }
+    }
+
+    /// The metadata key of the prefix of the references to this kind, with its default singular
+    /// and plural forms.
+    fn prefix_key(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Kind::Figure => ("figPrefix", "fig.", "figs."),
+            Kind::Table => ("tblPrefix", "tbl.", "tbls."),
+            Kind::Equation => ("eqnPrefix", "eq.", "eqns."),
+            Kind::Section => ("secPrefix", "sec.", "secs."),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Reference {
+    pub kind: Kind,
+    /// The label, without `#`.
+    pub id: String,
+    /// Number, e.g. `3` or `2.1` with chapters.
+    pub number: String,
+    /// Caption of a figure or a table, title of a section.
+    pub caption: Vec<Inline>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Prefix {
+    pub singular: Vec<Inline>,
+    pub plural: Vec<Inline>,
+}
+
+impl Prefix {
+    /// The prefix of the references to `kind` when the metadata do not set it.
+    pub fn default_of(kind: Kind) -> Self {
+        let (_, singular, plural) = kind.prefix_key();
+        Prefix { singular: text(singular), plural: text(plural) }
+    }
+}
+
+/// Output settings, read from the metadata. A kind missing from `prefixes` takes its default
+/// prefix.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub prefixes: HashMap<Kind, Prefix>,
+    pub figure_title: Vec<Inline>,
+    pub table_title: Vec<Inline>,
+    pub title_delim: Vec<Inline>,
+    pub lof_title: Vec<Inline>,
+    pub lot_title: Vec<Inline>,
+    pub chapters: bool,
+}
+
+impl Settings {
+    pub fn from_meta(meta: &Meta) -> Self {
+        let inlines = |key: &str, default: &str| meta.get(key).and_then(meta_inlines).unwrap_or_else(|| text(default));
+        let prefix = |kind: Kind| {
+            let (key, singular, plural) = kind.prefix_key();
+            match meta.get(key) {
+                Some(MetaList(values)) if values.len() >= 2 => Prefix {
+                    singular: meta_inlines(&values[0]).unwrap_or_else(|| text(singular)),
+                    plural: meta_inlines(&values[1]).unwrap_or_else(|| text(plural)),
+                },
+                Some(value) => {
+                    let inlines = meta_inlines(value).unwrap_or_else(|| text(singular));
+                    Prefix { singular: inlines.clone(), plural: inlines }
+                },
+                None => Prefix::default_of(kind),
+            }
+        };
+        Settings {
+            prefixes: [Kind::Figure, Kind::Table, Kind::Equation, Kind::Section].iter().map(|kind| (*kind, prefix(*kind))).collect(),
+            figure_title: inlines("figureTitle", "Figure"),
+            table_title: inlines("tableTitle", "Table"),
+            title_delim: inlines("titleDelim", ":"),
+            lof_title: inlines("lofTitle", "List of Figures"),
+            lot_title: inlines("lotTitle", "List of Tables"),
+            chapters: match meta.get("chapters") {
+                Some(MetaBool(value)) => *value,
+                Some(value) => stringify(value) == "true",
+                None => false,
+            },
+        }
+    }
+}
+
+/// The references of a document, collected by [`Crossref::number`] and used by
+/// [`Crossref::resolve`].
+#[derive(Clone, Debug)]
+pub struct Crossref {
+    pub settings: Settings,
+    references: HashMap<String, Reference>,
+    figures: Vec<String>,
+    tables: Vec<String>,
+}
+
+impl Crossref {
+    pub fn new(meta: &Meta) -> Self {
+        Crossref { settings: Settings::from_meta(meta), references: HashMap::new(), figures: Vec::new(), tables: Vec::new() }
+    }
+
+    pub fn get(&self, label: &str) -> Option<&Reference> {
+        self.references.get(label)
+    }
+
+    /// Numbers the labelled elements in document order, adding the number to the captions of
+    /// figures and tables and to the equations.
+    pub fn number(&mut self, mut pandoc: Pandoc) -> Pandoc {
+        let mut sections = [0usize; 6];
+        let mut counters: HashMap<Kind, usize> = HashMap::new();
+        context::walk_blocks(&mut pandoc.blocks, Traversal::TopDown, &mut |block: &mut Block, _| {
+            match block {
+                Header(level, attr, ils) if !attr.classes.iter().any(|it| it == "unnumbered") => {
+                    let level = (*level as usize).clamp(1, 6);
+                    sections[level - 1] += 1;
+                    sections[level..].iter_mut().for_each(|it| *it = 0);
+                    if level == 1 && self.settings.chapters {
+                        counters.clear();
+                    }
+                    if Kind::of(&attr.id) == Some(Kind::Section) {
+                        let number = sections[..level].iter().map(|it| it.to_string()).collect::<Vec<_>>().join(".");
+                        self.insert(Kind::Section, &attr.id, number, ils.clone());
+                    }
+                },
+                Figure(attr, caption, _) if Kind::of(&attr.id) == Some(Kind::Figure) => {
+                    let number = self.next_number(&mut counters, Kind::Figure, sections[0]);
+                    self.insert(Kind::Figure, &attr.id, number.clone(), caption_inlines(caption));
+                    self.figures.push(attr.id.clone());
+                    let title = self.settings.figure_title.clone();
+                    self.prefix_caption(caption, title, &number);
+                },
+                Table(attr, caption, ..) if Kind::of(&attr.id) == Some(Kind::Table) => {
+                    let number = self.next_number(&mut counters, Kind::Table, sections[0]);
+                    self.insert(Kind::Table, &attr.id, number.clone(), caption_inlines(caption));
+                    self.tables.push(attr.id.clone());
+                    let title = self.settings.table_title.clone();
+                    self.prefix_caption(caption, title, &number);
+                },
+                Plain(ils) | Para(ils) => {
+                    for (label, math) in equations(ils) {
+                        let number = self.next_number(&mut counters, Kind::Equation, sections[0]);
+                        self.insert(Kind::Equation, &label, number.clone(), vec![]);
+                        math.push_str(&format!("\\qquad({})", number));
+                    }
+                },
+                _ => {}
+            }
+        });
+        pandoc
+    }
+
+    /// Replaces the citations of labels by links to the elements, and the `\listoffigures`
+    /// and `\listoftables` raw blocks by the lists.
+    pub fn resolve(&self, pandoc: Pandoc) -> Pandoc {
+        let pandoc = traversal::walk_inlines(pandoc, Traversal::BottomUp, &mut |inline| match inline {
+            Cite(citations, _) if citations.iter().all(|it| Kind::of(&it.citation_id).is_some()) => self.cite(&citations),
+            _ => vec![inline],
+        });
+        traversal::walk_blocks(pandoc, Traversal::BottomUp, &mut |block| match &block {
+            RawBlock(format, text) if matches!(format.as_str(), "latex" | "tex") => match text.trim() {
+                "\\listoffigures" => self.list_of_figures(),
+                "\\listoftables" => self.list_of_tables(),
+                _ => vec![block],
+            },
+            _ => vec![block],
+        })
+    }
+
+    pub fn list_of_figures(&self) -> Vec<Block> {
+        self.list("list-of-figures", &self.settings.lof_title, &self.figures)
+    }
+
+    pub fn list_of_tables(&self) -> Vec<Block> {
+        self.list("list-of-tables", &self.settings.lot_title, &self.tables)
+    }
+
+    fn list(&self, class: &str, title: &[Inline], labels: &[String]) -> Vec<Block> {
+        let mut blocks = vec![Header(1, Attr { classes: vec!["unnumbered".to_string()], ..Attr::default() }, title.to_vec())];
+        for reference in labels.iter().filter_map(|it| self.references.get(it)) {
+            let mut entry = vec![link(reference), Str(":".to_string()), Space];
+            entry.extend(reference.caption.iter().cloned());
+            blocks.push(Para(entry));
+        }
+        vec![Div(Attr { classes: vec![class.to_string()], ..Attr::default() }, blocks)]
+    }
+
+    fn insert(&mut self, kind: Kind, label: &str, number: String, caption: Vec<Inline>) {
+        self.references.insert(label.to_string(), Reference { kind, id: label.to_string(), number, caption });
+    }
+
+    fn next_number(&self, counters: &mut HashMap<Kind, usize>, kind: Kind, chapter: usize) -> String {
+        let counter = counters.entry(kind).or_insert(0);
+        *counter += 1;
+        if self.settings.chapters && chapter > 0 {
+            format!("{}.{}", chapter, counter)
+        } else {
+            counter.to_string()
+        }
+    }
+
+    /// Turns `caption` into `Figure 1: caption`.
+    fn prefix_caption(&self, caption: &mut Caption, title: Vec<Inline>, number: &str) {
+        let mut prefix = title;
+        prefix.push(Space);
+        prefix.push(Str(number.to_string()));
+        match caption.1.first_mut() {
+            Some(Plain(ils)) | Some(Para(ils)) => {
+                prefix.extend(self.settings.title_delim.iter().cloned());
+                prefix.push(Space);
+                ils.splice(0..0, prefix);
+            },
+            _ => caption.1.insert(0, Plain(prefix)),
+        }
+    }
+
+    fn cite(&self, citations: &[Citation]) -> Vec<Inline> {
+        let mut result = Vec::new();
+        let mut groups: Vec<Vec<&Citation>> = Vec::new();
+        for citation in citations {
+            match groups.last_mut() {
+                Some(group) if Kind::of(&group[0].citation_id) == Kind::of(&citation.citation_id) => group.push(citation),
+                _ => groups.push(vec![citation]),
+            }
+        }
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                result.push(Str(",".to_string()));
+                result.push(Space);
+            }
+            let first = group[0];
+            result.extend(first.citation_prefix.iter().cloned());
+            if !first.citation_prefix.is_empty() {
+                result.push(Space);
+            }
+            if !matches!(first.citation_mode, CitationMode::SuppressAuthor) {
+                let kind = Kind::of(&first.citation_id).unwrap();
+                let prefix = self.settings.prefixes.get(&kind).cloned().unwrap_or_else(|| Prefix::default_of(kind));
+                let mut prefix = if group.len() > 1 { prefix.plural } else { prefix.singular };
+                if first.citation_id.starts_with(char::is_uppercase) {
+                    capitalize(&mut prefix);
+                }
+                result.extend(prefix);
+                result.push(Str("\u{a0}".to_string()));
+            }
+            for (j, citation) in group.iter().enumerate() {
+                if j > 0 {
+                    result.push(Str(",".to_string()));
+                    result.push(Space);
+                }
+                let label = lowercase_prefix(&citation.citation_id);
+                match self.references.get(&label) {
+                    Some(reference) => result.push(link(reference)),
+                    None => result.push(Str(format!("¿{}?", label))),
+                }
+                if !citation.citation_suffix.is_empty() {
+                    result.extend(citation.citation_suffix.iter().cloned());
+                }
+            }
+        }
+        result
+    }
+}
+
+fn link(reference: &Reference) -> Inline {
+    Link(Attr::default(), vec![Str(reference.number.clone())], (format!("#{}", reference.id), String::new()))
+}
+
+/// Finds the labelled equations among `inlines`, wrapping in a `Span` those followed by their
+/// label, and returns their labels and their TeX.
+fn equations(inlines: &mut Vec<Inline>) -> Vec<(String, &mut String)> {
+    let mut i = 0;
+    while i < inlines.len() {
+        if let Math(MathType::DisplayMath, _) = &inlines[i] {
+            let label_at = match inlines.get(i + 1) {
+                Some(Space) => i + 2,
+                _ => i + 1,
+            };
+            let label = match inlines.get(label_at) {
+                Some(Str(text)) => text.strip_prefix("{#").and_then(|it| it.strip_suffix('}')).filter(|it| Kind::of(it) == Some(Kind::Equation)).map(str::to_string),
+                _ => None,
+            };
+            if let Some(label) = label {
+                let math = inlines.remove(i);
+                inlines.drain(i..label_at);
+                inlines.insert(i, Span(Attr { id: label, ..Attr::default() }, vec![math]));
+            }
+        }
+        i += 1;
+    }
+    inlines.iter_mut()
+        .filter_map(|inline| match inline {
+            Span(attr, ils) if Kind::of(&attr.id) == Some(Kind::Equation) => match ils.as_mut_slice() {
+                [Math(MathType::DisplayMath, math)] => Some((attr.id.clone(), math)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn caption_inlines(caption: &Caption) -> Vec<Inline> {
+    match caption.1.first() {
+        Some(Plain(ils)) | Some(Para(ils)) => ils.clone(),
+        _ => vec![],
+    }
+}
+
+fn meta_inlines(value: &MetaValue) -> Option<Vec<Inline>> {
+    match value {
+        MetaInlines(ils) => Some(ils.clone()),
+        MetaString(value) => Some(text(value)),
+        _ => None,
+    }
+}
+
+fn text(text: &str) -> Vec<Inline> {
+    let mut result = Vec::new();
+    for (i, word) in text.split(' ').enumerate() {
+        if i > 0 {
+            result.push(Space);
+        }
+        if !word.is_empty() {
+            result.push(Str(word.to_string()));
+        }
+    }
+    result
+}
+
+fn capitalize(inlines: &mut [Inline]) {
+    if let Some(Str(text)) = inlines.first_mut() {
+        let mut chars = text.chars();
+        if let Some(first) = chars.next() {
+            *text = first.to_uppercase().chain(chars).collect();
+        }
+    }
+}
+
+/// `Fig:x` refers to `fig:x`.
+fn lowercase_prefix(label: &str) -> String {
+    match label.split_once(':') {
+        Some((prefix, rest)) => format!("{}:{}", prefix.to_lowercase(), rest),
+        None => label.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attr;
+    use crate::builder::{display_math, doc, figure_with, header, header_with, para, raw_block, row, simple_caption, simple_cell, span, str, text, Blocks, Inlines, TableBuilder};
+    use crate::definition::{Block, Citation, CitationMode, Inline, MathType, Meta, MetaValue, Pandoc};
+    use crate::definition::Block::{Div, Figure, Header, Para, Table};
+    use crate::definition::Inline::{Cite, Math, Span};
+    use crate::utils::stringify;
+    use super::{crossref, Crossref, Kind};
+
+    fn citation(id: &str, mode: CitationMode) -> Citation {
+        Citation { citation_id: id.into(), citation_prefix: vec![], citation_suffix: vec![], citation_mode: mode, citation_note_num: 0, citation_hash: 0 }
+    }
+
+    fn cite(ids: &[&str]) -> Inlines {
+        let citations = ids.iter().map(|id| citation(id, CitationMode::NormalCitation)).collect();
+        Cite(citations, vec![]).into()
+    }
+
+    fn labelled_figure(id: &str, caption: &str) -> Blocks {
+        figure_with(attr!(id), simple_caption(caption), para("image"))
+    }
+
+    fn labelled_table(id: &str, caption: &str) -> Blocks {
+        TableBuilder::new().attr(attr!(id)).caption(simple_caption(caption)).row(row(vec![simple_cell("cell")])).build()
+    }
+
+    fn numbers(crossref: &Crossref, labels: &[&str]) -> Vec<String> {
+        labels.iter().map(|it| crossref.get(it).map_or_else(|| "none".to_string(), |it| it.number.clone())).collect()
+    }
+
+    fn with_meta(mut pandoc: Pandoc, entries: Vec<(&str, MetaValue)>) -> Pandoc {
+        pandoc.meta = entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect::<Meta>();
+        pandoc
+    }
+
+    /// The text of the paragraphs of `blocks`.
+    fn paragraphs(blocks: &[Block]) -> Vec<String> {
+        blocks.iter().filter_map(|block| match block {
+            Para(ils) => Some(stringify(ils)),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn elements_are_numbered_in_document_order() {
+        let pandoc = doc(
+            header_with(attr!("sec:intro"), 1, "Intro")
+            + labelled_figure("fig:a", "First")
+            + header_with(attr!("sec:details"), 2, "Details")
+            + labelled_table("tbl:a", "Sizes")
+            + labelled_figure("fig:b", "Second")
+            + header_with(attr!("sec:skipped"; "unnumbered"), 2, "Skipped")
+            + labelled_figure("", "Unlabelled")
+            + header_with(attr!("sec:next"), 1, "Next")
+            + header_with(attr!("sec:deep"), 3, "Deep"),
+        );
+        let mut crossref = Crossref::new(&Meta::new());
+        let pandoc = crossref.number(pandoc);
+        assert_eq!(numbers(&crossref, &["fig:a", "fig:b", "tbl:a", "sec:intro", "sec:details", "sec:skipped", "sec:next", "sec:deep"]), ["1", "2", "1", "1", "1.1", "none", "2", "2.0.1"]);
+        assert_eq!(stringify(&crossref.get("sec:details").unwrap().caption), "Details");
+        assert_eq!(stringify(&crossref.get("fig:b").unwrap().caption), "Second");
+
+        let captions: Vec<String> = pandoc.blocks.iter().filter_map(|block| match block {
+            Figure(_, caption, _) | Table(_, caption, ..) => Some(stringify(&caption.1)),
+            _ => None,
+        }).collect();
+        assert_eq!(captions, ["Figure 1: First", "Table 1: Sizes", "Figure 2: Second", "Unlabelled"]);
+    }
+
+    #[test]
+    fn chapters_number_per_level_one_section() {
+        let pandoc = with_meta(doc(
+            labelled_figure("fig:before", "Before")
+            + header(1, "One")
+            + labelled_figure("fig:a", "A")
+            + labelled_table("tbl:a", "A")
+            + header(2, "Section")
+            + labelled_figure("fig:b", "B")
+            + header(1, "Two")
+            + labelled_figure("fig:c", "C")
+            + para(span(attr!("eq:c"), display_math("c"))),
+        ), vec![("chapters", MetaValue::MetaBool(true))]);
+        let mut crossref = Crossref::new(&pandoc.meta);
+        crossref.number(pandoc);
+        assert_eq!(numbers(&crossref, &["fig:before", "fig:a", "tbl:a", "fig:b", "fig:c", "eq:c"]), ["1", "1.1", "1.1", "1.2", "2.1", "2.1"]);
+    }
+
+    #[test]
+    fn equations_are_wrapped_and_numbered() {
+        let pandoc = doc(
+            para(display_math("a") + text(" {#eq:a}"))
+            + para(text("inline ") + display_math("b") + str("{#eq:b}") + text(" after"))
+            + para(span(attr!("eq:c"), display_math("c")))
+            + para(display_math("d") + text(" {#fig:d}"))
+            + para(display_math("e")),
+        );
+        let mut crossref = Crossref::new(&Meta::new());
+        let pandoc = crossref.number(pandoc);
+        let equation = |id: &str, tex: &str| Span(attr!(id), vec![Math(MathType::DisplayMath, tex.into())]);
+        let display = |tex: &str| Math(MathType::DisplayMath, tex.into());
+        let expected = vec![
+            Para(vec![equation("eq:a", "a\\qquad(1)")]),
+            Para(vec![Inline::Str("inline".into()), Inline::Space, equation("eq:b", "b\\qquad(2)"), Inline::Space, Inline::Str("after".into())]),
+            Para(vec![equation("eq:c", "c\\qquad(3)")]),
+            Para(vec![display("d"), Inline::Space, Inline::Str("{#fig:d}".into())]),
+            Para(vec![display("e")]),
+        ];
+        assert_eq!(serde_json::to_value(&pandoc.blocks).unwrap(), serde_json::to_value(&expected).unwrap());
+        assert_eq!(numbers(&crossref, &["eq:a", "eq:b", "eq:c"]), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn citations_are_grouped_by_kind() {
+        let pandoc = doc(
+            labelled_figure("fig:a", "A")
+            + labelled_figure("fig:b", "B")
+            + labelled_table("tbl:a", "A")
+            + header_with(attr!("sec:a"), 1, "A")
+            + para(cite(&["fig:a", "fig:b", "tbl:a", "fig:a"]))
+            + para(cite(&["fig:a"]))
+            + para(cite(&["Fig:a", "fig:b"]))
+            + para(cite(&["sec:a"]))
+            + para(Cite(vec![citation("fig:b", CitationMode::SuppressAuthor)], vec![]))
+            + para(cite(&["fig:missing"]))
+            + para(cite(&["fig:a", "doe"])),
+        );
+        let pandoc = crossref(pandoc);
+        assert_eq!(paragraphs(&pandoc.blocks), [
+            "figs.\u{a0}1, 2, tbl.\u{a0}1, fig.\u{a0}1",
+            "fig.\u{a0}1",
+            "Figs.\u{a0}1, 2",
+            "sec.\u{a0}1",
+            "2",
+            "fig.\u{a0}¿fig:missing?",
+            "",
+        ]);
+        assert!(matches!(pandoc.blocks.last(), Some(Para(ils)) if matches!(ils.as_slice(), [Cite(..)])));
+        let links: Vec<String> = pandoc.blocks.iter().filter_map(|block| match block {
+            Para(ils) => match ils.first() {
+                Some(Inline::Link(_, _, (url, _))) => Some(url.clone()),
+                _ => None,
+            },
+            _ => None,
+        }).collect();
+        assert_eq!(links, ["#fig:b"]);
+    }
+
+    #[test]
+    fn prefixes_are_read_from_the_metadata() {
+        let pandoc = with_meta(doc(
+            labelled_figure("fig:a", "A")
+            + labelled_figure("fig:b", "B")
+            + labelled_table("tbl:a", "A")
+            + para(cite(&["fig:a"]) + text(" ") + cite(&["fig:a", "fig:b"]) + text(" ") + cite(&["Tbl:a", "tbl:a"]))
+        ), vec![
+            ("figPrefix", MetaValue::MetaList(vec![MetaValue::MetaString("figure".into()), MetaValue::MetaString("figures".into())])),
+            ("tblPrefix", MetaValue::MetaInlines(text("table").into_vec())),
+            ("figureTitle", MetaValue::MetaString("Fig.".into())),
+            ("titleDelim", MetaValue::MetaString(".".into())),
+        ]);
+        let pandoc = crossref(pandoc);
+        assert_eq!(paragraphs(&pandoc.blocks).last().unwrap(), "figure\u{a0}1 figures\u{a0}1, 2 Table\u{a0}1, 1");
+        assert!(matches!(&pandoc.blocks[0], Figure(_, caption, _) if stringify(&caption.1) == "Fig. 1. A"));
+    }
+
+    #[test]
+    fn missing_prefixes_fall_back_to_the_defaults() {
+        let pandoc = doc(labelled_figure("fig:a", "A") + para(cite(&["fig:a"])));
+        let mut crossref = Crossref::new(&pandoc.meta);
+        crossref.settings.prefixes.remove(&Kind::Figure);
+        let pandoc = crossref.number(pandoc);
+        let pandoc = crossref.resolve(pandoc);
+        assert_eq!(paragraphs(&pandoc.blocks), ["fig.\u{a0}1"]);
+    }
+
+    #[test]
+    fn lists_of_figures_and_tables_replace_their_commands() {
+        let pandoc = doc(
+            raw_block("latex", "\\listoffigures\n")
+            + raw_block("tex", "\\listoftables")
+            + raw_block("html", "\\listoffigures")
+            + labelled_figure("fig:a", "First")
+            + labelled_figure("fig:b", "Second")
+            + labelled_table("tbl:a", "Sizes"),
+        );
+        let pandoc = crossref(pandoc);
+        let lists: Vec<(Vec<String>, Vec<String>)> = pandoc.blocks.iter().filter_map(|block| match block {
+            Div(attr, blocks) => match blocks.as_slice() {
+                [Header(1, header, title), entries @ ..] if header.classes == ["unnumbered"] => {
+                    Some((attr.classes.clone(), std::iter::once(stringify(title)).chain(paragraphs(entries)).collect()))
+                },
+                _ => None,
+            },
+            _ => None,
+        }).collect();
+        let strings = |it: &[&str]| it.iter().map(|it| it.to_string()).collect::<Vec<_>>();
+        assert_eq!(lists, [
+            (strings(&["list-of-figures"]), strings(&["List of Figures", "1: First", "2: Second"])),
+            (strings(&["list-of-tables"]), strings(&["List of Tables", "1: Sizes"])),
+        ]);
+        assert!(matches!(&pandoc.blocks[2], Block::RawBlock(format, _) if format == "html"));
+    }
+}
@@ -0,0 +1,225 @@
+//! Tables written as comma or tab separated values in code blocks.
+//!
+//! A code block with class `csv` or `tsv`, or with a `file` attribute naming a `.csv` or
+//! `.tsv` file, becomes a table. Its attributes configure the table and are removed from it:
+//!
+//! | Attribute | Meaning |
+//! |-----------|---------|
+//! | `file` | file to read the values from, instead of the content of the block |
+//! | `delimiter` | field separator, overriding the one of the class or of the file extension |
+//! | `header` | `yes` or `no`: whether the first record is the head row |
+//! | `align` | one letter per column among `l`, `c`, `r` and `d` (default), e.g. `lrr` |
+//! | `widths` | relative column widths, e.g. `0.5 0.25 0.25` |
+//! | `caption` | caption of the table |
+//!
+//! ````markdown
+//! ```{.csv #tbl:sizes caption="Sizes" align=lr}
+//! name,size
+//! a,10
+//! ```
+//! ````
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use crate::builder::{plain, text};
+use crate::definition::{Alignment, Attr, Block, Caption, ColSpec, ColWidth};
+use crate::table::Grid;
+
+/// Converts the CSV and TSV code blocks with the default options.
+pub fn csv_table(block: Block) -> crate::Result<Block> {
+    Options::default().convert(block)
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Whether the first record is the head row when the block does not say.
+    pub header: bool,
+    /// Directory the `file` attributes are relative to.
+    pub directory: PathBuf,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { header: true, directory: PathBuf::from(".") }
+    }
+}
+
+impl Options {
+    /// The table described by a CSV or TSV code block, other blocks being returned unchanged.
+    /// Fails when the `file` cannot be read.
+    pub fn convert(&self, block: Block) -> crate::Result<Block> {
+        let (mut attr, content) = match block {
+            Block::CodeBlock(attr, content) if is_table(&attr) => (attr, content),
+            _ => return Ok(block),
+        };
+        let file = take(&mut attr, "file");
+        let mut delimiter = if attr.classes.iter().any(|it| it == "tsv") || file.as_deref().is_some_and(|it| it.ends_with(".tsv")) { '\t' } else { ',' };
+        if let Some(value) = take(&mut attr, "delimiter") {
+            delimiter = match value.as_str() {
+                "\\t" | "tab" => '\t',
+                _ => value.chars().next().unwrap_or(delimiter),
+            };
+        }
+        let content = match &file {
+            Some(file) => {
+                let path = self.directory.join(file);
+                fs::read_to_string(&path).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?
+            },
+            None => content,
+        };
+        let header = take(&mut attr, "header").map_or(self.header, |it| matches!(it.to_lowercase().as_str(), "yes" | "true" | "1"));
+        let mut grid = Grid::from_csv(&content, delimiter, header);
+        if let Some(align) = take(&mut attr, "align") {
+            let alignments = align.chars().filter_map(|it| match it.to_ascii_lowercase() {
+                'l' => Some(Alignment::AlignLeft),
+                'c' => Some(Alignment::AlignCenter),
+                'r' => Some(Alignment::AlignRight),
+                'd' => Some(Alignment::AlignDefault),
+                _ => None,
+            });
+            grid.columns.iter_mut().zip(alignments).for_each(|(column, alignment)| column.0 = alignment);
+        }
+        if let Some(widths) = take(&mut attr, "widths") {
+            let widths: Vec<f64> = widths.split([' ', ',']).filter_map(|it| it.parse().ok()).collect();
+            let total: f64 = widths.iter().sum();
+            // Widths given in percents or in any unit are scaled to fractions of the text width.
+            let scale = if total > 1.0 { total } else { 1.0 };
+            grid.columns.iter_mut().zip(widths).for_each(|(column, width)| column.1 = ColWidth::ColWidth(width / scale));
+        }
+        if let Some(caption) = take(&mut attr, "caption") {
+            grid.caption = Caption(None, plain(text(&caption)).into_vec());
+        }
+        attr.classes.retain(|it| it != "csv" && it != "tsv");
+        grid.attr = attr;
+        if grid.columns.is_empty() {
+            grid.columns.push(ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault));
+        }
+        Ok(grid.into_block())
+    }
+}
+
+fn is_table(attr: &Attr) -> bool {
+    attr.classes.iter().any(|it| it == "csv" || it == "tsv")
+        || attr.attributes.iter().any(|(key, value)| key == "file" && (value.ends_with(".csv") || value.ends_with(".tsv")))
+}
+
+/// Removes the attribute `key` and returns its value.
+fn take(attr: &mut Attr, key: &str) -> Option<String> {
+    let index = attr.attributes.iter().position(|(it, _)| it == key)?;
+    Some(attr.attributes.remove(index).1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::definition::{Alignment, Attr, Block, ColWidth};
+    use crate::table::{Grid, GridRow};
+    use crate::utils::stringify;
+    use super::{csv_table, Options};
+
+    fn code_block(classes: &[&str], attributes: &[(&str, &str)], content: &str) -> Block {
+        Block::CodeBlock(Attr {
+            id: "tbl".into(),
+            classes: classes.iter().map(|it| it.to_string()).collect(),
+            attributes: attributes.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+        }, content.into())
+    }
+
+    fn grid(block: Block) -> Grid {
+        Grid::from_table(&csv_table(block).unwrap()).unwrap()
+    }
+
+    /// The texts of the head rows, then of the body rows.
+    fn texts(grid: &Grid) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+        let texts = |rows: &[GridRow]| rows.iter().map(|row| (0..grid.width()).map(|it| row.text(it)).collect()).collect();
+        (texts(&grid.head), texts(&grid.bodies[0].rows))
+    }
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter().map(|row| row.iter().map(|it| it.to_string()).collect()).collect()
+    }
+
+    /// A new directory holding the `files`, given as path and content.
+    fn directory(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("pandoc-csv-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for (path, content) in files {
+            fs::write(directory.join(path), content).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn the_delimiter_attribute_overrides_the_class_and_the_extension() {
+        let expected = (rows(&[&["a", "b"]]), rows(&[&["1", "2;3"]]));
+        assert_eq!(texts(&grid(code_block(&["csv"], &[], "a,b\n1,2;3"))), expected);
+        assert_eq!(texts(&grid(code_block(&["tsv"], &[], "a\tb\n1\t2;3"))), expected);
+        assert_eq!(texts(&grid(code_block(&["csv"], &[("delimiter", "tab")], "a\tb\n1\t2;3"))), expected);
+        assert_eq!(texts(&grid(code_block(&["csv"], &[("delimiter", "\\t")], "a\tb\n1\t2;3"))), expected);
+        assert_eq!(texts(&grid(code_block(&["tsv"], &[("delimiter", "|")], "a|b\n1|2;3"))), expected);
+
+        let directory = directory("delimiter", &[("values.tsv", "a\tb\n1\t2;3"), ("values.csv", "a;b\n1;2,3")]);
+        let options = Options { directory, ..Options::default() };
+        let from_file = |attributes: &[(&str, &str)]| texts(&Grid::from_table(&options.convert(code_block(&[], attributes, "")).unwrap()).unwrap());
+        assert_eq!(from_file(&[("file", "values.tsv")]), expected);
+        assert_eq!(from_file(&[("file", "values.csv"), ("delimiter", ";")]), (rows(&[&["a", "b"]]), rows(&[&["1", "2,3"]])));
+    }
+
+    #[test]
+    fn the_first_record_is_the_head_unless_told_otherwise() {
+        assert_eq!(texts(&grid(code_block(&["csv"], &[("header", "no")], "a,b\n1,2"))), (vec![], rows(&[&["a", "b"], &["1", "2"]])));
+        let options = Options { header: false, ..Options::default() };
+        let convert = |attributes: &[(&str, &str)]| texts(&Grid::from_table(&options.convert(code_block(&["csv"], attributes, "a,b\n1,2")).unwrap()).unwrap());
+        assert_eq!(convert(&[]), (vec![], rows(&[&["a", "b"], &["1", "2"]])));
+        assert_eq!(convert(&[("header", "Yes")]), (rows(&[&["a", "b"]]), rows(&[&["1", "2"]])));
+    }
+
+    #[test]
+    fn missing_alignments_are_left_to_the_default() {
+        let grid = grid(code_block(&["csv"], &[("align", "rxC")], "a,b,c"));
+        let alignments: Vec<&Alignment> = grid.columns.iter().map(|it| &it.0).collect();
+        assert!(matches!(alignments[..], [Alignment::AlignRight, Alignment::AlignCenter, Alignment::AlignDefault]));
+    }
+
+    #[test]
+    fn widths_are_scaled_to_fractions() {
+        let widths = |widths: &str| -> Vec<Option<f64>> {
+            grid(code_block(&["csv"], &[("widths", widths)], "a,b,c")).columns.iter()
+                .map(|it| match it.1 {
+                    ColWidth::ColWidth(width) => Some(width),
+                    ColWidth::ColWidthDefault => None,
+                }).collect()
+        };
+        assert_eq!(widths("50 25 25"), [Some(0.5), Some(0.25), Some(0.25)]);
+        assert_eq!(widths("1,1,2"), [Some(0.25), Some(0.25), Some(0.5)]);
+        assert_eq!(widths("0.2 0.3"), [Some(0.2), Some(0.3), None]);
+    }
+
+    #[test]
+    fn the_consumed_attributes_are_removed() {
+        let attributes = [("caption", "Sizes of things"), ("align", "l"), ("widths", "1"), ("header", "yes"), ("delimiter", ","), ("source", "survey")];
+        let grid = grid(code_block(&["csv", "wide"], &attributes, "a\n1"));
+        assert_eq!(stringify(&grid.caption), "Sizes of things");
+        assert_eq!(grid.attr.id, "tbl");
+        assert_eq!(grid.attr.classes, ["wide"]);
+        assert_eq!(grid.attr.attributes, [("source".to_string(), "survey".to_string())]);
+    }
+
+    #[test]
+    fn other_blocks_are_kept() {
+        let block = csv_table(code_block(&["json"], &[("file", "values.json")], "{}")).unwrap();
+        assert!(matches!(block, Block::CodeBlock(attr, _) if attr.attributes.len() == 1));
+    }
+
+    #[test]
+    fn missing_files_are_named_in_the_error() {
+        let directory = directory("missing", &[]);
+        let options = Options { directory: directory.clone(), ..Options::default() };
+        let error = options.convert(code_block(&[], &[("file", "missing.csv")], "")).unwrap_err();
+        let prefix = format!("I/O error: {}: ", directory.join("missing.csv").display());
+        assert!(error.to_string().starts_with(&prefix), "{}", error);
+    }
+}
@@ -1,9 +1,14 @@
 use std::collections::BTreeMap;
 use serde::{Serialize, Deserialize};
+use crate::queryable::Queryable;
+use crate::walkable::Walkable;
+use crate::walkable_mut::WalkableMut;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
 pub struct Pandoc {
     #[serde(rename = "pandoc-api-version")]
+    #[walkable(skip)]
     pub version: Vec<u32>,
     pub meta: Meta,
     pub blocks: Vec<Block>,
@@ -11,44 +16,88 @@ pub struct Pandoc {
 
 pub type Meta = BTreeMap<String, MetaValue>;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
 #[serde(tag = "t", content = "c")]
 pub enum MetaValue {
     MetaMap(Meta),
     MetaList(Vec<MetaValue>),
-    MetaBool(bool),
-    MetaString(String),
+    MetaBool(#[walkable(skip)] bool),
+    MetaString(#[walkable(skip)] String),
     MetaInlines(Vec<Inline>),
     MetaBlocks(Vec<Block>),
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
 #[serde(tag = "t", content = "c")]
 pub enum Block {
     Plain(Vec<Inline>),
     Para(Vec<Inline>),
     LineBlock(Vec<Vec<Inline>>),
-    CodeBlock(Attr, String),
-    RawBlock(Format, String),
+    CodeBlock(Attr, #[walkable(skip)] String),
+    RawBlock(#[walkable(skip)] Format, #[walkable(skip)] String),
     BlockQuote(Vec<Block>),
-    OrderedList(ListAttributes, Vec<Vec<Block>>),
+    OrderedList(#[walkable(skip)] ListAttributes, Vec<Vec<Block>>),
     BulletList(Vec<Vec<Block>>),
     DefinitionList(Vec<Definition>),
-    Header(u8, Attr, Vec<Inline>),
+    Header(#[walkable(skip)] u8, Attr, Vec<Inline>),
     HorizontalRule,
-    Table(Attr, Caption, Vec<ColSpec>, TableHead, Vec<TableBody>, TableFoot),
+    Table(Attr, Caption, #[walkable(skip)] Vec<ColSpec>, TableHead, Vec<TableBody>, TableFoot),
     Figure(Attr, Caption, Vec<Block>),
     Div(Attr, Vec<Block>)
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockKind {
+    Plain,
+    Para,
+    LineBlock,
+    CodeBlock,
+    RawBlock,
+    BlockQuote,
+    OrderedList,
+    BulletList,
+    DefinitionList,
+    Header,
+    HorizontalRule,
+    Table,
+    Figure,
+    Div,
+}
+
+impl Block {
+    pub fn kind(&self) -> BlockKind {
+        match self {
+            Block::Plain(..) => BlockKind::Plain,
+            Block::Para(..) => BlockKind::Para,
+            Block::LineBlock(..) => BlockKind::LineBlock,
+            Block::CodeBlock(..) => BlockKind::CodeBlock,
+            Block::RawBlock(..) => BlockKind::RawBlock,
+            Block::BlockQuote(..) => BlockKind::BlockQuote,
+            Block::OrderedList(..) => BlockKind::OrderedList,
+            Block::BulletList(..) => BlockKind::BulletList,
+            Block::DefinitionList(..) => BlockKind::DefinitionList,
+            Block::Header(..) => BlockKind::Header,
+            Block::HorizontalRule => BlockKind::HorizontalRule,
+            Block::Table(..) => BlockKind::Table,
+            Block::Figure(..) => BlockKind::Figure,
+            Block::Div(..) => BlockKind::Div,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate")]
 pub struct Definition(pub Vec<Inline>, pub Vec<Vec<Block>>);
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
 #[serde(tag = "t", content = "c")]
 pub enum Inline {
     /// Text
-    Str(String),
+    Str(#[walkable(skip)] String),
     /// Emphasized text
     Emph(Vec<Inline>),
     /// Underlined text
@@ -64,11 +113,11 @@ pub enum Inline {
     /// Small caps text
     SmallCaps(Vec<Inline>),
     /// Quoted text
-    Quoted(QuoteType, Vec<Inline>),
+    Quoted(#[walkable(skip)] QuoteType, Vec<Inline>),
     /// Citation
     Cite(Vec<Citation>, Vec<Inline>),
     /// Inline code
-    Code(Attr, String),
+    Code(Attr, #[walkable(skip)] String),
     /// Inter-word space
     Space,
     /// Soft line break
@@ -76,9 +125,9 @@ pub enum Inline {
     /// Hard line break
     LineBreak,
     /// TeX math
-    Math(MathType, String),
+    Math(#[walkable(skip)] MathType, #[walkable(skip)] String),
     /// Raw inline
-    RawInline(Format, String),
+    RawInline(#[walkable(skip)] Format, #[walkable(skip)] String),
     /// Hyperlink: alt text (list of inlines), target
     Link(Attr, Vec<Inline>, Target),
     /// Image: alt text (list of inlines), target
@@ -89,6 +138,57 @@ pub enum Inline {
     Span(Attr, Vec<Inline>),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InlineKind {
+    Str,
+    Emph,
+    Underline,
+    Strong,
+    Strikeout,
+    Superscript,
+    Subscript,
+    SmallCaps,
+    Quoted,
+    Cite,
+    Code,
+    Space,
+    SoftBreak,
+    LineBreak,
+    Math,
+    RawInline,
+    Link,
+    Image,
+    Note,
+    Span,
+}
+
+impl Inline {
+    pub fn kind(&self) -> InlineKind {
+        match self {
+            Inline::Str(..) => InlineKind::Str,
+            Inline::Emph(..) => InlineKind::Emph,
+            Inline::Underline(..) => InlineKind::Underline,
+            Inline::Strong(..) => InlineKind::Strong,
+            Inline::Strikeout(..) => InlineKind::Strikeout,
+            Inline::Superscript(..) => InlineKind::Superscript,
+            Inline::Subscript(..) => InlineKind::Subscript,
+            Inline::SmallCaps(..) => InlineKind::SmallCaps,
+            Inline::Quoted(..) => InlineKind::Quoted,
+            Inline::Cite(..) => InlineKind::Cite,
+            Inline::Code(..) => InlineKind::Code,
+            Inline::Space => InlineKind::Space,
+            Inline::SoftBreak => InlineKind::SoftBreak,
+            Inline::LineBreak => InlineKind::LineBreak,
+            Inline::Math(..) => InlineKind::Math,
+            Inline::RawInline(..) => InlineKind::RawInline,
+            Inline::Link(..) => InlineKind::Link,
+            Inline::Image(..) => InlineKind::Image,
+            Inline::Note(..) => InlineKind::Note,
+            Inline::Span(..) => InlineKind::Span,
+        }
+    }
+}
+
 pub type AttrList = Vec<(String, String)>;
 
 type AttrJson = (String, Vec<String>, AttrList);
@@ -107,15 +207,16 @@ impl From<AttrJson> for Attr {
     }
 }
 
-impl Into<AttrJson> for Attr {
-    fn into(self) -> AttrJson {
-        (self.id, self.classes, self.attributes)
+impl From<Attr> for AttrJson {
+    fn from(attr: Attr) -> Self {
+        (attr.id, attr.classes, attr.attributes)
     }
 }
 
 pub type ShortCaption = Vec<Inline>;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
 pub struct Caption(pub Option<ShortCaption>, pub Vec<Block>);
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -138,19 +239,24 @@ pub enum CitationMode {
     NormalCitation,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
 pub struct Citation {
     #[serde(rename = "citationId")]
+    #[walkable(skip)]
     pub citation_id: String,
     #[serde(rename = "citationPrefix")]
     pub citation_prefix: Vec<Inline>,
     #[serde(rename = "citationSuffix")]
     pub citation_suffix: Vec<Inline>,
     #[serde(rename = "citationMode")]
+    #[walkable(skip)]
     pub citation_mode: CitationMode,
     #[serde(rename = "citationNoteNum")]
+    #[walkable(skip)]
     pub citation_note_num: u64,
     #[serde(rename = "citationHash")]
+    #[walkable(skip)]
     pub citation_hash: u64,
 }
 
@@ -190,24 +296,29 @@ pub enum Alignment {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ColSpec(pub Alignment, pub ColWidth);
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate")]
 pub struct TableHead(pub Attr, pub Vec<Row>);
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
 pub struct Row(pub Attr, pub Vec<Cell>);
 
 pub type RowSpan = u32;
 pub type ColSpan = u32;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Cell(pub Attr, pub Alignment, pub RowSpan, pub ColSpan, pub Vec<Block>);
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate", node)]
+pub struct Cell(pub Attr, #[walkable(skip)] pub Alignment, #[walkable(skip)] pub RowSpan, #[walkable(skip)] pub ColSpan, pub Vec<Block>);
 
 pub type RowHeadColumns = u32;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct TableBody(pub Attr, pub RowHeadColumns, pub Vec<Row>, pub Vec<Row>);
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate")]
+pub struct TableBody(pub Attr, #[walkable(skip)] pub RowHeadColumns, pub Vec<Row>, pub Vec<Row>);
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Walkable, WalkableMut, Queryable)]
+#[walkable(crate = "crate")]
 pub struct TableFoot(pub Attr, pub Vec<Row>);
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
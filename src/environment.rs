@@ -0,0 +1,197 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use serde_json::Value;
+
+/// Output format given by pandoc to its filters, e.g. `html5` or `markdown+smart-raw_html`.
+///
+/// ```
+/// use pandoc::environment::OutputFormat;
+///
+/// let format: OutputFormat = "markdown+smart-raw_html".parse().unwrap();
+/// assert_eq!(format.name, "markdown");
+/// assert!(format.has_extension("smart"));
+/// assert!(format.lacks_extension("raw_html"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputFormat {
+    pub name: String,
+    pub enabled_extensions: Vec<String>,
+    pub disabled_extensions: Vec<String>,
+}
+
+impl OutputFormat {
+    pub fn is_html(&self) -> bool {
+        matches!(self.name.as_str(), "html" | "html4" | "html5" | "chunkedhtml" | "epub" | "epub2" | "epub3" | "revealjs" | "s5" | "slideous" | "slidy" | "dzslides")
+    }
+
+    pub fn is_latex(&self) -> bool {
+        matches!(self.name.as_str(), "latex" | "beamer")
+    }
+
+    pub fn is_markdown(&self) -> bool {
+        matches!(self.name.as_str(), "markdown" | "markdown_strict" | "markdown_phpextra" | "markdown_mmd" | "markdown_github" | "commonmark" | "commonmark_x" | "gfm")
+    }
+
+    pub fn is_docx(&self) -> bool {
+        self.name == "docx"
+    }
+
+    /// Whether the extension has been explicitly enabled (`+ext`) on the command line.
+    pub fn has_extension(&self, extension: &str) -> bool {
+        self.enabled_extensions.iter().any(|it| it == extension)
+    }
+
+    /// Whether the extension has been explicitly disabled (`-ext`) on the command line.
+    pub fn lacks_extension(&self, extension: &str) -> bool {
+        self.disabled_extensions.iter().any(|it| it == extension)
+    }
+
+    /// The format to use for the `RawInline`s and `RawBlock`s targeting this output, shared by
+    /// the variants of HTML, LaTeX and Markdown.
+    pub fn raw_format(&self) -> &str {
+        if self.is_html() {
+            "html"
+        } else if self.is_latex() {
+            "latex"
+        } else if self.is_markdown() {
+            "markdown"
+        } else if self.name == "docx" {
+            "openxml"
+        } else {
+            self.name.as_str()
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = std::convert::Infallible;
+
+    /// Reads `name+ext-ext…`; a later sign of an extension overrides an earlier one, and the empty
+    /// extensions are ignored.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let end_of_name = spec.find(['+', '-']).unwrap_or(spec.len());
+        let mut format = OutputFormat { name: spec[..end_of_name].to_string(), ..OutputFormat::default() };
+        let mut rest = &spec[end_of_name..];
+        while let Some(sign) = rest.chars().next() {
+            let end = rest[1..].find(['+', '-']).map_or(rest.len(), |it| it + 1);
+            let extension = &rest[1..end];
+            rest = &rest[end..];
+            if extension.is_empty() {
+                continue;
+            }
+            format.enabled_extensions.retain(|it| it != extension);
+            format.disabled_extensions.retain(|it| it != extension);
+            if sign == '+' {
+                format.enabled_extensions.push(extension.to_string());
+            } else {
+                format.disabled_extensions.push(extension.to_string());
+            }
+        }
+        Ok(format)
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for extension in &self.enabled_extensions {
+            write!(f, "+{}", extension)?;
+        }
+        for extension in &self.disabled_extensions {
+            write!(f, "-{}", extension)?;
+        }
+        Ok(())
+    }
+}
+
+/// What pandoc tells a JSON filter about the conversion it is part of.
+#[derive(Clone, Debug, Default)]
+pub struct Environment {
+    /// First command line argument of the filter.
+    pub format: OutputFormat,
+    /// Content of `PANDOC_VERSION`, e.g. `[3, 1, 2]`.
+    pub pandoc_version: Option<Vec<u32>>,
+    /// Content of `PANDOC_READER_OPTIONS`, as sent by pandoc.
+    pub reader_options: Option<Value>,
+}
+
+impl Environment {
+    /// Reads the environment of the filter; what is missing or invalid is left to its default.
+    pub fn from_env() -> Self {
+        Environment::from_values(env::args().nth(1), env::var("PANDOC_VERSION").ok(), env::var("PANDOC_READER_OPTIONS").ok())
+    }
+
+    fn from_values(format: Option<String>, pandoc_version: Option<String>, reader_options: Option<String>) -> Self {
+        let format = format
+            .map(|it| it.parse().unwrap_or_default())
+            .unwrap_or_default();
+        let pandoc_version = pandoc_version
+            .and_then(|it| it.split('.').map(|it| it.parse().ok()).collect());
+        let reader_options = reader_options
+            .and_then(|it| serde_json::from_str(&it).ok());
+        Environment { format, pandoc_version, reader_options }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::{Environment, OutputFormat};
+
+    fn format(spec: &str) -> OutputFormat {
+        spec.parse().unwrap()
+    }
+
+    fn extensions(format: &OutputFormat) -> (Vec<&str>, Vec<&str>) {
+        (format.enabled_extensions.iter().map(String::as_str).collect(), format.disabled_extensions.iter().map(String::as_str).collect())
+    }
+
+    #[test]
+    fn extensions_follow_the_name() {
+        let markdown = format("markdown+smart-raw_html+footnotes");
+        assert_eq!(markdown.name, "markdown");
+        assert_eq!(extensions(&markdown), (vec!["smart", "footnotes"], vec!["raw_html"]));
+        assert_eq!(markdown.to_string(), "markdown+smart+footnotes-raw_html");
+        assert_eq!(format("html5"), OutputFormat { name: "html5".into(), ..OutputFormat::default() });
+    }
+
+    #[test]
+    fn the_last_sign_of_an_extension_wins() {
+        let markdown = format("markdown+smart+smart-smart+raw_html-footnotes+footnotes");
+        assert_eq!(extensions(&markdown), (vec!["raw_html", "footnotes"], vec!["smart"]));
+        assert!(markdown.lacks_extension("smart") && !markdown.has_extension("smart"));
+        assert!(markdown.has_extension("footnotes") && !markdown.lacks_extension("footnotes"));
+    }
+
+    #[test]
+    fn empty_names_and_extensions() {
+        assert_eq!(format(""), OutputFormat::default());
+        assert_eq!(format("+"), OutputFormat::default());
+        let markdown = format("markdown+-smart+");
+        assert_eq!((markdown.name.as_str(), extensions(&markdown)), ("markdown", (vec![], vec!["smart"])));
+        assert_eq!(extensions(&format("+smart")), (vec!["smart"], vec![]));
+    }
+
+    #[test]
+    fn raw_formats_are_shared_by_the_variants() {
+        let raw_formats: Vec<String> = ["html5", "revealjs", "beamer", "markdown_strict", "gfm+smart", "commonmark_x", "docx", "rst"].iter()
+            .map(|it| format(it).raw_format().to_string())
+            .collect();
+        assert_eq!(raw_formats, ["html", "html", "latex", "markdown", "markdown", "markdown", "openxml", "rst"]);
+    }
+
+    #[test]
+    fn the_environment_keeps_what_is_valid() {
+        let environment = Environment::from_values(Some("latex-smart".into()), Some("3.1.2".into()), Some(r#"{"columns":72}"#.into()));
+        assert_eq!(environment.format, format("latex-smart"));
+        assert_eq!(environment.pandoc_version, Some(vec![3, 1, 2]));
+        assert_eq!(environment.reader_options, Some(json!({"columns": 72})));
+
+        let environment = Environment::from_values(None, None, None);
+        assert_eq!((environment.format, environment.pandoc_version, environment.reader_options), (OutputFormat::default(), None, None));
+
+        let environment = Environment::from_values(Some(String::new()), Some("3.x".into()), Some("{".into()));
+        assert_eq!((environment.format, environment.pandoc_version, environment.reader_options), (OutputFormat::default(), None, None));
+    }
+}
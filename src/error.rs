@@ -0,0 +1,156 @@
+use std::error;
+use std::fmt;
+use std::io;
+use crate::compat::UnsupportedVersion;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Failure of a filter run.
+///
+/// Its `Debug` output is the same as its `Display` one, so a `main` returning
+/// `Result<(), pandoc::Error>` reports failures to pandoc readably.
+pub enum Error {
+    /// Reading the document or writing the result failed.
+    Io(io::Error),
+    /// The input is not valid JSON.
+    Json { message: String, line: usize, column: usize },
+    /// The input is valid JSON but not a pandoc AST; `path` locates the faulty value, e.g. `blocks[12].c[2]`,
+    /// and `line` and `column` its end in the text, 0 when it was not parsed from text.
+    Shape { path: String, message: String, line: usize, column: usize },
+    /// A metadata value cannot be converted from or to the expected Rust type; `path` locates
+    /// it, e.g. `my-filter.columns[2]`.
+    Meta { path: String, message: String },
+    /// The document has been emitted with a pandoc API this crate cannot convert.
+    UnsupportedApiVersion(Vec<u32>),
+    /// The user callback failed.
+    Filter(Box<dyn error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Wraps the failure of a callback, unless it already is an `Error`, e.g. a metadata error
+    /// propagated with `?`.
+    pub fn filter<E>(error: E) -> Self where E: Into<Box<dyn error::Error + Send + Sync>> {
+        match error.into().downcast::<Error>() {
+            Ok(error) => *error,
+            Err(error) => Error::Filter(error),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "I/O error: {}", error),
+            Error::Json { message, line, column } => write!(f, "invalid JSON at line {}, column {}: {}", line, column, message),
+            Error::Shape { path, message, line: 0, .. } => write!(f, "unexpected pandoc AST at {}: {}", path, message),
+            Error::Shape { path, message, line, column } => write!(f, "unexpected pandoc AST at {} (line {}, column {}): {}", path, line, column, message),
+            Error::Meta { path, message } if path.is_empty() => write!(f, "invalid metadata: {}", message),
+            Error::Meta { path, message } => write!(f, "invalid metadata at {}: {}", path, message),
+            Error::UnsupportedApiVersion(version) => write!(f, "{}", UnsupportedVersion(version.clone())),
+            Error::Filter(error) => write!(f, "filter error: {}", error),
+        }
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Filter(error) => Some(error.as_ref()),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<UnsupportedVersion> for Error {
+    fn from(error: UnsupportedVersion) -> Self {
+        Error::UnsupportedApiVersion(error.0)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        shape_at(".".to_string(), error)
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for Error {
+    fn from(error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let path = error.path().to_string();
+        shape_at(path, error.into_inner())
+    }
+}
+
+/// Classifies a `serde_json` error, a data error being a [`Error::Shape`] at `path`.
+fn shape_at(path: String, error: serde_json::Error) -> Error {
+    if let serde_json::error::Category::Io = error.classify() {
+        return Error::Io(error.into());
+    }
+    let (line, column) = (error.line(), error.column());
+    let message = error.to_string();
+    let message = message.trim_end_matches(&format!(" at line {} column {}", line, column)).to_string();
+    match error.classify() {
+        serde_json::error::Category::Data => Error::Shape { path, message, line, column },
+        _ => Error::Json { message, line, column },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::Pandoc;
+    use super::Error;
+
+    fn parse(json: &str) -> Error {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        serde_path_to_error::deserialize::<_, Pandoc>(&mut deserializer).unwrap_err().into()
+    }
+
+    #[test]
+    fn shape_errors_keep_their_path_and_location() {
+        let error = parse(r#"{"pandoc-api-version":[1,23],"meta":{},"blocks":[{"t":"Para","c":[{"t":"Strr","c":"a"}]}]}"#);
+        match error {
+            Error::Shape { path, line, column, .. } => assert_eq!((path.as_str(), line, column), ("blocks[0].c[0].t", 1, 77)),
+            _ => panic!("unexpected error: {}", error),
+        }
+        let error: Error = serde_json::from_str::<Pandoc>(r#"{"pandoc-api-version":[1,23],"meta":{}}"#).unwrap_err().into();
+        match error {
+            Error::Shape { path, line, column, .. } => assert_eq!((path.as_str(), line, column), (".", 1, 39)),
+            _ => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn syntax_errors_are_json_errors() {
+        let error = parse("{\n\"meta\": }");
+        match error {
+            Error::Json { message, line, column } => assert_eq!((message.as_str(), line, column), ("expected value", 2, 9)),
+            _ => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn shape_errors_from_values_have_no_location() {
+        let error: Error = serde_json::from_value::<Pandoc>(serde_json::json!({ "meta": {} })).unwrap_err().into();
+        assert_eq!(error.to_string(), "unexpected pandoc AST at .: missing field `pandoc-api-version`");
+    }
+
+    #[test]
+    fn filter_errors_wrap_only_foreign_errors() {
+        let meta = Error::filter(Error::Meta { path: "my-filter.columns".into(), message: "expected an integer".into() });
+        assert_eq!(meta.to_string(), "invalid metadata at my-filter.columns: expected an integer");
+        let foreign = Error::filter("no columns");
+        assert_eq!(foreign.to_string(), "filter error: no columns");
+    }
+}
@@ -1,4 +1,3 @@
-use std::io;
 use pandoc::definition::Inline;
 use pandoc::to_json_filter;
 
@@ -9,6 +8,6 @@ fn caps(inline: Inline) -> Inline {
     }
 }
 
-fn main() -> io::Result<()> {
+fn main() -> pandoc::Result<()> {
     to_json_filter(&mut caps)
 }
\ No newline at end of file
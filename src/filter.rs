@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use crate::definition::{Attr, Block, BlockKind, Caption, Cell, Citation, Inline, InlineKind, Meta, MetaValue, Pandoc, Row, Target};
+use crate::ReadOptions;
+use crate::traversal::{Traversal, Walk};
+use crate::walkable::{walk_across, Blocks, Inlines, WalkChildren, Walkable};
+
+/// A set of element handlers applied in a single traversal, in the spirit of pandoc's Lua
+/// filters. The traversal is bottom-up unless set otherwise with [`Filter::traverse`].
+///
+/// ```no_run
+/// use pandoc::definition::{Inline, InlineKind};
+/// use pandoc::filter::Filter;
+///
+/// Filter::new()
+///     .inline(InlineKind::Str, |inline| match inline {
+///         Inline::Str(text) => vec![Inline::Str(text.to_uppercase())],
+///         _ => vec![inline]
+///     })
+///     .to_json_filter()
+///     .unwrap();
+/// ```
+#[derive(Default)]
+pub struct Filter<'a> {
+    inlines: HashMap<InlineKind, Box<dyn FnMut(Inline) -> Walk<Inlines> + 'a>>,
+    blocks: HashMap<BlockKind, Box<dyn FnMut(Block) -> Walk<Blocks> + 'a>>,
+    meta: Option<Box<dyn FnMut(Meta) -> Meta + 'a>>,
+    pandoc: Option<Box<dyn FnMut(Pandoc) -> Pandoc + 'a>>,
+    traversal: Traversal,
+    read_options: ReadOptions,
+}
+
+impl<'a> Filter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the handler of the `kind` inlines. The returned inlines replace the visited one;
+    /// in a top-down traversal, their children are not walked if they are returned as [`Walk::Skip`].
+    pub fn inline<F, R>(mut self, kind: InlineKind, mut f: F) -> Self where F: FnMut(Inline) -> R + 'a, R: Into<Walk<Inlines>> {
+        self.inlines.insert(kind, Box::new(move |it| f(it).into()));
+        self
+    }
+
+    /// Registers the handler of the `kind` blocks. The returned blocks replace the visited one;
+    /// in a top-down traversal, their children are not walked if they are returned as [`Walk::Skip`].
+    pub fn block<F, R>(mut self, kind: BlockKind, mut f: F) -> Self where F: FnMut(Block) -> R + 'a, R: Into<Walk<Blocks>> {
+        self.blocks.insert(kind, Box::new(move |it| f(it).into()));
+        self
+    }
+
+    /// Registers the handler of the document metadata, called once its content has been filtered.
+    pub fn meta<F>(mut self, f: F) -> Self where F: FnMut(Meta) -> Meta + 'a {
+        self.meta = Some(Box::new(f));
+        self
+    }
+
+    /// Registers the handler of the whole document, called last, or first in a top-down traversal.
+    pub fn pandoc<F>(mut self, f: F) -> Self where F: FnMut(Pandoc) -> Pandoc + 'a {
+        self.pandoc = Some(Box::new(f));
+        self
+    }
+
+    /// Sets the order the elements are visited in, like the `traverse` field of a Lua filter.
+    pub fn traverse(mut self, traversal: Traversal) -> Self {
+        self.traversal = traversal;
+        self
+    }
+
+    /// Sets how [`Filter::to_json_filter`] reads the document.
+    pub fn read_options(mut self, options: ReadOptions) -> Self {
+        self.read_options = options;
+        self
+    }
+
+    pub fn apply(&mut self, pandoc: Pandoc) -> Pandoc {
+        let pandoc = match (&mut self.pandoc, self.traversal) {
+            (Some(f), Traversal::TopDown) => f(pandoc),
+            _ => pandoc
+        };
+        let meta = match (&mut self.meta, self.traversal) {
+            (Some(f), Traversal::TopDown) => f(pandoc.meta),
+            _ => pandoc.meta
+        };
+        let meta = self.walk(meta);
+        let meta = match (&mut self.meta, self.traversal) {
+            (Some(f), Traversal::BottomUp) => f(meta),
+            _ => meta
+        };
+        let pandoc = Pandoc { meta, blocks: self.walk(pandoc.blocks), ..pandoc };
+        match (&mut self.pandoc, self.traversal) {
+            (Some(f), Traversal::BottomUp) => f(pandoc),
+            _ => pandoc
+        }
+    }
+
+    pub fn to_json_filter(&mut self) -> crate::Result<()> {
+        let pandoc = crate::read_pandoc_with(&self.read_options)?;
+        crate::write_pandoc(&self.apply(pandoc))
+    }
+
+    fn walk<W>(&mut self, node: W) -> W where W: Walkable<Node, Nodes> + Walkable<Node, Walk<Nodes>> {
+        match self.traversal {
+            Traversal::BottomUp => Walkable::<Node, Nodes>::walk(node, &mut |it| self.handle(it).into_inner()),
+            Traversal::TopDown => Walkable::<Node, Walk<Nodes>>::walk(node, &mut |it| self.handle(it)),
+        }
+    }
+
+    fn handle(&mut self, node: Node) -> Walk<Nodes> {
+        match node {
+            Node::Block(block) => match self.blocks.get_mut(&block.kind()) {
+                Some(f) => f(block).map(Nodes::Blocks),
+                None => Walk::Descend(Nodes::Blocks(vec![block]))
+            },
+            Node::Inline(inline) => match self.inlines.get_mut(&inline.kind()) {
+                Some(f) => f(inline).map(Nodes::Inlines),
+                None => Walk::Descend(Nodes::Inlines(vec![inline]))
+            },
+        }
+    }
+}
+
+/// The elements a filter has handlers for: the blocks and the inlines are walked in a single
+/// traversal, with a callback of both.
+#[allow(clippy::large_enum_variant)]
+enum Node {
+    Block(Block),
+    Inline(Inline),
+}
+
+/// The elements replacing a [`Node`], of the same kind.
+enum Nodes {
+    Blocks(Blocks),
+    Inlines(Inlines),
+}
+
+/// The walks of a `$t` wrapped in `Node::$node`, replaced by the `Nodes::$nodes` returned for it:
+/// the children first, or the returned elements first in a top-down walk.
+macro_rules! walk_filtered {
+    ($($t:ty, $node:ident, $nodes:ident);*) => {
+        $(impl Walkable<Node, Nodes> for $t {
+            fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(Node) -> Result<Nodes, E> {
+                WalkChildren::try_walk_children(self, f)
+            }
+
+            fn try_walk_vec<F, E>(list: Vec<Self>, f: &mut F) -> Result<Vec<Self>, E> where F: FnMut(Node) -> Result<Nodes, E> {
+                let mut spliced = Vec::with_capacity(list.len());
+                for node in list {
+                    let node = node.try_walk(f)?;
+                    match f(Node::$node(node))? {
+                        Nodes::$nodes(nodes) => spliced.extend(nodes),
+                        _ => unreachable!("a {} is replaced by elements of its kind", stringify!($t))
+                    }
+                }
+                Ok(spliced)
+            }
+        }
+
+        impl Walkable<Node, Walk<Nodes>> for $t {
+            fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(Node) -> Result<Walk<Nodes>, E> {
+                WalkChildren::try_walk_children(self, f)
+            }
+
+            fn try_walk_vec<F, E>(list: Vec<Self>, f: &mut F) -> Result<Vec<Self>, E> where F: FnMut(Node) -> Result<Walk<Nodes>, E> {
+                let mut descended = Vec::with_capacity(list.len());
+                for node in list {
+                    match f(Node::$node(node))? {
+                        Walk::Descend(Nodes::$nodes(nodes)) => for node in nodes {
+                            descended.push(node.try_walk(f)?);
+                        },
+                        Walk::Skip(Nodes::$nodes(nodes)) => descended.extend(nodes),
+                        _ => unreachable!("a {} is replaced by elements of its kind", stringify!($t))
+                    }
+                }
+                Ok(descended)
+            }
+        })*
+    };
+}
+
+walk_filtered!(Block, Block, Blocks; Inline, Inline, Inlines);
+
+walk_across!([] Node, Nodes; Meta, MetaValue, Attr, Target, Caption, Row, Cell, Citation);
+walk_across!([] Node, Walk<Nodes>; Meta, MetaValue, Attr, Target, Caption, Row, Cell, Citation);
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use crate::definition::{Attr, Block, BlockKind, Inline, InlineKind, Meta, MetaValue, Pandoc};
+    use crate::definition::Block::{CodeBlock, Div, Para, Plain};
+    use crate::definition::Inline::{Emph, Note, Str};
+    use crate::definition::MetaValue::{MetaBlocks, MetaInlines};
+    use crate::traversal::{Traversal, Walk};
+    use crate::queryable::Queryable;
+    use crate::utils::stringify;
+    use crate::walkable::tests::{block_name, inline_name, names};
+    use super::Filter;
+
+    fn para(text: &str) -> Block {
+        Para(vec![Str(text.into())])
+    }
+
+    fn document(blocks: Vec<Block>) -> Pandoc {
+        let meta: Meta = vec![("title".to_string(), MetaInlines(vec![Str("t".into())]))].into_iter().collect();
+        Pandoc { version: vec![1, 23], meta, blocks }
+    }
+
+    fn strs(value: &impl Queryable<Inline>) -> Vec<String> {
+        value.query(&mut |inline: &Inline| match inline {
+            Str(text) => vec![text.clone()],
+            _ => vec![],
+        })
+    }
+
+    fn upper(inline: Inline) -> Vec<Inline> {
+        match inline {
+            Str(text) => vec![Str(text.to_uppercase())],
+            _ => vec![inline],
+        }
+    }
+
+    #[test]
+    fn handlers_are_called_on_their_kind() {
+        let mut pandoc = document(vec![
+            para("a"),
+            Div(Attr::default(), vec![Plain(vec![Emph(vec![Str("b".into())]), Note(vec![para("c")])])]),
+            CodeBlock(Attr::default(), "d".into()),
+        ]);
+        pandoc.meta.insert("abstract".into(), MetaBlocks(vec![para("e")]));
+        let pandoc = Filter::new()
+            .inline(InlineKind::Str, upper)
+            .inline(InlineKind::Emph, |inline| match inline {
+                Emph(ils) => ils,
+                _ => vec![inline],
+            })
+            .block(BlockKind::Para, |block| match block {
+                Para(ils) => vec![Plain(ils)],
+                _ => vec![block],
+            })
+            .apply(pandoc);
+        assert_eq!(strs(&pandoc.blocks), ["A", "B", "C"]);
+        assert_eq!(names(&pandoc.blocks, false, false), ["Plain", "Div", "Plain", "Plain", "CodeBlock"]);
+        assert_eq!(names(&pandoc.blocks, true, false), ["Str", "Str", "Note", "Str"]);
+        assert_eq!(stringify(&pandoc.meta["title"]), "T");
+        assert!(matches!(&pandoc.meta["abstract"], MetaBlocks(blocks) if matches!(blocks[..], [Plain(_)])));
+    }
+
+    #[test]
+    fn meta_and_pandoc_handlers_see_the_document_in_the_traversal_order() {
+        for (traversal, title, text) in [(Traversal::BottomUp, "T", "A"), (Traversal::TopDown, "t", "a")] {
+            let metas = RefCell::new(Vec::new());
+            let documents = RefCell::new(Vec::new());
+            let pandoc = Filter::new()
+                .inline(InlineKind::Str, upper)
+                .meta(|mut meta| {
+                    metas.borrow_mut().push(stringify(&meta["title"]));
+                    meta.insert("date".into(), MetaInlines(vec![Str("today".into())]));
+                    meta
+                })
+                .pandoc(|mut pandoc| {
+                    documents.borrow_mut().push(stringify(&pandoc.blocks));
+                    pandoc.blocks.push(para("added"));
+                    pandoc
+                })
+                .traverse(traversal)
+                .apply(document(vec![para("a")]));
+            assert_eq!(metas.into_inner(), [title]);
+            assert_eq!(documents.into_inner(), [text]);
+            // The handlers run before the walk in a top-down traversal, which walks what they add.
+            let walked = traversal == Traversal::TopDown;
+            assert_eq!(stringify(&pandoc.meta["date"]), if walked { "TODAY" } else { "today" });
+            assert_eq!(stringify(&pandoc.blocks), if walked { "A ADDED" } else { "A added" });
+        }
+    }
+
+    #[test]
+    fn top_down_handlers_can_skip_the_returned_elements() {
+        let pandoc = document(vec![
+            Div(Attr { id: "skipped".into(), ..Attr::default() }, vec![para("a")]),
+            Div(Attr::default(), vec![para("b")]),
+        ]);
+        let pandoc = Filter::new()
+            .inline(InlineKind::Str, upper)
+            .block(BlockKind::Div, |block| match block {
+                Div(attr, blks) if attr.id == "skipped" => Walk::Skip(blks),
+                _ => Walk::Descend(vec![block]),
+            })
+            .traverse(Traversal::TopDown)
+            .apply(pandoc);
+        assert_eq!(stringify(&pandoc.blocks), "a B");
+        assert_eq!(names(&pandoc.blocks, false, false), ["Para", "Div", "Para"]);
+    }
+
+    #[test]
+    fn handlers_are_interleaved_in_a_single_traversal() {
+        let pandoc = document(vec![Para(vec![Str("a".into()), Note(vec![para("b")])])]);
+        for (traversal, expected) in [
+            (Traversal::BottomUp, ["Str t", "meta", "Str a", "Str b", "Para b", "Note", "Para a", "pandoc"]),
+            (Traversal::TopDown, ["pandoc", "meta", "Str t", "Para a", "Str a", "Note", "Para b", "Str b"]),
+        ] {
+            let events = RefCell::new(Vec::new());
+            let log = |name: String, text: String| events.borrow_mut().push(format!("{} {}", name, text).trim_end().to_string());
+            Filter::new()
+                .inline(InlineKind::Str, |inline| {
+                    log(inline_name(&inline), stringify(&inline));
+                    vec![inline]
+                })
+                .inline(InlineKind::Note, |inline| {
+                    log(inline_name(&inline), String::new());
+                    vec![inline]
+                })
+                .block(BlockKind::Para, |block| {
+                    log(block_name(&block), stringify(&block));
+                    vec![block]
+                })
+                .meta(|meta| {
+                    log("meta".into(), String::new());
+                    meta
+                })
+                .pandoc(|pandoc| {
+                    log("pandoc".into(), String::new());
+                    pandoc
+                })
+                .traverse(traversal)
+                .apply(pandoc.clone());
+            assert_eq!(events.into_inner(), expected, "{:?}", traversal);
+        }
+    }
+
+    #[test]
+    fn meta_values_are_walked() {
+        let value = MetaValue::MetaList(vec![MetaInlines(vec![Str("a".into())]), MetaBlocks(vec![para("b")])]);
+        let mut pandoc = document(vec![]);
+        pandoc.meta.insert("list".into(), value);
+        let pandoc = Filter::new().inline(InlineKind::Str, upper).apply(pandoc);
+        assert_eq!(stringify(&pandoc.meta["list"]), "A B");
+    }
+}
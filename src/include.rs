@@ -0,0 +1,285 @@
+//! Inclusion of other documents, listed in code blocks of class `include`.
+//!
+//! Each non-empty line of the block is a path, relative to the directory of the including
+//! document; lines starting with `//` are comments. The included documents are read, their own
+//! includes resolved, and their blocks replace the code block. A `shift-heading-level-by`
+//! attribute shifts the levels of their headers, headers shifted below level 1 becoming
+//! paragraphs, and a `format` attribute gives the input format for the `pandoc` reader.
+//!
+//! ````markdown
+//! ```{.include shift-heading-level-by=1}
+//! chapters/introduction.md
+//! // chapters/draft.md
+//! chapters/conclusion.md
+//! ```
+//! ````
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::definition::{Block, Inline, Pandoc};
+use crate::walkable::try_walk;
+use crate::walkable_mut::WalkableMut;
+
+/// Resolves the includes with the default options, paths being relative to the working
+/// directory.
+pub fn include(pandoc: Pandoc) -> crate::Result<Pandoc> {
+    Options::default().apply(pandoc)
+}
+
+/// How the included documents are parsed.
+#[derive(Clone, Debug)]
+pub enum Reader {
+    /// The embedded CommonMark reader; the `pandoc` executable reads the documents given a
+    /// `format` instead.
+    #[cfg(feature = "commonmark")]
+    CommonMark { options: crate::readers::commonmark::Options, pandoc: PathBuf },
+    /// A `pandoc` executable, run on each file with `--to json`; `from` is the default input
+    /// format, pandoc guessing it from the extension when `None`.
+    Pandoc { executable: PathBuf, from: Option<String> },
+}
+
+impl Default for Reader {
+    #[cfg(feature = "commonmark")]
+    fn default() -> Self {
+        Reader::CommonMark { options: Default::default(), pandoc: PathBuf::from("pandoc") }
+    }
+
+    #[cfg(not(feature = "commonmark"))]
+    fn default() -> Self {
+        Reader::Pandoc { executable: PathBuf::from("pandoc"), from: None }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Directory of the including document.
+    pub directory: PathBuf,
+    /// Path of the including document, if it is a file, so that including it back is reported
+    /// as a cycle.
+    pub source: Option<PathBuf>,
+    pub reader: Reader,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { directory: PathBuf::from("."), source: None, reader: Reader::default() }
+    }
+}
+
+impl Options {
+    /// The default options for the document read from `source`, its includes being relative to
+    /// its directory.
+    pub fn for_source<P>(source: P) -> Self where P: Into<PathBuf> {
+        let source = source.into();
+        let directory = source.parent().map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        Options { directory, source: Some(source), reader: Reader::default() }
+    }
+
+    /// Replaces the include blocks of `pandoc`, failing on unreadable files and on cycles.
+    pub fn apply(&self, mut pandoc: Pandoc) -> crate::Result<Pandoc> {
+        let mut includer = Includer { options: self, stack: Vec::new() };
+        if let Some(source) = &self.source {
+            includer.stack.push(source.canonicalize().map_err(|error| with_path(error, source))?);
+        }
+        pandoc.blocks = includer.blocks(pandoc.blocks, &self.directory)?;
+        Ok(pandoc)
+    }
+}
+
+impl Reader {
+    /// Reads the document at `path` as its blocks, in the given input `format` if any; a
+    /// format always goes through `pandoc`.
+    pub fn read(&self, path: &Path, format: Option<&str>) -> crate::Result<Vec<Block>> {
+        match self {
+            #[cfg(feature = "commonmark")]
+            Reader::CommonMark { options, .. } if format.is_none() => {
+                let markdown = std::fs::read_to_string(path).map_err(|error| with_path(error, path))?;
+                Ok(crate::readers::commonmark::read(&markdown, options).blocks)
+            },
+            #[cfg(feature = "commonmark")]
+            Reader::CommonMark { pandoc, .. } => run_pandoc(pandoc, path, format),
+            Reader::Pandoc { executable, from } => run_pandoc(executable, path, format.or(from.as_deref())),
+        }
+    }
+}
+
+fn run_pandoc(executable: &Path, path: &Path, from: Option<&str>) -> crate::Result<Vec<Block>> {
+    let mut command = Command::new(executable);
+    if let Some(from) = from {
+        command.args(["--from", from]);
+    }
+    let output = command.args(["--to", "json"]).arg(path).output().map_err(|error| with_path(error, executable))?;
+    if !output.status.success() {
+        let message = format!("{} failed on {}: {}", executable.display(), path.display(), String::from_utf8_lossy(&output.stderr).trim());
+        return Err(crate::Error::filter(message));
+    }
+    Ok(crate::parse_pandoc(&String::from_utf8_lossy(&output.stdout))?.blocks)
+}
+
+struct Includer<'a> {
+    options: &'a Options,
+    /// Files being included, outermost first, from the including document when known.
+    stack: Vec<PathBuf>,
+}
+
+impl<'a> Includer<'a> {
+    fn blocks(&mut self, blocks: Vec<Block>, directory: &Path) -> crate::Result<Vec<Block>> {
+        try_walk(blocks, &mut |block: Block| match block {
+            Block::CodeBlock(attr, paths) if attr.classes.iter().any(|it| it == "include") => {
+                let attribute = |key: &str| attr.attributes.iter().find(|(it, _)| it == key).map(|(_, value)| value.as_str());
+                let shift: i32 = match attribute("shift-heading-level-by") {
+                    Some(shift) => shift.trim().parse().map_err(|_| crate::Error::filter(format!("invalid shift-heading-level-by: {}", shift)))?,
+                    None => 0,
+                };
+                let format = attribute("format");
+                let mut result = Vec::new();
+                for path in paths.lines().map(str::trim).filter(|it| !it.is_empty() && !it.starts_with("//")) {
+                    let mut blocks = self.file(&directory.join(path), format)?;
+                    if shift != 0 {
+                        shift_headers(&mut blocks, shift);
+                    }
+                    result.extend(blocks);
+                }
+                Ok(result)
+            },
+            _ => Ok(vec![block]),
+        })
+    }
+
+    fn file(&mut self, path: &Path, format: Option<&str>) -> crate::Result<Vec<Block>> {
+        let canonical = path.canonicalize().map_err(|error| with_path(error, path))?;
+        if self.stack.contains(&canonical) {
+            let cycle: Vec<String> = self.stack.iter()
+                .skip_while(|it| **it != canonical)
+                .chain(Some(&canonical))
+                .map(|it| it.display().to_string())
+                .collect();
+            return Err(crate::Error::filter(format!("include cycle: {}", cycle.join(" -> "))));
+        }
+        let blocks = self.options.reader.read(path, format)?;
+        self.stack.push(canonical);
+        let directory = path.parent().unwrap_or(Path::new("."));
+        let blocks = self.blocks(blocks, directory);
+        self.stack.pop();
+        blocks
+    }
+}
+
+/// Adds `shift` to the header levels, the headers below level 1 becoming paragraphs.
+fn shift_headers(blocks: &mut Vec<Block>, shift: i32) {
+    blocks.walk_mut(&mut |block: &mut Block| {
+        if let Block::Header(level, _, inlines) = block {
+            let shifted = i32::from(*level) + shift;
+            if shifted < 1 {
+                *block = Block::Para(std::mem::take::<Vec<Inline>>(inlines));
+            } else {
+                *level = shifted.min(i32::from(u8::MAX)) as u8;
+            }
+        }
+    });
+}
+
+fn with_path(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
+
+#[cfg(all(test, feature = "commonmark"))]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::definition::{Attr, Block, Pandoc};
+    use crate::readers::commonmark;
+    use super::{Options, Reader};
+
+    /// A new directory holding the `files`, given as path and content.
+    fn directory(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("pandoc-include-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&directory);
+        for (path, content) in files {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        directory
+    }
+
+    fn include_block(attributes: &[(&str, &str)], paths: &str) -> Block {
+        let attributes = attributes.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        Block::CodeBlock(Attr { classes: vec!["include".into()], attributes, ..Attr::default() }, paths.into())
+    }
+
+    fn document(blocks: Vec<Block>) -> Pandoc {
+        Pandoc { version: crate::compat::API_VERSION.to_vec(), meta: Default::default(), blocks }
+    }
+
+    fn read(path: PathBuf) -> Pandoc {
+        commonmark::read(&fs::read_to_string(path).unwrap(), &commonmark::Options::default())
+    }
+
+    #[test]
+    fn nested_includes_are_relative_to_their_document() {
+        let directory = directory("nested", &[
+            ("a.md", "# A\n\n```include\n// skipped.md\nsub/b.md\n```"),
+            ("sub/b.md", "b\n\n```include\nc.md\n```"),
+            ("sub/c.md", "c"),
+        ]);
+        let options = Options { directory: directory.clone(), ..Options::default() };
+        let pandoc = options.apply(document(vec![include_block(&[], "a.md")])).unwrap();
+        assert_eq!(crate::utils::stringify(&pandoc.blocks), "A b c");
+        assert_eq!(pandoc.blocks.len(), 3);
+    }
+
+    #[test]
+    fn headers_are_shifted() {
+        let directory = directory("shift", &[("a.md", "# One\n\n## Two\n\n### Three")]);
+        let options = Options { directory: directory.clone(), ..Options::default() };
+        let levels = |shift: &str| {
+            let pandoc = options.apply(document(vec![include_block(&[("shift-heading-level-by", shift)], "a.md")])).unwrap();
+            pandoc.blocks.iter()
+                .map(|block| match block {
+                    Block::Header(level, ..) => *level,
+                    _ => 0,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(levels("1"), [2, 3, 4]);
+        assert_eq!(levels("-1"), [0, 1, 2]);
+        assert!(options.apply(document(vec![include_block(&[("shift-heading-level-by", "x")], "a.md")])).is_err());
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let directory = directory("cycle", &[
+            ("root.md", "```include\nback.md\n```"),
+            ("a.md", "```include\nb.md\n```"),
+            ("b.md", "```include\na.md\n```"),
+            ("self.md", "```include\nself.md\n```"),
+            ("back.md", "```include\nroot.md\n```"),
+        ]);
+        let options = Options { directory: directory.clone(), ..Options::default() };
+        let error = options.apply(document(vec![include_block(&[], "a.md")])).unwrap_err().to_string();
+        assert!(error.contains("include cycle: "), "{}", error);
+        assert!(error.ends_with("a.md"), "{}", error);
+        assert!(options.apply(document(vec![include_block(&[], "self.md")])).is_err());
+
+        let root = directory.join("root.md");
+        let error = Options::for_source(&root).apply(read(root.clone())).unwrap_err().to_string();
+        let cycle = format!("include cycle: {0}/root.md -> {0}/back.md -> {0}/root.md", directory.canonicalize().unwrap().display());
+        assert!(error.ends_with(&cycle), "{}", error);
+        // Without its source, the root document is only caught once included.
+        let error = options.apply(read(root)).unwrap_err().to_string();
+        assert!(error.ends_with("back.md"), "{}", error);
+    }
+
+    #[test]
+    fn formats_go_through_the_configured_pandoc() {
+        let directory = directory("format", &[("a.txt", "text")]);
+        let reader = Reader::CommonMark { options: Default::default(), pandoc: directory.join("no-pandoc") };
+        let options = Options { directory: directory.clone(), source: None, reader };
+        let error = options.apply(document(vec![include_block(&[("format", "markdown")], "a.txt")])).unwrap_err().to_string();
+        assert!(error.contains("no-pandoc"), "{}", error);
+        let missing = options.apply(document(vec![include_block(&[], "missing.md")])).unwrap_err().to_string();
+        assert!(missing.contains("missing.md"), "{}", missing);
+    }
+}
@@ -1,27 +1,202 @@
 pub mod definition;
+pub mod borrowed;
 pub mod walkable;
+pub mod walkable_mut;
+pub mod table;
+pub mod builder;
+pub mod compat;
+pub mod citeproc;
+pub mod context;
+pub mod crossref;
+pub mod csv_table;
+pub mod environment;
+pub mod include;
+pub mod meta;
+pub mod filter;
+pub mod pipeline;
+pub mod queryable;
+pub mod traversal;
+pub mod error;
+pub mod utils;
+pub mod readers;
+pub mod writers;
 
 extern crate serde;
 
-use std::io;
-use std::io::{BufRead, stdin};
+use std::error::Error as StdError;
+use std::io::{self, BufReader, BufWriter, Read, Write, stdin, stdout};
+use serde::Serialize;
 use crate::definition::Pandoc;
-use crate::walkable::Walkable;
+use crate::environment::Environment;
+use crate::walkable::{Walkable, try_walk};
+use crate::walkable_mut::WalkableMut;
 
-pub fn to_json_filter<F, I, O>(f: &mut F) -> io::Result<()> where Pandoc: Walkable<I, O>, F: FnMut(I) -> O {
+pub use crate::error::{Error, Result};
+
+/// How the document is read on the standard input.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    /// Size in bytes above which the input fails with [`Error::Io`], `None` (the default) for no
+    /// limit.
+    pub max_input_size: Option<u64>,
+}
+
+/// The free functions below read the document with the default options; these methods are the
+/// same with the given ones.
+impl ReadOptions {
+    /// Same as [`to_json_filter`](crate::to_json_filter).
+    pub fn to_json_filter<F, I, O>(&self, f: &mut F) -> Result<()> where Pandoc: Walkable<I, O>, F: FnMut(I) -> O {
+        let pandoc = read_pandoc_with(self)?;
+        write_pandoc(&pandoc.walk(f))
+    }
+
+    /// Same as [`to_json_filter_with_format`](crate::to_json_filter_with_format).
+    pub fn to_json_filter_with_format<F, I, O>(&self, f: &mut F) -> Result<()> where Pandoc: Walkable<I, O>, F: FnMut(&Environment, I) -> O {
+        let environment = Environment::from_env();
+        let pandoc = read_pandoc_with(self)?;
+        write_pandoc(&pandoc.walk(&mut |it| f(&environment, it)))
+    }
+
+    /// Same as [`try_to_json_filter`](crate::try_to_json_filter).
+    pub fn try_to_json_filter<F, I, O, E>(&self, f: &mut F) -> Result<()> where Pandoc: Walkable<I, O>, F: FnMut(I) -> std::result::Result<O, E>, E: Into<Box<dyn StdError + Send + Sync>> {
+        let pandoc = read_pandoc_with(self)?;
+        let pandoc = try_walk(pandoc, f).map_err(Error::filter)?;
+        write_pandoc(&pandoc)
+    }
+
+    /// Same as [`to_json_filter_mut`](crate::to_json_filter_mut).
+    pub fn to_json_filter_mut<F, I, O>(&self, f: &mut F) -> Result<()> where Pandoc: WalkableMut<I, O>, F: FnMut(&mut I) -> O {
+        let mut pandoc = read_pandoc_with(self)?;
+        pandoc.walk_mut(f);
+        write_pandoc(&pandoc)
+    }
+
+    /// Same as [`to_json_filter_borrowed`](crate::to_json_filter_borrowed).
+    pub fn to_json_filter_borrowed<F>(&self, f: F) -> Result<()> where F: for<'a> FnOnce(borrowed::Pandoc<'a>) -> borrowed::Pandoc<'a> {
+        let pandoc_json = read_text(self.limit(stdin().lock()))?;
+        let pandoc = f(borrowed::Pandoc::from_json(&pandoc_json)?);
+        write_json(&pandoc, &pandoc.version)
+    }
+
+    /// The `reader`, limited to `max_input_size`.
+    fn limit<R>(&self, reader: R) -> Limited<R> {
+        Limited { reader, max: self.max_input_size.unwrap_or(u64::MAX), read: 0 }
+    }
+}
+
+pub fn to_json_filter<F, I, O>(f: &mut F) -> Result<()> where Pandoc: Walkable<I, O>, F: FnMut(I) -> O {
+    ReadOptions::default().to_json_filter(f)
+}
+
+/// Same as [`to_json_filter`] with the [`Environment`] given by pandoc (output format, version
+/// and reader options) passed to `f`.
+pub fn to_json_filter_with_format<F, I, O>(f: &mut F) -> Result<()> where Pandoc: Walkable<I, O>, F: FnMut(&Environment, I) -> O {
+    ReadOptions::default().to_json_filter_with_format(f)
+}
+
+/// Same as [`to_json_filter`] with a fallible `f`: its first error stops the filter and is
+/// returned as [`Error::Filter`], or as is when it already is an [`Error`].
+pub fn try_to_json_filter<F, I, O, E>(f: &mut F) -> Result<()> where Pandoc: Walkable<I, O>, F: FnMut(I) -> std::result::Result<O, E>, E: Into<Box<dyn StdError + Send + Sync>> {
+    ReadOptions::default().try_to_json_filter(f)
+}
+
+pub fn to_json_filter_mut<F, I, O>(f: &mut F) -> Result<()> where Pandoc: WalkableMut<I, O>, F: FnMut(&mut I) -> O {
+    ReadOptions::default().to_json_filter_mut(f)
+}
+
+/// Same as [`to_json_filter`] on a [`borrowed`] document, whose text is not copied unless `f`
+/// changes it. `f` receives the whole document, to walk with the callbacks it needs.
+pub fn to_json_filter_borrowed<F>(f: F) -> Result<()> where F: for<'a> FnOnce(borrowed::Pandoc<'a>) -> borrowed::Pandoc<'a> {
+    ReadOptions::default().to_json_filter_borrowed(f)
+}
+
+/// Reads the document given by pandoc on the standard input, upgrading it from an older API.
+/// It is deserialized as it is read, without holding its whole text.
+pub fn read_pandoc() -> Result<Pandoc> {
+    read_pandoc_with(&ReadOptions::default())
+}
+
+/// Same as [`read_pandoc`] with the given `options`.
+pub fn read_pandoc_with(options: &ReadOptions) -> Result<Pandoc> {
+    read_json(serde_json::Deserializer::from_reader(BufReader::new(options.limit(stdin().lock()))))
+}
+
+/// Reads the whole JSON text of a document, upgraded to the current API for the borrowed types.
+fn read_text<R>(mut reader: R) -> Result<String> where R: Read {
     let mut pandoc_json = String::new();
-    for line in stdin().lock().lines() {
-        if let Ok(line) = line {
-            pandoc_json += line.as_str();
+    reader.read_to_string(&mut pandoc_json)?;
+    compat::upgrade_json(pandoc_json)
+}
+
+/// Parses the JSON emitted by pandoc, upgrading it from an older API.
+fn parse_pandoc(pandoc_json: &str) -> Result<Pandoc> {
+    read_json(serde_json::Deserializer::from_str(pandoc_json))
+}
+
+fn read_json<'de, R>(mut deserializer: serde_json::Deserializer<R>) -> Result<Pandoc> where R: serde_json::de::Read<'de> {
+    let document: compat::Document = serde_path_to_error::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    document.into_pandoc()
+}
+
+struct Limited<R> {
+    reader: R,
+    max: u64,
+    read: u64,
+}
+
+impl<R> Read for Limited<R> where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.read += n as u64;
+        if self.read > self.max {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("input larger than {} bytes", self.max)));
         }
+        Ok(n)
     }
-    let pandoc: Pandoc = serde_json::from_str(&pandoc_json)?;
-    println!("{}", serde_json::to_string(&pandoc.walk(f))?);
-    Ok(())
 }
 
+/// Writes the document on the standard output, downgraded to the API it was read with.
+pub fn write_pandoc(pandoc: &Pandoc) -> Result<()> {
+    write_json(pandoc, &pandoc.version)
+}
 
+/// Writes a document on the standard output, downgraded to the API `version` it was read with.
+fn write_json<D>(document: &D, version: &[u32]) -> Result<()> where D: Serialize {
+    compat::check(version)?;
+    let mut stdout = BufWriter::new(stdout().lock());
+    if version[..2] == compat::API_VERSION {
+        serde_json::to_writer(&mut stdout, document)?;
+    } else {
+        serde_json::to_writer(&mut stdout, &compat::downgrade(serde_json::to_value(document)?, version)?)?;
+    }
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
 
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use crate::{read_json, read_text, Error, Limited, ReadOptions};
 
+    #[test]
+    fn inputs_fail_past_their_limit() {
+        let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[]}"#;
+        let read = |max| read_json(serde_json::Deserializer::from_reader(BufReader::new(Limited { reader: json.as_bytes(), max, read: 0 })));
+        assert!(read(json.len() as u64).is_ok());
+        assert!(matches!(read(json.len() as u64 - 1).unwrap_err(), Error::Io(_)));
+    }
 
-
+    #[test]
+    fn read_options_limit_both_readers() {
+        let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[{"t":"Para","c":[{"t":"Str","c":"a"}]}]}"#;
+        let options = |max| ReadOptions { max_input_size: Some(max) };
+        let read = |options: ReadOptions| read_json(serde_json::Deserializer::from_reader(BufReader::new(options.limit(json.as_bytes()))));
+        assert_eq!(read(options(json.len() as u64)).unwrap().blocks.len(), 1);
+        assert!(matches!(read(options(16)).unwrap_err(), Error::Io(_)));
+        assert!(read(ReadOptions::default()).is_ok());
+        assert!(read_text(options(json.len() as u64).limit(json.as_bytes())).is_ok());
+        assert!(matches!(read_text(options(16).limit(json.as_bytes())).unwrap_err(), Error::Io(_)));
+    }
+}
@@ -0,0 +1,778 @@
+//! Conversions between metadata and Rust values through serde.
+//!
+//! Maps, lists and booleans map to their Rust counterparts; text, whether a `MetaString`, a
+//! `MetaInlines` or a `MetaBlocks`, is stringified and parsed when a number is expected. A
+//! single value is read as a list of one, as in `bibliography: refs.bib`.
+//!
+//! ```
+//! use serde::Deserialize;
+//! use pandoc::definition::Meta;
+//! use pandoc::definition::MetaValue::{MetaMap, MetaString};
+//! use pandoc::meta::MetaExt;
+//!
+//! #[derive(Deserialize)]
+//! struct Options {
+//!     columns: usize,
+//!     #[serde(default)]
+//!     classes: Vec<String>,
+//! }
+//!
+//! let mut meta = Meta::new();
+//! meta.insert("my-filter".into(), MetaMap([("columns".to_string(), MetaString("3".into()))].into()));
+//! let options: Options = meta.get_as("my-filter").unwrap().unwrap();
+//! assert_eq!(options.columns, 3);
+//!
+//! meta.insert("my-filter".into(), MetaMap([("columns".to_string(), MetaString("three".into()))].into()));
+//! let error = meta.get_as::<Options>("my-filter").err().unwrap();
+//! assert_eq!(error.to_string(), "invalid metadata at my-filter.columns: expected an integer, found `three`");
+//! ```
+
+use std::collections::btree_map;
+use std::fmt::{self, Display};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{self, Serialize};
+use serde::forward_to_deserialize_any;
+use crate::definition::{Meta, MetaValue};
+use crate::definition::MetaValue::{MetaBlocks, MetaBool, MetaInlines, MetaList, MetaMap, MetaString};
+use crate::utils::stringify;
+
+/// Typed access to the metadata.
+pub trait MetaExt {
+    /// Reads the value of `key`, `None` when it is missing.
+    fn get_as<T>(&self, key: &str) -> crate::Result<Option<T>> where T: DeserializeOwned;
+    /// Reads the whole metadata as one value, usually a struct.
+    fn deserialize_as<T>(&self) -> crate::Result<T> where T: DeserializeOwned;
+    /// Writes `value` under `key`, replacing the previous value.
+    fn insert_as<T>(&mut self, key: &str, value: &T) -> crate::Result<()> where T: Serialize + ?Sized;
+}
+
+impl MetaExt for Meta {
+    fn get_as<T>(&self, key: &str) -> crate::Result<Option<T>> where T: DeserializeOwned {
+        self.get(key).map(|value| deserialize(Deserializer(value), key)).transpose()
+    }
+
+    fn deserialize_as<T>(&self) -> crate::Result<T> where T: DeserializeOwned {
+        deserialize(MetaDeserializer(self), "")
+    }
+
+    fn insert_as<T>(&mut self, key: &str, value: &T) -> crate::Result<()> where T: Serialize + ?Sized {
+        let value = to_value(value).map_err(|error| match error {
+            crate::Error::Meta { message, .. } => crate::Error::Meta { path: key.to_string(), message },
+            error => error,
+        })?;
+        self.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Reads a Rust value from `value`.
+pub fn from_value<T>(value: &MetaValue) -> crate::Result<T> where T: DeserializeOwned {
+    deserialize(Deserializer(value), "")
+}
+
+/// Writes a Rust value as metadata; `None` and `()`, which have no metadata counterpart, are
+/// left out of maps and lists and rejected on their own.
+pub fn to_value<T>(value: &T) -> crate::Result<MetaValue> where T: Serialize + ?Sized {
+    match value.serialize(Serializer) {
+        Ok(Some(value)) => Ok(value),
+        Ok(None) => Err(crate::Error::Meta { path: String::new(), message: "no metadata value for a unit or a `None`".to_string() }),
+        Err(Message(message)) => Err(crate::Error::Meta { path: String::new(), message }),
+    }
+}
+
+fn deserialize<'de, D, T>(deserializer: D, key: &str) -> crate::Result<T> where D: de::Deserializer<'de, Error = Message>, T: DeserializeOwned {
+    serde_path_to_error::deserialize(deserializer).map_err(|error| {
+        let path = error.path().to_string();
+        let path = match (key, path.as_str()) {
+            (_, ".") => key.to_string(),
+            ("", _) => path,
+            _ => format!("{}.{}", key, path),
+        };
+        crate::Error::Meta { path, message: error.into_inner().0 }
+    })
+}
+
+/// Error of the conversions, turned into [`Error::Meta`](crate::Error::Meta) with its path.
+#[derive(Debug)]
+pub struct Message(String);
+
+impl Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Message {}
+
+impl de::Error for Message {
+    fn custom<T>(message: T) -> Self where T: Display {
+        Message(message.to_string())
+    }
+}
+
+impl ser::Error for Message {
+    fn custom<T>(message: T) -> Self where T: Display {
+        Message(message.to_string())
+    }
+}
+
+/// Deserializer reading a Rust value from a metadata value.
+pub struct Deserializer<'a>(pub &'a MetaValue);
+
+impl<'a> Deserializer<'a> {
+    fn text(&self) -> Option<String> {
+        match self.0 {
+            MetaString(_) | MetaInlines(_) | MetaBlocks(_) => Some(stringify(self.0)),
+            MetaBool(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.0 {
+            MetaMap(_) => "a map",
+            MetaList(_) => "a list",
+            MetaBool(_) => "a boolean",
+            MetaString(_) | MetaInlines(_) => "text",
+            MetaBlocks(_) => "blocks",
+        }
+    }
+
+    fn parse<T>(&self, expected: &str) -> Result<T, Message> where T: std::str::FromStr {
+        match self.text() {
+            Some(text) => text.trim().parse().map_err(|_| Message(format!("expected {}, found `{}`", expected, text))),
+            None => Err(Message(format!("expected {}, found {}", expected, self.kind()))),
+        }
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident, $expected:expr;)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+                visitor.$visit(self.parse($expected)?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = Message;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        match self.0 {
+            MetaMap(map) => visitor.visit_map(Entries { entries: map.iter(), value: None }),
+            MetaList(values) => visitor.visit_seq(Values(values.iter())),
+            MetaBool(value) => visitor.visit_bool(*value),
+            _ => visitor.visit_string(stringify(self.0)),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8, "an integer";
+        deserialize_i16 => visit_i16, "an integer";
+        deserialize_i32 => visit_i32, "an integer";
+        deserialize_i64 => visit_i64, "an integer";
+        deserialize_u8 => visit_u8, "an integer";
+        deserialize_u16 => visit_u16, "an integer";
+        deserialize_u32 => visit_u32, "an integer";
+        deserialize_u64 => visit_u64, "an integer";
+        deserialize_f32 => visit_f32, "a number";
+        deserialize_f64 => visit_f64, "a number";
+        deserialize_char => visit_char, "a character";
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        match self.0 {
+            MetaBool(value) => visitor.visit_bool(*value),
+            _ => visitor.visit_bool(self.parse("a boolean")?),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        match self.text() {
+            Some(text) => visitor.visit_string(text),
+            None => Err(Message(format!("expected text, found {}", self.kind()))),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        match self.text() {
+            Some(text) => visitor.visit_byte_buf(text.into_bytes()),
+            None => Err(Message(format!("expected text, found {}", self.kind()))),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        match self.0 {
+            MetaList(values) => visitor.visit_seq(Values(values.iter())),
+            value => visitor.visit_seq(Values(std::slice::from_ref(value).iter())),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        match self.0 {
+            MetaMap(map) => visitor.visit_map(Entries { entries: map.iter(), value: None }),
+            _ => Err(Message(format!("expected a map, found {}", self.kind()))),
+        }
+    }
+
+    fn deserialize_struct<V>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        self.deserialize_map(visitor)
+    }
+
+    /// Reads a unit variant from text and the other variants from a map of a single entry, the
+    /// variant name mapping to its content.
+    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        match self.0 {
+            MetaMap(map) if map.len() == 1 => {
+                let (variant, value) = map.iter().next().unwrap();
+                visitor.visit_enum(Variant { name: variant.clone(), value: Some(value) })
+            },
+            _ => match self.text() {
+                Some(name) => visitor.visit_enum(Variant { name, value: None }),
+                None => Err(Message(format!("expected a variant name or a map of one entry, found {}", self.kind()))),
+            },
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        visitor.visit_unit()
+    }
+}
+
+/// Deserializer reading a Rust value from the whole metadata.
+struct MetaDeserializer<'a>(&'a Meta);
+
+impl<'de, 'a> de::Deserializer<'de> for MetaDeserializer<'a> {
+    type Error = Message;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        visitor.visit_map(Entries { entries: self.0.iter(), value: None })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct Entries<'a> {
+    entries: btree_map::Iter<'a, String, MetaValue>,
+    value: Option<&'a MetaValue>,
+}
+
+impl<'de, 'a> MapAccess<'de> for Entries<'a> {
+    type Error = Message;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Message> where K: DeserializeSeed<'de> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.as_str().into_deserializer()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S>(&mut self, seed: S) -> Result<S::Value, Message> where S: DeserializeSeed<'de> {
+        seed.deserialize(Deserializer(self.value.take().unwrap()))
+    }
+}
+
+struct Values<'a>(std::slice::Iter<'a, MetaValue>);
+
+impl<'de, 'a> SeqAccess<'de> for Values<'a> {
+    type Error = Message;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>, Message> where S: DeserializeSeed<'de> {
+        self.0.next().map(|it| seed.deserialize(Deserializer(it))).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct Variant<'a> {
+    name: String,
+    value: Option<&'a MetaValue>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for Variant<'a> {
+    type Error = Message;
+    type Variant = Self;
+
+    fn variant_seed<S>(self, seed: S) -> Result<(S::Value, Self), Message> where S: DeserializeSeed<'de> {
+        let variant = seed.deserialize(self.name.as_str().into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for Variant<'a> {
+    type Error = Message;
+
+    fn unit_variant(self) -> Result<(), Message> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S>(self, seed: S) -> Result<S::Value, Message> where S: DeserializeSeed<'de> {
+        seed.deserialize(Deserializer(self.content()?))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        de::Deserializer::deserialize_seq(Deserializer(self.content()?), visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Message> where V: Visitor<'de> {
+        de::Deserializer::deserialize_map(Deserializer(self.content()?), visitor)
+    }
+}
+
+impl<'a> Variant<'a> {
+    fn content(&self) -> Result<&'a MetaValue, Message> {
+        self.value.ok_or_else(|| Message(format!("variant `{}` expects a content", self.name)))
+    }
+}
+
+macro_rules! serialize_display {
+    ($($method:ident: $type:ty),* $(,)?) => {
+        $(
+            fn $method(self, value: $type) -> Result<Self::Ok, Message> {
+                self.collect_str(&value)
+            }
+        )*
+    };
+}
+
+/// Serializer writing a Rust value as metadata; `None` stands for the values having no
+/// metadata counterpart.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+    type SerializeSeq = List;
+    type SerializeTuple = List;
+    type SerializeTupleStruct = List;
+    type SerializeTupleVariant = Tagged<List>;
+    type SerializeMap = Map;
+    type SerializeStruct = Map;
+    type SerializeStructVariant = Tagged<Map>;
+
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok, Message> {
+        Ok(Some(MetaBool(value)))
+    }
+
+    serialize_display! {
+        serialize_i8: i8, serialize_i16: i16, serialize_i32: i32, serialize_i64: i64,
+        serialize_u8: u8, serialize_u16: u16, serialize_u32: u32, serialize_u64: u64,
+        serialize_f32: f32, serialize_f64: f64,
+    }
+
+    fn serialize_char(self, value: char) -> Result<Self::Ok, Message> {
+        self.collect_str(&value)
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Self::Ok, Message> {
+        Ok(Some(MetaString(value.to_string())))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok, Message> {
+        match std::str::from_utf8(value) {
+            Ok(text) => self.serialize_str(text),
+            Err(_) => Err(Message("bytes must be UTF-8 to be written as metadata".to_string())),
+        }
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Message> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Message> where T: Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Message> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Message> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Self::Ok, Message> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Message> where T: Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<Self::Ok, Message> where T: Serialize + ?Sized {
+        Ok(tagged(variant, value.serialize(Serializer)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<List, Message> {
+        Ok(List(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<List, Message> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<List, Message> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<Tagged<List>, Message> {
+        Ok(Tagged { variant, content: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Map, Message> {
+        Ok(Map { map: Meta::new(), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Map, Message> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<Tagged<Map>, Message> {
+        Ok(Tagged { variant, content: self.serialize_map(Some(len))? })
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<Self::Ok, Message> where T: Display + ?Sized {
+        Ok(Some(MetaString(value.to_string())))
+    }
+}
+
+pub struct List(Vec<MetaValue>);
+
+impl List {
+    fn push<T>(&mut self, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        self.0.extend(value.serialize(Serializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for List {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Message> {
+        Ok(Some(MetaList(self.0)))
+    }
+}
+
+impl ser::SerializeTuple for List {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Message> {
+        Ok(Some(MetaList(self.0)))
+    }
+}
+
+impl ser::SerializeTupleStruct for List {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Message> {
+        Ok(Some(MetaList(self.0)))
+    }
+}
+
+pub struct Map {
+    map: Meta,
+    key: Option<String>,
+}
+
+impl Map {
+    fn insert<T>(&mut self, key: String, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        if let Some(value) = value.serialize(Serializer)? {
+            self.map.insert(key, value);
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for Map {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        match key.serialize(Serializer)? {
+            Some(MetaString(key)) => {
+                self.key = Some(key);
+                Ok(())
+            },
+            Some(MetaBool(key)) => {
+                self.key = Some(key.to_string());
+                Ok(())
+            },
+            _ => Err(Message("metadata keys must be text, numbers or booleans".to_string())),
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        let key = self.key.take().unwrap();
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Message> {
+        Ok(Some(MetaMap(self.map)))
+    }
+}
+
+impl ser::SerializeStruct for Map {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Message> {
+        Ok(Some(MetaMap(self.map)))
+    }
+}
+
+/// Content of a tuple or struct variant, written as a map of one entry.
+pub struct Tagged<T> {
+    variant: &'static str,
+    content: T,
+}
+
+/// A map of one entry, the variant name mapping to its content.
+fn tagged(variant: &str, content: Option<MetaValue>) -> Option<MetaValue> {
+    Some(MetaMap(content.map(|it| (variant.to_string(), it)).into_iter().collect()))
+}
+
+impl ser::SerializeTupleVariant for Tagged<List> {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        self.content.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Message> {
+        Ok(tagged(self.variant, Some(MetaList(self.content.0))))
+    }
+}
+
+impl ser::SerializeStructVariant for Tagged<Map> {
+    type Ok = Option<MetaValue>;
+    type Error = Message;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Message> where T: Serialize + ?Sized {
+        self.content.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Message> {
+        Ok(tagged(self.variant, Some(MetaMap(self.content.map))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use crate::definition::{Block, Inline, Meta, MetaValue};
+    use crate::definition::MetaValue::{MetaBlocks, MetaBool, MetaInlines, MetaList, MetaMap, MetaString};
+    use super::{from_value, to_value, MetaExt};
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Shape {
+        Square,
+        Circle(f64),
+        Rectangle { width: u32, height: u32 },
+        Polygon(u32, u32),
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Options {
+        columns: usize,
+        title: String,
+        numbered: bool,
+        #[serde(default)]
+        classes: Vec<String>,
+        #[serde(default)]
+        note: Option<String>,
+        shapes: Vec<Shape>,
+    }
+
+    fn map(entries: Vec<(&str, MetaValue)>) -> MetaValue {
+        MetaMap(entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    fn string(text: &str) -> MetaValue {
+        MetaString(text.into())
+    }
+
+    fn meta(key: &str, value: MetaValue) -> Meta {
+        vec![(key.to_string(), value)].into_iter().collect()
+    }
+
+    fn options() -> MetaValue {
+        map(vec![
+            ("columns", string("3")),
+            ("title", MetaInlines(vec![Inline::Str("A".into()), Inline::Space, Inline::Str("title".into())])),
+            ("numbered", string("true")),
+            ("shapes", MetaList(vec![
+                string("square"),
+                map(vec![("circle", MetaBlocks(vec![Block::Plain(vec![Inline::Str("1.5".into())])]))]),
+                map(vec![("rectangle", map(vec![("width", string("2")), ("height", string("4"))]))]),
+                map(vec![("polygon", MetaList(vec![string("5"), string("6")]))]),
+            ])),
+        ])
+    }
+
+    fn json(value: &MetaValue) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn structs_are_read_from_maps() {
+        let options: Options = meta("my-filter", options()).get_as("my-filter").unwrap().unwrap();
+        assert_eq!(options, Options {
+            columns: 3,
+            title: "A title".into(),
+            numbered: true,
+            classes: vec![],
+            note: None,
+            shapes: vec![Shape::Square, Shape::Circle(1.5), Shape::Rectangle { width: 2, height: 4 }, Shape::Polygon(5, 6)],
+        });
+    }
+
+    #[test]
+    fn missing_keys_and_options() {
+        let meta = meta("note", string("n"));
+        assert_eq!(meta.get_as::<String>("missing").unwrap(), None);
+        assert_eq!(meta.get_as::<Option<String>>("note").unwrap(), Some(Some("n".to_string())));
+
+        let mut options = options();
+        if let MetaMap(map) = &mut options {
+            map.insert("note".into(), string("a note"));
+            map.insert("classes".into(), string("wide"));
+        }
+        let options: Options = from_value(&options).unwrap();
+        assert_eq!(options.note.as_deref(), Some("a note"));
+        assert_eq!(options.classes, ["wide"]);
+    }
+
+    #[test]
+    fn enums_are_read_from_names_and_maps_of_one_entry() {
+        assert_eq!(from_value::<Shape>(&string("square")).unwrap(), Shape::Square);
+        assert_eq!(from_value::<Shape>(&MetaInlines(vec![Inline::Str("square".into())])).unwrap(), Shape::Square);
+        assert_eq!(from_value::<Shape>(&map(vec![("circle", string("2"))])).unwrap(), Shape::Circle(2.0));
+        assert_eq!(from_value::<Shape>(&map(vec![("polygon", MetaList(vec![string("3"), string("4")]))])).unwrap(), Shape::Polygon(3, 4));
+
+        let error = |value: &MetaValue| from_value::<Shape>(value).unwrap_err().to_string();
+        assert_eq!(error(&string("circle")), "invalid metadata at circle: variant `circle` expects a content");
+        assert_eq!(error(&MetaList(vec![])), "invalid metadata: expected a variant name or a map of one entry, found a list");
+        assert!(error(&string("hexagon")).starts_with("invalid metadata: unknown variant `hexagon`"));
+    }
+
+    #[test]
+    fn errors_give_the_path_of_the_value() {
+        let error = |value: MetaValue| meta("my-filter", value).get_as::<Options>("my-filter").unwrap_err().to_string();
+        let with = |key: &str, value: MetaValue| {
+            let mut options = options();
+            if let MetaMap(map) = &mut options {
+                map.insert(key.into(), value);
+            }
+            options
+        };
+        assert_eq!(error(with("columns", string("three"))), "invalid metadata at my-filter.columns: expected an integer, found `three`");
+        assert_eq!(error(with("numbered", map(vec![]))), "invalid metadata at my-filter.numbered: expected a boolean, found a map");
+        assert_eq!(error(with("title", MetaList(vec![]))), "invalid metadata at my-filter.title: expected text, found a list");
+        assert_eq!(
+            error(with("shapes", MetaList(vec![string("square"), map(vec![("rectangle", map(vec![("width", string("2")), ("height", MetaBool(true))]))])]))),
+            "invalid metadata at my-filter.shapes[1].rectangle.height: expected an integer, found `true`",
+        );
+        assert_eq!(error(string("text")), "invalid metadata at my-filter: expected a map, found text");
+        assert_eq!(meta("columns", string("x")).deserialize_as::<Options>().unwrap_err().to_string(), "invalid metadata at columns: expected an integer, found `x`");
+    }
+
+    #[test]
+    fn written_values_are_read_back() {
+        let options = Options {
+            columns: 2,
+            title: "Title".into(),
+            numbered: false,
+            classes: vec!["a".into(), "b".into()],
+            note: None,
+            shapes: vec![Shape::Square, Shape::Circle(0.5), Shape::Rectangle { width: 1, height: 2 }, Shape::Polygon(3, 4)],
+        };
+        let value = to_value(&options).unwrap();
+        assert_eq!(json(&value), json(&map(vec![
+            ("columns", string("2")),
+            ("title", string("Title")),
+            ("numbered", MetaBool(false)),
+            ("classes", MetaList(vec![string("a"), string("b")])),
+            ("shapes", MetaList(vec![
+                string("square"),
+                map(vec![("circle", string("0.5"))]),
+                map(vec![("rectangle", map(vec![("width", string("1")), ("height", string("2"))]))]),
+                map(vec![("polygon", MetaList(vec![string("3"), string("4")]))]),
+            ])),
+        ])));
+        assert_eq!(from_value::<Options>(&value).unwrap(), options);
+
+        let mut meta = Meta::new();
+        meta.insert_as("my-filter", &options).unwrap();
+        assert_eq!(meta.get_as::<Options>("my-filter").unwrap(), Some(options));
+        assert_eq!(to_value(&()).unwrap_err().to_string(), "invalid metadata: no metadata value for a unit or a `None`");
+        assert_eq!(meta.insert_as("key", &None::<u32>).unwrap_err().to_string(), "invalid metadata at key: no metadata value for a unit or a `None`");
+    }
+}
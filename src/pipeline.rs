@@ -0,0 +1,167 @@
+use std::error::Error as StdError;
+use crate::ReadOptions;
+use crate::definition::Pandoc;
+use crate::walkable::Walkable;
+
+type Pass<'a, S> = Box<dyn FnMut(Pandoc, &mut S) -> crate::Result<Pandoc> + 'a>;
+
+/// Passes run one after the other over a single parsed document, sharing a state of type `S`.
+///
+/// ```no_run
+/// use std::collections::HashSet;
+/// use pandoc::definition::{Block, Inline};
+/// use pandoc::pipeline::Pipeline;
+///
+/// Pipeline::new(HashSet::new())
+///     .walk(|ids: &mut HashSet<String>, block: Block| {
+///         if let Block::Header(_, attr, _) = &block {
+///             ids.insert(attr.id.clone());
+///         }
+///         block
+///     })
+///     .walk(|ids: &mut HashSet<String>, inline: Inline| match inline {
+///         Inline::Link(attr, ils, (url, _)) if url.starts_with('#') && !ids.contains(&url[1..]) => Inline::Span(attr, ils),
+///         _ => inline
+///     })
+///     .to_json_filter()
+///     .unwrap();
+/// ```
+pub struct Pipeline<'a, S> {
+    state: S,
+    passes: Vec<Pass<'a, S>>,
+    read_options: ReadOptions,
+}
+
+impl<'a, S> Pipeline<'a, S> {
+    pub fn new(state: S) -> Self {
+        Pipeline { state, passes: Vec::new(), read_options: ReadOptions::default() }
+    }
+
+    /// Adds a pass transforming the whole document.
+    pub fn pass<F>(self, mut f: F) -> Self where F: FnMut(Pandoc, &mut S) -> Pandoc + 'a {
+        self.push(move |pandoc, state| Ok(f(pandoc, state)))
+    }
+
+    /// Adds a fallible pass; its first error stops the pipeline.
+    pub fn try_pass<F, E>(self, mut f: F) -> Self where F: FnMut(Pandoc, &mut S) -> Result<Pandoc, E> + 'a, E: Into<Box<dyn StdError + Send + Sync>> {
+        self.push(move |pandoc, state| f(pandoc, state).map_err(crate::Error::filter))
+    }
+
+    /// Adds a pass walking the document like [`to_json_filter`](crate::to_json_filter), `f`
+    /// also receiving the state.
+    pub fn walk<F, I, O>(self, mut f: F) -> Self where Pandoc: Walkable<I, O>, F: FnMut(&mut S, I) -> O + 'a {
+        self.push(move |pandoc, state| Ok(pandoc.walk(&mut |it| f(state, it))))
+    }
+
+    fn push<F>(mut self, f: F) -> Self where F: FnMut(Pandoc, &mut S) -> crate::Result<Pandoc> + 'a {
+        self.passes.push(Box::new(f));
+        self
+    }
+
+    /// Sets how [`Pipeline::to_json_filter`] reads the document.
+    pub fn read_options(mut self, options: ReadOptions) -> Self {
+        self.read_options = options;
+        self
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn into_state(self) -> S {
+        self.state
+    }
+
+    /// Runs the passes in the order they have been added.
+    pub fn apply(&mut self, pandoc: Pandoc) -> crate::Result<Pandoc> {
+        let mut pandoc = pandoc;
+        for pass in &mut self.passes {
+            pandoc = pass(pandoc, &mut self.state)?;
+        }
+        Ok(pandoc)
+    }
+
+    /// Reads the document once from the standard input, runs the passes and writes the result
+    /// once to the standard output.
+    pub fn to_json_filter(&mut self) -> crate::Result<()> {
+        let pandoc = crate::read_pandoc_with(&self.read_options)?;
+        let pandoc = self.apply(pandoc)?;
+        crate::write_pandoc(&pandoc)
+    }
+}
+
+impl<'a, S> Default for Pipeline<'a, S> where S: Default {
+    fn default() -> Self {
+        Pipeline::new(S::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Block, Inline, Pandoc};
+    use crate::definition::Block::Para;
+    use crate::definition::Inline::Str;
+    use crate::utils::stringify;
+    use crate::Error;
+    use super::Pipeline;
+
+    fn document(text: &str) -> Pandoc {
+        Pandoc { version: vec![1, 23], meta: Default::default(), blocks: vec![Para(vec![Str(text.into())])] }
+    }
+
+    fn append(text: &'static str) -> impl FnMut(Pandoc, &mut Vec<String>) -> Pandoc {
+        move |mut pandoc, passes| {
+            passes.push(text.to_string());
+            pandoc.blocks.push(Para(vec![Str(text.into())]));
+            pandoc
+        }
+    }
+
+    #[test]
+    fn passes_run_in_the_order_they_are_added() {
+        let mut pipeline = Pipeline::new(Vec::new()).pass(append("b")).pass(append("c")).pass(append("d"));
+        let pandoc = pipeline.apply(document("a")).unwrap();
+        assert_eq!(stringify(&pandoc.blocks), "a b c d");
+        assert_eq!(pipeline.into_state(), ["b", "c", "d"]);
+    }
+
+    #[test]
+    fn the_state_is_shared_by_the_passes() {
+        let mut pipeline = Pipeline::new(0)
+            .walk(|count: &mut i32, inline: Inline| {
+                *count += 1;
+                inline
+            })
+            .pass(|mut pandoc, count| {
+                pandoc.blocks.push(Para(vec![Str(count.to_string())]));
+                *count *= 10;
+                pandoc
+            })
+            .walk(|count: &mut i32, block: Block| match block {
+                Para(_) => {
+                    *count += 1;
+                    Para(vec![Str(count.to_string())])
+                },
+                _ => block,
+            });
+        let pandoc = pipeline.apply(document("a")).unwrap();
+        assert_eq!(stringify(&pandoc.blocks), "11 12");
+        assert_eq!(*pipeline.state(), 12);
+        // The state is kept from one document to the next.
+        pipeline.apply(document("a")).unwrap();
+        assert_eq!(*pipeline.state(), (12 + 1) * 10 + 2);
+    }
+
+    #[test]
+    fn a_failing_pass_stops_the_pipeline() {
+        let mut pipeline = Pipeline::new(Vec::new())
+            .pass(append("b"))
+            .try_pass(|pandoc, _: &mut Vec<String>| if pandoc.blocks.len() > 1 { Err("too long") } else { Ok(pandoc) })
+            .pass(append("c"));
+        match pipeline.apply(document("a")) {
+            Err(Error::Filter(error)) => assert_eq!(error.to_string(), "too long"),
+            _ => panic!("the pipeline should fail"),
+        }
+        assert_eq!(pipeline.state(), &["b"]);
+    }
+}
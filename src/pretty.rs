@@ -1,15 +1,10 @@
-use std::io;
-use std::io::{BufRead, stdin};
-use pandoc::definition::Pandoc;
+use std::io::{BufWriter, Write, stdout};
 
-fn main() -> io::Result<()> {
-    let mut pandoc_json = String::new();
-    for line in  stdin().lock().lines() {
-        if let Ok(line) = line {
-            pandoc_json += line.as_str();
-        }
-    }
-    let pandoc: Pandoc = serde_json::from_str(&pandoc_json)?;
-    println!("{}", serde_json::to_string_pretty(&pandoc)?);
+fn main() -> pandoc::Result<()> {
+    let pandoc = pandoc::read_pandoc()?;
+    let mut stdout = BufWriter::new(stdout().lock());
+    serde_json::to_writer_pretty(&mut stdout, &pandoc)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use crate::definition::{Attr, Block, Caption, Cell, Citation, Inline, Meta, MetaValue, Pandoc, Row, Target};
+use crate::definition::MetaValue::{MetaList, MetaMap};
+
+pub use pandoc_derive::Queryable;
+
+/// A value that can be combined with others of its kind, `empty` being the neutral element.
+pub trait Monoid {
+    fn empty() -> Self;
+    fn append(self, other: Self) -> Self;
+}
+
+impl Monoid for () {
+    fn empty() -> Self {}
+    fn append(self, _: Self) -> Self {}
+}
+
+impl<T> Monoid for Vec<T> {
+    fn empty() -> Self {
+        Vec::new()
+    }
+
+    fn append(mut self, other: Self) -> Self {
+        self.extend(other);
+        self
+    }
+}
+
+impl Monoid for String {
+    fn empty() -> Self {
+        String::new()
+    }
+
+    fn append(self, other: Self) -> Self {
+        self + other.as_str()
+    }
+}
+
+impl<T: Monoid> Monoid for Option<T> {
+    fn empty() -> Self {
+        None
+    }
+
+    fn append(self, other: Self) -> Self {
+        match (self, other) {
+            (Some(a), Some(b)) => Some(a.append(b)),
+            (a, None) => a,
+            (None, b) => b,
+        }
+    }
+}
+
+impl<A: Monoid, B: Monoid> Monoid for (A, B) {
+    fn empty() -> Self {
+        (A::empty(), B::empty())
+    }
+
+    fn append(self, other: Self) -> Self {
+        (self.0.append(other.0), self.1.append(other.1))
+    }
+}
+
+macro_rules! sum_monoid {
+    ($($t:ty),*) => {
+        $(impl Monoid for $t {
+            fn empty() -> Self {
+                0
+            }
+
+            fn append(self, other: Self) -> Self {
+                self + other
+            }
+        })*
+    };
+}
+
+sum_monoid!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// Read-only counterpart of [`Walkable`](crate::walkable::Walkable), for the same targets: `f` is
+/// applied to every `T` of the tree, in document order, and the results are combined.
+///
+/// ```
+/// use pandoc::definition::{Block, Inline};
+/// use pandoc::queryable::Queryable;
+///
+/// let blocks = vec![Block::Para(vec![Inline::Str("Hello".into()), Inline::Space, Inline::Emph(vec![Inline::Str("world".into())])])];
+/// let words: usize = blocks.query(&mut |inline: &Inline| match inline {
+///     Inline::Str(_) => 1,
+///     _ => 0
+/// });
+/// assert_eq!(words, 2);
+/// ```
+pub trait Queryable<T> {
+    fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&T) -> R, R: Monoid;
+}
+
+impl<T, Q> Queryable<T> for Vec<Q> where Q: Queryable<T> {
+    fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&T) -> R, R: Monoid {
+        self.iter().fold(R::empty(), |acc, it| acc.append(it.query(f)))
+    }
+}
+
+impl<T, Q> Queryable<T> for Option<Q> where Q: Queryable<T> {
+    fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&T) -> R, R: Monoid {
+        match self {
+            Some(it) => it.query(f),
+            None => R::empty()
+        }
+    }
+}
+
+/// The query of the children of a node, derived with `#[walkable(node)]`, as
+/// [`WalkChildren`](crate::walkable::WalkChildren) is for `Walkable`.
+pub(crate) trait QueryChildren<T> {
+    fn query_children<F, R>(&self, f: &mut F) -> R where F: FnMut(&T) -> R, R: Monoid;
+}
+
+impl<K, V, T> QueryChildren<T> for BTreeMap<K, V> where V: Queryable<T> {
+    fn query_children<F, R>(&self, f: &mut F) -> R where F: FnMut(&T) -> R, R: Monoid {
+        self.values().fold(R::empty(), |acc, it| acc.append(it.query(f)))
+    }
+}
+
+impl Queryable<Pandoc> for Pandoc {
+    fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&Pandoc) -> R, R: Monoid {
+        f(self)
+    }
+}
+
+impl Queryable<Meta> for Pandoc {
+    fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&Meta) -> R, R: Monoid {
+        self.meta.query(f)
+    }
+}
+
+impl Queryable<Meta> for Meta {
+    fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&Meta) -> R, R: Monoid {
+        f(self).append(self.query_children(f))
+    }
+}
+
+impl Queryable<Meta> for MetaValue {
+    fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&Meta) -> R, R: Monoid {
+        match self {
+            MetaMap(map) => map.query(f),
+            MetaList(values) => values.query(f),
+            _ => R::empty()
+        }
+    }
+}
+
+/// The nodes without children.
+macro_rules! query_leaf {
+    ($($t:ty),*) => {
+        $(impl<T> QueryChildren<T> for $t {
+            fn query_children<F, R>(&self, _: &mut F) -> R where F: FnMut(&T) -> R, R: Monoid {
+                R::empty()
+            }
+        })*
+    };
+}
+
+/// Queries a `$t`, then its children.
+macro_rules! query_node {
+    ($($t:ty),*) => {
+        $(impl Queryable<$t> for $t {
+            fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&$t) -> R, R: Monoid {
+                f(self).append(self.query_children(f))
+            }
+        })*
+    };
+}
+
+/// Queries the nodes `$other` for the target `$t`: only their children are queried.
+macro_rules! query_across {
+    ($t:ty; $($other:ty),*) => {
+        $(impl Queryable<$t> for $other {
+            fn query<F, R>(&self, f: &mut F) -> R where F: FnMut(&$t) -> R, R: Monoid {
+                self.query_children(f)
+            }
+        })*
+    };
+}
+
+query_leaf!(Attr, Target);
+query_node!(Block, Inline, Attr, Target, Caption, Row, Cell, Citation);
+
+query_across!(Inline; Pandoc, Meta, MetaValue, Block, Attr, Target, Caption, Row, Cell, Citation);
+query_across!(Block; Pandoc, Meta, MetaValue, Inline, Attr, Target, Caption, Row, Cell, Citation);
+query_across!(Attr; Pandoc, Meta, MetaValue, Block, Inline, Target, Caption, Row, Cell, Citation);
+query_across!(Target; Pandoc, Meta, MetaValue, Block, Inline, Attr, Caption, Row, Cell, Citation);
+query_across!(Caption; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Row, Cell, Citation);
+query_across!(Row; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Cell, Citation);
+query_across!(Cell; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Row, Citation);
+query_across!(Citation; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Row, Cell);
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Block, Inline};
+    use crate::definition::{Attr, Caption, Cell, Citation, Meta, MetaValue, Row, Target};
+    use crate::walkable::Blocks;
+    use crate::walkable::tests::{block_name, document, inline_name, names, other_targets, samples};
+    use super::Queryable;
+
+    #[test]
+    fn every_variant_is_queried_in_document_order() {
+        for (name, sample) in samples() {
+            let inlines: Vec<String> = sample.query(&mut |inline: &Inline| vec![inline_name(inline)]);
+            assert_eq!(inlines, names(&sample, true, false), "{}", name);
+            let blocks: Vec<String> = sample.query(&mut |block: &Block| vec![block_name(block)]);
+            assert_eq!(blocks, names(&sample, false, false), "{}", name);
+        }
+    }
+
+    #[test]
+    fn other_targets_are_queried_in_every_variant() {
+        fn visits<T>(blocks: &Blocks) -> usize where Blocks: Queryable<T> {
+            blocks.query(&mut |_: &T| 1)
+        }
+        for (name, sample) in samples() {
+            let visited = [visits::<Attr>(&sample), visits::<Target>(&sample), visits::<Caption>(&sample), visits::<Row>(&sample), visits::<Cell>(&sample), visits::<Citation>(&sample)];
+            assert_eq!(visited, other_targets(&sample), "{}", name);
+        }
+    }
+
+    #[test]
+    fn documents_are_queried_with_their_metadata() {
+        let pandoc = document();
+        let inlines: usize = pandoc.query(&mut |_: &Inline| 1);
+        let blocks: usize = pandoc.query(&mut |_: &Block| 1);
+        assert_eq!((inlines, blocks), (names(&pandoc, true, false).len(), names(&pandoc, false, false).len()));
+        let metas: Vec<usize> = pandoc.query(&mut |meta: &Meta| vec![meta.len()]);
+        assert_eq!(metas, [4, 2]);
+    }
+
+    #[test]
+    fn nested_metadata_is_queried_before_its_content() {
+        fn map(entries: Vec<(&str, MetaValue)>) -> Meta {
+            entries.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+        }
+        let innermost = map(vec![("c", MetaValue::MetaBool(true))]);
+        let inner = map(vec![("b", MetaValue::MetaList(vec![MetaValue::MetaMap(innermost)])), ("d", MetaValue::MetaString("d".into()))]);
+        let outer = map(vec![("a", MetaValue::MetaMap(inner)), ("e", MetaValue::MetaMap(Meta::new())), ("f", MetaValue::MetaBool(false))]);
+        let keys: Vec<Vec<String>> = outer.query(&mut |meta: &Meta| vec![meta.keys().cloned().collect()]);
+        assert_eq!(keys, [vec!["a", "e", "f"], vec!["b", "d"], vec!["c"], vec![]]);
+    }
+}
@@ -0,0 +1,517 @@
+//! CommonMark reader, with the GFM tables, strikethrough, task lists, autolinks and footnotes,
+//! and optionally header attributes.
+//!
+//! ```
+//! use pandoc::definition::Block;
+//! use pandoc::readers::commonmark;
+//!
+//! let pandoc = commonmark::read("# Hello *world*", &commonmark::Options::default());
+//! match &pandoc.blocks[0] {
+//!     Block::Header(1, attr, _) => assert_eq!(attr.id, "hello-world"),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use pulldown_cmark::{CodeBlockKind, Event, LinkType, Parser, Tag, TagEnd};
+use crate::compat::API_VERSION;
+use crate::definition::{Alignment, Attr, Block, Caption, Cell, ColSpec, ColWidth, Inline, ListNumberDelim, ListNumberStyle, MathType, Meta, Pandoc, Row, TableBody, TableFoot, TableHead};
+use crate::definition::Block::{BlockQuote, BulletList, CodeBlock, Header, HorizontalRule, OrderedList, Para, Plain, RawBlock, Table};
+use crate::definition::Inline::{Code, Emph, Image, LineBreak, Link, Math, Note, RawInline, SoftBreak, Space, Str, Strikeout, Strong};
+use crate::utils::stringify;
+use crate::walkable_mut::WalkableMut;
+
+/// How headers without an explicit identifier get one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Identifiers {
+    /// Left without identifier.
+    None,
+    /// Pandoc's `auto_identifiers`: `1. Hello, World!` gives `hello-world`.
+    Pandoc,
+    /// Pandoc's `gfm_auto_identifiers`, as on GitHub: `1. Hello, World!` gives `1-hello-world`.
+    Gfm,
+}
+
+/// Reader settings; the default parses GFM with pandoc identifiers.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Enable the GFM extensions and footnotes, otherwise parse strict CommonMark.
+    pub gfm: bool,
+    /// Pandoc's `attributes` extension, limited to headers: `# Title {#id .class}`.
+    pub attributes: bool,
+    pub identifiers: Identifiers,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { gfm: true, attributes: false, identifiers: Identifiers::Pandoc }
+    }
+}
+
+pub fn read(input: &str, options: &Options) -> Pandoc {
+    let mut parser_options = pulldown_cmark::Options::empty();
+    if options.attributes {
+        parser_options |= pulldown_cmark::Options::ENABLE_HEADING_ATTRIBUTES;
+    }
+    if options.gfm {
+        parser_options |= pulldown_cmark::Options::ENABLE_TABLES
+            | pulldown_cmark::Options::ENABLE_STRIKETHROUGH
+            | pulldown_cmark::Options::ENABLE_TASKLISTS
+            | pulldown_cmark::Options::ENABLE_FOOTNOTES;
+    }
+    let events: Vec<(Event, Range<usize>)> = Parser::new_ext(input, parser_options).into_offset_iter().collect();
+    let notes = note_definitions(&events);
+    let mut reader = Reader { source: input, events: &events, position: 0, notes: &notes, open_notes: Vec::new(), in_link: false, gfm: options.gfm };
+    let mut pandoc = Pandoc { version: API_VERSION.to_vec(), meta: Meta::new(), blocks: reader.blocks() };
+    if options.identifiers != Identifiers::None {
+        add_identifiers(&mut pandoc, options.identifiers);
+    }
+    pandoc
+}
+
+/// Locates the events of each footnote definition, as `[first, last)` indices.
+fn note_definitions(events: &[(Event, Range<usize>)]) -> HashMap<String, Range<usize>> {
+    let mut result = HashMap::new();
+    let mut current: Option<(String, usize)> = None;
+    for (i, (event, _)) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => current = Some((label.to_string(), i + 1)),
+            Event::End(TagEnd::FootnoteDefinition) => if let Some((label, start)) = current.take() {
+                result.entry(label).or_insert(start..i);
+            },
+            _ => {}
+        }
+    }
+    result
+}
+
+struct Reader<'a, 'e> {
+    source: &'a str,
+    events: &'e [(Event<'a>, Range<usize>)],
+    position: usize,
+    notes: &'e HashMap<String, Range<usize>>,
+    /// Labels of the notes being read, to stop on self-referencing notes.
+    open_notes: Vec<String>,
+    in_link: bool,
+    gfm: bool,
+}
+
+impl<'a, 'e> Reader<'a, 'e> {
+    fn next(&mut self) -> Option<&'e (Event<'a>, Range<usize>)> {
+        let event = self.events.get(self.position);
+        self.position += 1;
+        event
+    }
+
+    fn peek(&self) -> Option<&'e Event<'a>> {
+        self.events.get(self.position).map(|it| &it.0)
+    }
+
+    /// Reads blocks up to the end of the enclosing element.
+    fn blocks(&mut self) -> Vec<Block> {
+        let mut result = Vec::new();
+        while let Some(event) = self.peek() {
+            if let Event::End(_) = event {
+                self.position += 1;
+                break;
+            }
+            result.extend(self.block());
+        }
+        result
+    }
+
+    fn block(&mut self) -> Option<Block> {
+        let (event, range) = self.next()?;
+        match event {
+            Event::Start(Tag::Paragraph) => Some(Para(self.inlines())),
+            Event::Start(Tag::Heading { level, id, classes, attrs }) => {
+                let attr = Attr {
+                    id: id.as_deref().unwrap_or_default().to_string(),
+                    classes: classes.iter().map(|it| it.to_string()).collect(),
+                    attributes: attrs.iter().map(|(k, v)| (k.to_string(), v.as_deref().unwrap_or_default().to_string())).collect(),
+                };
+                Some(Header(*level as u8, attr, self.inlines()))
+            },
+            Event::Start(Tag::BlockQuote(_)) => Some(BlockQuote(self.blocks())),
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let mut attr = Attr::default();
+                if let CodeBlockKind::Fenced(info) = kind {
+                    if let Some(lang) = info.split_whitespace().next().filter(|it| !it.starts_with('{')) {
+                        attr.classes.push(lang.to_string());
+                    }
+                }
+                let code = self.text();
+                Some(CodeBlock(attr, code.strip_suffix('\n').unwrap_or(&code).to_string()))
+            },
+            Event::Start(Tag::HtmlBlock) => Some(RawBlock("html".to_string(), self.text())),
+            Event::Start(Tag::List(start)) => {
+                let mut items = Vec::new();
+                while let Some((Event::Start(Tag::Item), _)) = self.next() {
+                    items.push(self.item());
+                }
+                match start {
+                    Some(start) => {
+                        let delim = match self.source[range.clone()].trim_start().trim_start_matches(|c: char| c.is_ascii_digit()).chars().next() {
+                            Some(')') => ListNumberDelim::OneParen,
+                            _ => ListNumberDelim::Period,
+                        };
+                        Some(OrderedList((*start, ListNumberStyle::Decimal, delim), items))
+                    },
+                    None => Some(BulletList(items)),
+                }
+            },
+            Event::Start(Tag::Table(aligns)) => Some(self.table(aligns)),
+            // Footnote definitions are read where they are referenced.
+            Event::Start(_) => {
+                self.blocks();
+                None
+            },
+            Event::Html(html) => Some(RawBlock("html".to_string(), html.to_string())),
+            Event::Rule => Some(HorizontalRule),
+            _ => None,
+        }
+    }
+
+    /// Reads an item, whose inlines are not wrapped in a paragraph when the list is tight.
+    fn item(&mut self) -> Vec<Block> {
+        let mut result = Vec::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some(Event::End(_)) => {
+                    self.position += 1;
+                    break;
+                },
+                Some(event) if is_inline(event) => {
+                    let mut ils = Vec::new();
+                    while self.peek().is_some_and(is_inline) {
+                        ils.extend(self.inline());
+                    }
+                    result.push(Plain(normalize(ils)));
+                },
+                Some(_) => result.extend(self.block()),
+            }
+        }
+        result
+    }
+
+    fn table(&mut self, aligns: &[pulldown_cmark::Alignment]) -> Block {
+        let specs = aligns.iter()
+            .map(|it| {
+                let align = match it {
+                    pulldown_cmark::Alignment::None => Alignment::AlignDefault,
+                    pulldown_cmark::Alignment::Left => Alignment::AlignLeft,
+                    pulldown_cmark::Alignment::Center => Alignment::AlignCenter,
+                    pulldown_cmark::Alignment::Right => Alignment::AlignRight,
+                };
+                ColSpec(align, ColWidth::ColWidthDefault)
+            }).collect();
+        let mut head = Vec::new();
+        let mut body = Vec::new();
+        while let Some((event, _)) = self.next() {
+            match event {
+                Event::Start(Tag::TableHead) => head.push(self.row()),
+                Event::Start(Tag::TableRow) => body.push(self.row()),
+                _ => break,
+            }
+        }
+        let body = if body.is_empty() { vec![] } else { vec![TableBody(Attr::default(), 0, vec![], body)] };
+        Table(
+            Attr::default(),
+            Caption(None, vec![]),
+            specs,
+            TableHead(Attr::default(), head),
+            body,
+            TableFoot(Attr::default(), vec![]),
+        )
+    }
+
+    fn row(&mut self) -> Row {
+        let mut cells = Vec::new();
+        while let Some((Event::Start(Tag::TableCell), _)) = self.next() {
+            let ils = self.inlines();
+            let blks = if ils.is_empty() { vec![] } else { vec![Plain(ils)] };
+            cells.push(Cell(Attr::default(), Alignment::AlignDefault, 1, 1, blks));
+        }
+        Row(Attr::default(), cells)
+    }
+
+    /// Concatenates the text up to the end of the enclosing element.
+    fn text(&mut self) -> String {
+        let mut result = String::new();
+        while let Some((event, _)) = self.next() {
+            match event {
+                Event::Text(text) | Event::Html(text) => result.push_str(text),
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// Reads inlines up to the end of the enclosing element.
+    fn inlines(&mut self) -> Vec<Inline> {
+        let mut result = Vec::new();
+        while let Some(event) = self.peek() {
+            if let Event::End(_) = event {
+                self.position += 1;
+                break;
+            }
+            result.extend(self.inline());
+        }
+        normalize(result)
+    }
+
+    fn inline(&mut self) -> Vec<Inline> {
+        let (event, _) = match self.next() {
+            Some(it) => it,
+            None => return vec![],
+        };
+        match event {
+            Event::Text(text) => {
+                // Text comes in several events around the would-be delimiters, e.g. `_`.
+                let mut text = text.to_string();
+                while let Some(Event::Text(next)) = self.peek() {
+                    text.push_str(next);
+                    self.position += 1;
+                }
+                self.words(&text)
+            },
+            Event::Code(code) => vec![Code(Attr::default(), code.to_string())],
+            Event::InlineMath(math) => vec![Math(MathType::InlineMath, math.to_string())],
+            Event::DisplayMath(math) => vec![Math(MathType::DisplayMath, math.to_string())],
+            Event::InlineHtml(html) | Event::Html(html) => vec![RawInline("html".to_string(), html.to_string())],
+            Event::SoftBreak => vec![SoftBreak],
+            Event::HardBreak => vec![LineBreak],
+            Event::TaskListMarker(checked) => vec![Str(if *checked { "☒" } else { "☐" }.to_string()), Space],
+            Event::FootnoteReference(label) => vec![self.note(label)],
+            Event::Start(Tag::Emphasis) => vec![Emph(self.inlines())],
+            Event::Start(Tag::Strong) => vec![Strong(self.inlines())],
+            Event::Start(Tag::Strikethrough) => vec![Strikeout(self.inlines())],
+            Event::Start(Tag::Link { link_type, dest_url, title, .. }) => {
+                let in_link = std::mem::replace(&mut self.in_link, true);
+                let ils = self.inlines();
+                self.in_link = in_link;
+                let attr = match link_type {
+                    LinkType::Autolink => Attr { classes: vec!["uri".to_string()], ..Attr::default() },
+                    LinkType::Email => Attr { classes: vec!["email".to_string()], ..Attr::default() },
+                    _ => Attr::default(),
+                };
+                let url = if *link_type == LinkType::Email { format!("mailto:{}", dest_url) } else { dest_url.to_string() };
+                vec![Link(attr, ils, (url, title.to_string()))]
+            },
+            Event::Start(Tag::Image { dest_url, title, .. }) => {
+                vec![Image(Attr::default(), self.inlines(), (dest_url.to_string(), title.to_string()))]
+            },
+            Event::Start(_) => self.inlines(),
+            _ => vec![],
+        }
+    }
+
+    fn note(&mut self, label: &str) -> Inline {
+        let range = match self.notes.get(label) {
+            Some(range) if !self.open_notes.iter().any(|it| it == label) => range.clone(),
+            _ => return Str(format!("[^{}]", label)),
+        };
+        let mut open_notes = self.open_notes.clone();
+        open_notes.push(label.to_string());
+        let mut reader = Reader { events: &self.events[range], position: 0, open_notes, in_link: false, ..*self };
+        let mut blocks = Vec::new();
+        while reader.position < reader.events.len() {
+            blocks.extend(reader.block());
+        }
+        Note(blocks)
+    }
+
+    /// Splits text into words and spaces, turning the bare URLs into links in GFM.
+    fn words(&self, text: &str) -> Vec<Inline> {
+        let mut result = Vec::new();
+        for (i, word) in text.split([' ', '\t']).enumerate() {
+            if i > 0 {
+                result.push(Space);
+            }
+            if word.is_empty() {
+                continue;
+            }
+            match bare_url(word).filter(|_| self.gfm && !self.in_link) {
+                Some((before, url, after)) => {
+                    if !before.is_empty() {
+                        result.push(Str(before.to_string()));
+                    }
+                    let target = if url.starts_with("www.") { format!("http://{}", url) } else { url.to_string() };
+                    let attr = Attr { classes: vec!["uri".to_string()], ..Attr::default() };
+                    result.push(Link(attr, vec![Str(url.to_string())], (target, String::new())));
+                    if !after.is_empty() {
+                        result.push(Str(after.to_string()));
+                    }
+                },
+                None => result.push(Str(word.to_string())),
+            }
+        }
+        result
+    }
+}
+
+fn is_inline(event: &Event) -> bool {
+    match event {
+        Event::Start(tag) => matches!(tag, Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Superscript | Tag::Subscript | Tag::Link { .. } | Tag::Image { .. }),
+        Event::End(_) | Event::Rule | Event::Html(_) => false,
+        _ => true,
+    }
+}
+
+/// Splits `word` around the URL it contains, following GFM extended autolinks: the trailing
+/// punctuation and unbalanced closing parentheses are not part of the URL.
+fn bare_url(word: &str) -> Option<(&str, &str, &str)> {
+    let start = ["https://", "http://", "www."].iter().filter_map(|it| word.find(it)).min()?;
+    let (before, rest) = word.split_at(start);
+    if !before.is_empty() && !before.ends_with(['(', '*', '_', '~']) {
+        return None;
+    }
+    let mut end = rest.len();
+    loop {
+        let url = &rest[..end];
+        match url.chars().last() {
+            Some('?' | '!' | '.' | ',' | ':' | '*' | '_' | '~' | '\'' | '"') => end -= 1,
+            Some(')') if url.matches(')').count() > url.matches('(').count() => end -= 1,
+            _ => break,
+        }
+    }
+    let url = &rest[..end];
+    let host = url.trim_start_matches("https://").trim_start_matches("http://");
+    if host.is_empty() || host == "www." || !host.contains('.') {
+        return None;
+    }
+    Some((before, url, &rest[end..]))
+}
+
+/// Merges the adjacent `Str`s and `Space`s, as parsing yields text in several pieces.
+fn normalize(inlines: Vec<Inline>) -> Vec<Inline> {
+    let mut result: Vec<Inline> = Vec::with_capacity(inlines.len());
+    for inline in inlines {
+        match (result.last_mut(), inline) {
+            (Some(Str(previous)), Str(text)) => previous.push_str(&text),
+            (Some(Space), Space) => {},
+            (Some(Space), SoftBreak) => *result.last_mut().unwrap() = SoftBreak,
+            (Some(SoftBreak | LineBreak), Space) => {},
+            (_, inline) => result.push(inline),
+        }
+    }
+    result
+}
+
+/// Gives an identifier to the headers without one, unique in the document.
+fn add_identifiers(pandoc: &mut Pandoc, identifiers: Identifiers) {
+    let mut used: HashSet<String> = HashSet::new();
+    WalkableMut::<Block>::walk_mut(pandoc, &mut |block: &mut Block| {
+        if let Header(_, attr, _) = block {
+            if !attr.id.is_empty() {
+                used.insert(attr.id.clone());
+            }
+        }
+    });
+    WalkableMut::<Block>::walk_mut(pandoc, &mut |block: &mut Block| {
+        if let Header(_, attr, ils) = block {
+            if attr.id.is_empty() {
+                let base = identifier(&stringify(ils), identifiers);
+                let mut id = base.clone();
+                let mut n = 0;
+                while used.contains(&id) {
+                    n += 1;
+                    id = format!("{}-{}", base, n);
+                }
+                used.insert(id.clone());
+                attr.id = id;
+            }
+        }
+    });
+}
+
+fn identifier(text: &str, identifiers: Identifiers) -> String {
+    let text = text.to_lowercase();
+    let id = if identifiers == Identifiers::Gfm {
+        text.chars()
+            .filter(|c| c.is_whitespace() || c.is_alphanumeric() || *c == '_' || *c == '-')
+            .map(|c| if c.is_whitespace() { '-' } else { c })
+            .collect()
+    } else {
+        let text: String = text.chars()
+            .filter(|c| c.is_whitespace() || c.is_alphanumeric() || *c == '_' || *c == '-' || *c == '.')
+            .collect();
+        let text = text.split_whitespace().collect::<Vec<_>>().join("-");
+        text.trim_start_matches(|c: char| !c.is_alphabetic()).to_string()
+    };
+    if id.is_empty() { "section".to_string() } else { id }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use super::{read, Identifiers, Options};
+
+    fn blocks(input: &str, options: &Options) -> Value {
+        serde_json::to_value(read(input, options).blocks).unwrap()
+    }
+
+    fn strict() -> Options {
+        Options { gfm: false, attributes: false, identifiers: Identifiers::None }
+    }
+
+    #[test]
+    fn header_attributes_need_the_extension() {
+        let with = Options { attributes: true, ..strict() };
+        assert_eq!(
+            blocks("# Title {#id .class}", &with),
+            json!([{"t": "Header", "c": [1, ["id", ["class"], []], [{"t": "Str", "c": "Title"}]]}])
+        );
+        assert_eq!(
+            blocks("# Title {#id .class}", &strict()),
+            json!([{"t": "Header", "c": [1, ["", [], []], [{"t": "Str", "c": "Title"}, {"t": "Space"}, {"t": "Str", "c": "{#id"}, {"t": "Space"}, {"t": "Str", "c": ".class}"}]]}])
+        );
+        assert_eq!(blocks("# Title {#id}", &Options::default())[0]["c"][1], json!(["title-id", [], []]));
+    }
+
+    #[test]
+    fn gfm_extensions() {
+        let gfm = Options { identifiers: Identifiers::None, ..Options::default() };
+        assert_eq!(blocks("~~struck~~", &gfm), json!([{"t": "Para", "c": [{"t": "Strikeout", "c": [{"t": "Str", "c": "struck"}]}]}]));
+        assert_eq!(blocks("~~struck~~", &strict()), json!([{"t": "Para", "c": [{"t": "Str", "c": "~~struck~~"}]}]));
+        assert_eq!(
+            blocks("- [x] done", &gfm),
+            json!([{"t": "BulletList", "c": [[{"t": "Plain", "c": [{"t": "Str", "c": "☒"}, {"t": "Space"}, {"t": "Str", "c": "done"}]}]]}])
+        );
+        assert_eq!(
+            blocks("see www.example.com.", &gfm),
+            json!([{"t": "Para", "c": [
+                {"t": "Str", "c": "see"}, {"t": "Space"},
+                {"t": "Link", "c": [["", ["uri"], []], [{"t": "Str", "c": "www.example.com"}], ["http://www.example.com", ""]]},
+                {"t": "Str", "c": "."}
+            ]}])
+        );
+        assert_eq!(blocks("see www.example.com.", &strict())[0]["c"][2], json!({"t": "Str", "c": "www.example.com."}));
+        assert_eq!(blocks("| a |\n|---|\n| 1 |", &gfm)[0]["t"], json!("Table"));
+        assert_eq!(blocks("| a |\n|---|\n| 1 |", &strict())[0]["t"], json!("Para"));
+    }
+
+    #[test]
+    fn notes() {
+        let gfm = Options { identifiers: Identifiers::None, ..Options::default() };
+        assert_eq!(
+            blocks("a[^1]\n\n[^1]: note", &gfm),
+            json!([{"t": "Para", "c": [{"t": "Str", "c": "a"}, {"t": "Note", "c": [{"t": "Para", "c": [{"t": "Str", "c": "note"}]}]}]}])
+        );
+        assert_eq!(blocks("a[^1]\n\n[^1]: self[^1]", &gfm)[0]["c"][1]["c"][0]["c"], json!([{"t": "Str", "c": "self[^1]"}]));
+    }
+
+    #[test]
+    fn identifiers() {
+        let ids = |identifiers| {
+            let pandoc = read("# 1. Hello, World!\n\n# 1. Hello, World!\n\n# ?", &Options { identifiers, ..Options::default() });
+            pandoc.blocks.iter().map(|it| serde_json::to_value(it).unwrap()["c"][1][0].clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(Identifiers::Pandoc), vec![json!("hello-world"), json!("hello-world-1"), json!("section")]);
+        assert_eq!(ids(Identifiers::Gfm), vec![json!("1-hello-world"), json!("1-hello-world-1"), json!("section")]);
+        assert_eq!(ids(Identifiers::None), vec![json!(""), json!(""), json!("")]);
+    }
+}
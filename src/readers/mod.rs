@@ -0,0 +1,2 @@
+#[cfg(feature = "commonmark")]
+pub mod commonmark;
@@ -0,0 +1,560 @@
+//! Tables as grids of cells, with their spans resolved.
+//!
+//! A [`Grid`] gives each row one [`Slot`] per column: the cell starting there, a position
+//! covered by a cell spanning several rows or columns, or an empty position of a short row.
+//!
+//! ```
+//! use pandoc::table::Grid;
+//!
+//! let mut grid = Grid::from_csv("name,size\nb,10\na,9\n", ',', true);
+//! grid.sort_by_column(1, false);
+//! grid.transpose();
+//! let rows: Vec<Vec<String>> = grid.rows().map(|row| (0..3).map(|i| row.text(i)).collect()).collect();
+//! assert_eq!(rows, [["name", "a", "b"], ["size", "9", "10"]]);
+//! let table = grid.into_block();
+//! ```
+
+use std::cmp::Ordering;
+use crate::builder::{cell, plain, text};
+use crate::definition::{Alignment, Attr, Block, Caption, Cell, ColSpec, ColWidth, Inline, Row, TableBody, TableFoot, TableHead};
+use crate::utils::stringify;
+
+#[derive(Clone, Debug)]
+pub enum Slot {
+    /// The top left position of a cell.
+    Cell(Cell),
+    /// A position covered by the cell `up` rows above and `left` columns on the left.
+    Covered { up: usize, left: usize },
+    /// A position no cell covers.
+    Empty,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GridRow {
+    pub attr: Attr,
+    pub slots: Vec<Slot>,
+}
+
+impl GridRow {
+    /// The cell covering `column`, unless it starts on a row above.
+    pub fn cell(&self, column: usize) -> Option<&Cell> {
+        match self.slots.get(column)? {
+            Slot::Cell(cell) => Some(cell),
+            Slot::Covered { up: 0, left } => match &self.slots[column - left] {
+                Slot::Cell(cell) => Some(cell),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Text of the cell covering `column`, empty if there is none.
+    pub fn text(&self, column: usize) -> String {
+        self.cell(column).map(|it| stringify(&it.4)).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GridBody {
+    pub attr: Attr,
+    /// Number of columns of row headers.
+    pub row_head_columns: u32,
+    /// Intermediate head rows.
+    pub head: Vec<GridRow>,
+    pub rows: Vec<GridRow>,
+}
+
+/// A `Table` whose rows all have one slot per column.
+#[derive(Clone, Debug)]
+pub struct Grid {
+    pub attr: Attr,
+    pub caption: Caption,
+    pub columns: Vec<ColSpec>,
+    pub head_attr: Attr,
+    pub head: Vec<GridRow>,
+    pub bodies: Vec<GridBody>,
+    pub foot_attr: Attr,
+    pub foot: Vec<GridRow>,
+}
+
+impl Grid {
+    /// An empty grid of `columns` columns, with default alignment and width.
+    pub fn new(columns: usize) -> Self {
+        Grid {
+            attr: Attr::default(),
+            caption: Caption(None, Vec::new()),
+            columns: vec![ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault); columns],
+            head_attr: Attr::default(),
+            head: Vec::new(),
+            bodies: Vec::new(),
+            foot_attr: Attr::default(),
+            foot: Vec::new(),
+        }
+    }
+
+    /// Lays out the cells of a `Table`; `None` for other blocks. Spans are clipped to the table
+    /// and to their section.
+    pub fn from_table(block: &Block) -> Option<Grid> {
+        match block {
+            Block::Table(attr, caption, columns, TableHead(head_attr, head), bodies, TableFoot(foot_attr, foot)) => {
+                let width = columns.len();
+                Some(Grid {
+                    attr: attr.clone(),
+                    caption: caption.clone(),
+                    columns: columns.clone(),
+                    head_attr: head_attr.clone(),
+                    head: layout(head, width),
+                    bodies: bodies.iter()
+                        .map(|TableBody(attr, row_head_columns, head, rows)| GridBody {
+                            attr: attr.clone(),
+                            row_head_columns: *row_head_columns,
+                            head: layout(head, width),
+                            rows: layout(rows, width),
+                        })
+                        .collect(),
+                    foot_attr: foot_attr.clone(),
+                    foot: layout(foot, width),
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// A grid of text cells, with a head row unless `headers` is empty.
+    pub fn from_inlines(headers: Vec<Vec<Inline>>, rows: Vec<Vec<Vec<Inline>>>) -> Grid {
+        let width = rows.iter().map(Vec::len).chain(Some(headers.len())).max().unwrap_or_default();
+        let row = |cells: Vec<Vec<Inline>>| {
+            let mut slots: Vec<Slot> = cells.into_iter().map(|it| Slot::Cell(cell(plain(it)))).collect();
+            slots.resize(width, Slot::Empty);
+            GridRow { attr: Attr::default(), slots }
+        };
+        let mut grid = Grid::new(width);
+        if !headers.is_empty() {
+            grid.head.push(row(headers));
+        }
+        grid.bodies.push(GridBody { rows: rows.into_iter().map(row).collect(), ..GridBody::default() });
+        grid
+    }
+
+    /// Reads comma or tab separated values, with quoted fields; the first record is the head
+    /// when `header` is set.
+    pub fn from_csv(csv: &str, delimiter: char, header: bool) -> Grid {
+        let mut records: Vec<Vec<Vec<Inline>>> = parse_csv(csv, delimiter).into_iter()
+            .map(|record| record.iter().map(|field| text(field).into_vec()).collect())
+            .collect();
+        let headers = if header && !records.is_empty() { records.remove(0) } else { Vec::new() };
+        Grid::from_inlines(headers, records)
+    }
+
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The rows from top to bottom: head, bodies with their intermediate heads, foot.
+    pub fn rows(&self) -> impl Iterator<Item = &GridRow> {
+        self.head.iter()
+            .chain(self.bodies.iter().flat_map(|it| it.head.iter().chain(&it.rows)))
+            .chain(&self.foot)
+    }
+
+    fn rows_mut(&mut self) -> impl Iterator<Item = &mut GridRow> {
+        self.head.iter_mut()
+            .chain(self.bodies.iter_mut().flat_map(|it| it.head.iter_mut().chain(&mut it.rows)))
+            .chain(&mut self.foot)
+    }
+
+    /// The cell covering the position, rows counted as [`rows`](Grid::rows) lists them.
+    pub fn cell(&self, row: usize, column: usize) -> Option<&Cell> {
+        let rows: Vec<&GridRow> = self.rows().collect();
+        match rows.get(row)?.slots.get(column)? {
+            Slot::Cell(cell) => Some(cell),
+            Slot::Covered { up, left } => match &rows[row - up].slots[column - left] {
+                Slot::Cell(cell) => Some(cell),
+                _ => None,
+            },
+            Slot::Empty => None,
+        }
+    }
+
+    /// Sorts the rows of each body, keeping heads and foot in place; cells spanning several
+    /// body rows are first split, their content repeated on each row.
+    pub fn sort_rows_by<F>(&mut self, mut compare: F) where F: FnMut(&GridRow, &GridRow) -> Ordering {
+        for body in &mut self.bodies {
+            split_row_spans(&mut body.rows);
+            body.rows.sort_by(&mut compare);
+        }
+    }
+
+    /// Sorts the body rows by the text of `column`, numerically when both are numbers.
+    pub fn sort_by_column(&mut self, column: usize, descending: bool) {
+        self.sort_rows_by(|a, b| {
+            let ordering = compare_text(&a.text(column), &b.text(column));
+            if descending { ordering.reverse() } else { ordering }
+        });
+    }
+
+    /// Inserts a column before `index`, taking one cell per row from `cells` in the order of
+    /// [`rows`](Grid::rows); the column extends the cells spanning across it.
+    pub fn insert_column<C>(&mut self, index: usize, spec: ColSpec, cells: C) where C: IntoIterator<Item = Cell> {
+        let index = index.min(self.width());
+        self.columns.insert(index, spec);
+        let mut cells = cells.into_iter();
+        let mut rows: Vec<&mut GridRow> = self.rows_mut().collect();
+        let mut widened = Vec::new();
+        for (r, row) in rows.iter_mut().enumerate() {
+            let spanned = match row.slots.get(index) {
+                Some(Slot::Covered { up, left }) if *left > 0 => Some(Slot::Covered { up: *up, left: *left }),
+                _ => None,
+            };
+            for (column, slot) in row.slots.iter_mut().enumerate().skip(index) {
+                if let Slot::Covered { left, .. } = slot {
+                    if column - *left < index {
+                        *left += 1;
+                    }
+                }
+            }
+            let cell = cells.next();
+            match spanned {
+                Some(Slot::Covered { up, left }) => {
+                    row.slots.insert(index, Slot::Covered { up, left });
+                    if up == 0 {
+                        widened.push((r, index - left));
+                    }
+                },
+                _ => row.slots.insert(index, cell.map_or(Slot::Empty, Slot::Cell)),
+            }
+        }
+        for (r, column) in widened {
+            if let Slot::Cell(cell) = &mut rows[r].slots[column] {
+                cell.3 += 1;
+            }
+        }
+    }
+
+    /// Appends a column on the right.
+    pub fn push_column<C>(&mut self, spec: ColSpec, cells: C) where C: IntoIterator<Item = Cell> {
+        self.insert_column(self.width(), spec, cells);
+    }
+
+    /// Swaps rows and columns. The head rows become row header columns and the row header
+    /// columns of the first body become head rows; the other rows, foot included, make a single
+    /// body with the attributes of the first one. Rows have no attributes once columns, and the
+    /// row header columns and attributes of the other bodies are lost. Cells of the new head
+    /// spanning into the body are cut at its end, as pandoc does.
+    pub fn transpose(&mut self) {
+        let head_rows = self.head.len();
+        let row_head_columns = self.bodies.first().map_or(0, |it| it.row_head_columns as usize).min(self.width());
+        let body_attr = self.bodies.first().map(|it| it.attr.clone()).unwrap_or_default();
+        let rows = std::mem::take(&mut self.head).into_iter()
+            .chain(std::mem::take(&mut self.bodies).into_iter().flat_map(|it| it.head.into_iter().chain(it.rows)))
+            .chain(std::mem::take(&mut self.foot));
+        let mut transposed: Vec<GridRow> = (0..self.width()).map(|_| GridRow::default()).collect();
+        let mut height = 0;
+        for row in rows {
+            height += 1;
+            for (column, slot) in row.slots.into_iter().enumerate() {
+                transposed[column].slots.push(match slot {
+                    Slot::Cell(Cell(attr, alignment, row_span, col_span, blocks)) => Slot::Cell(Cell(attr, alignment, col_span, row_span, blocks)),
+                    Slot::Covered { up, left } => Slot::Covered { up: left, left: up },
+                    Slot::Empty => Slot::Empty,
+                });
+            }
+        }
+        self.columns = vec![ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault); height];
+        let mut body = transposed.split_off(row_head_columns);
+        clip_row_spans(&mut transposed, &mut body);
+        self.head = transposed;
+        self.bodies = vec![GridBody { attr: body_attr, row_head_columns: head_rows as u32, head: Vec::new(), rows: body }];
+    }
+
+    /// Back to a `Table`; empty slots before the last cell of a row become empty cells.
+    pub fn into_block(self) -> Block {
+        Block::Table(
+            self.attr,
+            self.caption,
+            self.columns,
+            TableHead(self.head_attr, rows(self.head)),
+            self.bodies.into_iter()
+                .map(|body| TableBody(body.attr, body.row_head_columns, rows(body.head), rows(body.rows)))
+                .collect(),
+            TableFoot(self.foot_attr, rows(self.foot)),
+        )
+    }
+}
+
+/// Splits `csv` into records of fields; quoted fields may contain the delimiter, line breaks
+/// and doubled quotes.
+pub fn parse_csv(csv: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            },
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            _ if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' => {},
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.len() == 1 && record[0].is_empty() {
+                    // A blank line.
+                    record.clear();
+                } else {
+                    records.push(std::mem::take(&mut record));
+                }
+            },
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn compare_text(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+fn layout(rows: &[Row], width: usize) -> Vec<GridRow> {
+    let mut grid: Vec<GridRow> = rows.iter()
+        .map(|Row(attr, _)| GridRow { attr: attr.clone(), slots: vec![Slot::Empty; width] })
+        .collect();
+    for (r, Row(_, cells)) in rows.iter().enumerate() {
+        let mut column = 0;
+        for cell in cells {
+            while column < width && !matches!(grid[r].slots[column], Slot::Empty) {
+                column += 1;
+            }
+            if column >= width {
+                break;
+            }
+            let row_span = (cell.2.max(1) as usize).min(rows.len() - r);
+            let col_span = (cell.3.max(1) as usize).min(width - column);
+            for up in 0..row_span {
+                for left in 0..col_span {
+                    grid[r + up].slots[column + left] = Slot::Covered { up, left };
+                }
+            }
+            let Cell(attr, alignment, _, _, blocks) = cell;
+            grid[r].slots[column] = Slot::Cell(Cell(attr.clone(), alignment.clone(), row_span as u32, col_span as u32, blocks.clone()));
+            column += col_span;
+        }
+    }
+    grid
+}
+
+/// Gives each row spanned by a cell its own copy of the cell.
+fn split_row_spans(rows: &mut [GridRow]) {
+    for r in 0..rows.len() {
+        for column in 0..rows[r].slots.len() {
+            let copy = match &mut rows[r].slots[column] {
+                Slot::Cell(cell) if cell.2 > 1 => {
+                    let span = cell.2 as usize;
+                    cell.2 = 1;
+                    Some((cell.clone(), span))
+                },
+                _ => None,
+            };
+            if let Some((cell, span)) = copy {
+                for row in rows.iter_mut().skip(r + 1).take(span - 1) {
+                    row.slots[column] = Slot::Cell(cell.clone());
+                    for left in 1..cell.3 as usize {
+                        row.slots[column + left] = Slot::Covered { up: 0, left };
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ends the cells of `head` spanning into `body` on its last row, the positions they covered in
+/// `body` becoming empty.
+fn clip_row_spans(head: &mut [GridRow], body: &mut [GridRow]) {
+    let height = head.len();
+    for (r, row) in head.iter_mut().enumerate() {
+        for slot in &mut row.slots {
+            if let Slot::Cell(cell) = slot {
+                cell.2 = cell.2.min((height - r) as u32);
+            }
+        }
+    }
+    for (r, row) in body.iter_mut().enumerate() {
+        for slot in &mut row.slots {
+            if matches!(slot, Slot::Covered { up, .. } if *up > r) {
+                *slot = Slot::Empty;
+            }
+        }
+    }
+}
+
+fn rows(rows: Vec<GridRow>) -> Vec<Row> {
+    rows.into_iter()
+        .map(|row| {
+            let last = row.slots.iter().rposition(|it| matches!(it, Slot::Cell(_))).map_or(0, |it| it + 1);
+            let cells = row.slots.into_iter()
+                .take(last)
+                .filter_map(|slot| match slot {
+                    Slot::Cell(cell) => Some(cell),
+                    Slot::Covered { .. } => None,
+                    Slot::Empty => Some(cell(Vec::new())),
+                })
+                .collect();
+            Row(row.attr, cells)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::{cell, plain, str};
+    use crate::definition::{Alignment, Attr, Block, Caption, Cell, ColSpec, ColWidth, Row, TableBody, TableFoot, TableHead};
+    use super::{split_row_spans, Grid, GridRow, Slot};
+
+    fn spanned(text: &str, row_span: u32, col_span: u32) -> Cell {
+        Cell(Attr::default(), Alignment::AlignDefault, row_span, col_span, vec![Block::Plain(str(text).into_vec())])
+    }
+
+    fn row(cells: Vec<Cell>) -> Row {
+        Row(Attr::default(), cells)
+    }
+
+    fn attr(id: &str) -> Attr {
+        Attr { id: id.into(), ..Attr::default() }
+    }
+
+    /// | A       | B |
+    /// |---------|---|
+    /// | x | y   | z |
+    /// |   | w   | v |
+    /// | p | q       |
+    fn table() -> Block {
+        Block::Table(
+            attr("table"),
+            Caption(None, vec![]),
+            vec![ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault); 3],
+            TableHead(attr("head"), vec![row(vec![spanned("A", 1, 2), spanned("B", 1, 1)])]),
+            vec![TableBody(attr("body"), 1, vec![], vec![
+                row(vec![spanned("x", 2, 1), spanned("y", 1, 1), spanned("z", 1, 1)]),
+                row(vec![spanned("w", 1, 1), spanned("v", 1, 1)]),
+                row(vec![spanned("p", 1, 1), spanned("q", 1, 2)]),
+            ])],
+            TableFoot(attr("foot"), vec![]),
+        )
+    }
+
+    /// One line per row: the text and spans of the cells, `^up<left` for the covered
+    /// positions and `.` for the empty ones.
+    fn slots(rows: &[GridRow]) -> Vec<String> {
+        rows.iter()
+            .map(|row| row.slots.iter()
+                .map(|slot| match slot {
+                    Slot::Cell(cell) if cell.2 == 1 && cell.3 == 1 => crate::utils::stringify(&cell.4),
+                    Slot::Cell(cell) => format!("{}{}x{}", crate::utils::stringify(&cell.4), cell.2, cell.3),
+                    Slot::Covered { up, left } => format!("^{}<{}", up, left),
+                    Slot::Empty => ".".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" "))
+            .collect()
+    }
+
+    fn grid_slots(grid: &Grid) -> Vec<String> {
+        slots(&grid.rows().cloned().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn spans_are_resolved() {
+        let grid = Grid::from_table(&table()).unwrap();
+        assert_eq!(grid_slots(&grid), ["A1x2 ^0<1 B", "x2x1 y z", "^1<0 w v", "p q1x2 ^0<1"]);
+        assert_eq!(grid.cell(2, 0).map(|it| crate::utils::stringify(&it.4)), Some("x".to_string()));
+        assert_eq!(grid.cell(3, 2).map(|it| crate::utils::stringify(&it.4)), Some("q".to_string()));
+        assert_eq!(grid.rows().nth(2).unwrap().text(0), "");
+        assert_eq!(grid.rows().nth(3).unwrap().text(2), "q");
+    }
+
+    #[test]
+    fn spans_are_clipped_to_their_section() {
+        let block = Block::Table(
+            Attr::default(),
+            Caption(None, vec![]),
+            vec![ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault); 2],
+            TableHead(Attr::default(), vec![row(vec![spanned("h", 3, 5)])]),
+            vec![TableBody(Attr::default(), 0, vec![], vec![row(vec![spanned("a", 1, 1), spanned("b", 1, 1), spanned("dropped", 1, 1)]), row(vec![spanned("c", 1, 1)])])],
+            TableFoot(Attr::default(), vec![]),
+        );
+        let grid = Grid::from_table(&block).unwrap();
+        assert_eq!(grid_slots(&grid), ["h1x2 ^0<1", "a b", "c ."]);
+        match grid.into_block() {
+            Block::Table(_, _, _, head, bodies, _) => {
+                assert_eq!(head.1[0].1.len(), 1);
+                assert_eq!(bodies[0].3[1].1.len(), 1);
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn inserted_columns_extend_spanning_cells() {
+        let mut grid = Grid::from_table(&table()).unwrap();
+        grid.insert_column(1, ColSpec(Alignment::AlignLeft, ColWidth::ColWidthDefault), ["h", "1", "2", "3"].iter().map(|it| cell(plain(str(*it)))));
+        assert_eq!(grid_slots(&grid), ["A1x3 ^0<1 ^0<2 B", "x2x1 1 y z", "^1<0 2 w v", "p 3 q1x2 ^0<1"]);
+        grid.push_column(ColSpec(Alignment::AlignLeft, ColWidth::ColWidthDefault), vec![]);
+        assert_eq!(grid_slots(&grid)[3], "p 3 q1x2 ^0<1 .");
+        assert_eq!(grid.width(), 5);
+
+        let mut grid = Grid::new(3);
+        grid.bodies.push(Default::default());
+        grid.bodies[0].rows = vec![
+            GridRow { attr: Attr::default(), slots: vec![Slot::Cell(spanned("s", 2, 2)), Slot::Covered { up: 0, left: 1 }, Slot::Cell(spanned("t", 1, 1))] },
+            GridRow { attr: Attr::default(), slots: vec![Slot::Covered { up: 1, left: 0 }, Slot::Covered { up: 1, left: 1 }, Slot::Cell(spanned("u", 1, 1))] },
+        ];
+        grid.insert_column(1, ColSpec(Alignment::AlignLeft, ColWidth::ColWidthDefault), vec![]);
+        assert_eq!(grid_slots(&grid), ["s2x3 ^0<1 ^0<2 t", "^1<0 ^1<1 ^1<2 u"]);
+    }
+
+    #[test]
+    fn row_spans_are_split() {
+        let mut grid = Grid::new(3);
+        grid.bodies.push(Default::default());
+        grid.bodies[0].rows = vec![
+            GridRow { attr: Attr::default(), slots: vec![Slot::Cell(spanned("s", 3, 2)), Slot::Covered { up: 0, left: 1 }, Slot::Cell(spanned("a", 1, 1))] },
+            GridRow { attr: Attr::default(), slots: vec![Slot::Covered { up: 1, left: 0 }, Slot::Covered { up: 1, left: 1 }, Slot::Cell(spanned("b", 1, 1))] },
+            GridRow { attr: Attr::default(), slots: vec![Slot::Covered { up: 2, left: 0 }, Slot::Covered { up: 2, left: 1 }, Slot::Cell(spanned("c", 1, 1))] },
+        ];
+        split_row_spans(&mut grid.bodies[0].rows);
+        assert_eq!(slots(&grid.bodies[0].rows), ["s1x2 ^0<1 a", "s1x2 ^0<1 b", "s1x2 ^0<1 c"]);
+
+        let mut grid = Grid::from_table(&table()).unwrap();
+        grid.sort_by_column(1, false);
+        assert_eq!(grid_slots(&grid), ["A1x2 ^0<1 B", "p q1x2 ^0<1", "x w v", "x y z"]);
+    }
+
+    #[test]
+    fn transposed_tables_keep_their_sections() {
+        let mut grid = Grid::from_table(&table()).unwrap();
+        grid.transpose();
+        assert_eq!(slots(&grid.head), ["A x1x2 ^0<1 p"]);
+        assert_eq!(slots(&grid.bodies[0].rows), [". y w q2x1", "B z v ^1<0"]);
+        assert_eq!(grid.bodies[0].row_head_columns, 1);
+        assert_eq!(grid.width(), 4);
+        assert_eq!((grid.attr.id.as_str(), grid.head_attr.id.as_str(), grid.bodies[0].attr.id.as_str()), ("table", "head", "body"));
+        grid.transpose();
+        assert_eq!(grid_slots(&grid), ["A . B", "x2x1 y z", "^1<0 w v", "p q1x2 ^0<1"]);
+        assert_eq!(grid.bodies[0].row_head_columns, 1);
+    }
+}
@@ -0,0 +1,216 @@
+//! Walks with a selectable order, like the `traverse` field of pandoc's Lua filters.
+//!
+//! A [`Walkable`] callback returning blocks or inlines walks bottom-up: children first, then the
+//! element. One returning a [`Walk`] of them walks top-down: it sees an element first, and can
+//! return [`Walk::Skip`] to keep what it returned out of the walk. The functions here choose
+//! between both with a [`Traversal`].
+//!
+//! ```
+//! use pandoc::definition::Block::{self, Div, Para, RawBlock};
+//! use pandoc::definition::Inline::Str;
+//! use pandoc::traversal::{walk_blocks, Traversal, Walk};
+//!
+//! let blocks = vec![Div(Default::default(), vec![Para(vec![Str("a".into())])])];
+//! let mut visited = 0;
+//! let blocks = walk_blocks(blocks, Traversal::TopDown, &mut |block: Block| {
+//!     visited += 1;
+//!     match block {
+//!         Div(..) => Walk::Skip(vec![RawBlock("html".into(), "<hr />".into())]),
+//!         _ => Walk::Descend(vec![block]),
+//!     }
+//! });
+//! assert_eq!(visited, 1);
+//! assert!(matches!(blocks[0], RawBlock(..)));
+//! ```
+
+use crate::definition::{Block, Inline};
+use crate::walkable::{Blocks, Inlines, Walkable};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Traversal {
+    /// Children first, then the element.
+    #[default]
+    BottomUp,
+    /// The element first, then the children of what the callback returned.
+    TopDown,
+}
+
+/// Result of a callback: the replacement of the visited element, whose children are walked or
+/// not. Both are the same in a bottom-up walk, where the children have already been walked.
+#[derive(Clone, Debug)]
+pub enum Walk<T> {
+    Descend(T),
+    Skip(T),
+}
+
+impl<T> Walk<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Walk::Descend(value) | Walk::Skip(value) => value,
+        }
+    }
+
+    pub fn map<R, F>(self, f: F) -> Walk<R> where F: FnOnce(T) -> R {
+        match self {
+            Walk::Descend(value) => Walk::Descend(f(value)),
+            Walk::Skip(value) => Walk::Skip(f(value)),
+        }
+    }
+}
+
+impl<T> From<T> for Walk<T> {
+    fn from(value: T) -> Self {
+        Walk::Descend(value)
+    }
+}
+
+/// Values walkable in both orders: [`Walkable`] with the callbacks returning a list of `T`, for
+/// the bottom-up walks, and a [`Walk`] of one, for the top-down walks. Every value walkable
+/// for the blocks or the inlines is.
+pub trait Traversable<T>: Walkable<T, Vec<T>> + Walkable<T, Walk<Vec<T>>> {}
+
+impl<W, T> Traversable<T> for W where W: Walkable<T, Vec<T>> + Walkable<T, Walk<Vec<T>>> {}
+
+/// Calls `f` on every block in `node`, in the `traversal` order.
+pub fn walk_blocks<W, F, R>(node: W, traversal: Traversal, f: &mut F) -> W where W: Traversable<Block>, F: FnMut(Block) -> R, R: Into<Walk<Blocks>> {
+    walk(node, traversal, f)
+}
+
+/// Calls `f` on every inline in `node`, in the `traversal` order.
+pub fn walk_inlines<W, F, R>(node: W, traversal: Traversal, f: &mut F) -> W where W: Traversable<Inline>, F: FnMut(Inline) -> R, R: Into<Walk<Inlines>> {
+    walk(node, traversal, f)
+}
+
+fn walk<W, T, F, R>(node: W, traversal: Traversal, f: &mut F) -> W where W: Traversable<T>, F: FnMut(T) -> R, R: Into<Walk<Vec<T>>> {
+    match traversal {
+        Traversal::BottomUp => Walkable::<T, Vec<T>>::walk(node, &mut |it| f(it).into().into_inner()),
+        Traversal::TopDown => Walkable::<T, Walk<Vec<T>>>::walk(node, &mut |it| f(it).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Alignment, Attr, Block, Caption, Cell, Inline, Row, TableBody, TableFoot, TableHead};
+    use crate::definition::Block::{BlockQuote, BulletList, Div, HorizontalRule, Para, Plain, Table};
+    use crate::definition::Inline::{Emph, Note, Str};
+    use crate::utils::stringify;
+    use crate::walkable::tests::{block_name, document, inline_name, names, samples};
+    use super::{walk_blocks, walk_inlines, Traversal, Walk};
+
+    fn visits<W>(node: W, traversal: Traversal) -> (Vec<String>, Vec<String>) where W: super::Traversable<Block> + super::Traversable<Inline> + Clone {
+        let mut blocks = Vec::new();
+        walk_blocks(node.clone(), traversal, &mut |block: Block| {
+            blocks.push(block_name(&block));
+            vec![block]
+        });
+        let mut inlines = Vec::new();
+        walk_inlines(node, traversal, &mut |inline: Inline| {
+            inlines.push(inline_name(&inline));
+            vec![inline]
+        });
+        (blocks, inlines)
+    }
+
+    #[test]
+    fn every_variant_is_walked_in_both_orders() {
+        for (name, sample) in samples() {
+            assert_eq!(visits(sample.clone(), Traversal::BottomUp), (names(&sample, false, true), names(&sample, true, true)), "{}", name);
+            assert_eq!(visits(sample.clone(), Traversal::TopDown), (names(&sample, false, false), names(&sample, true, false)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn documents_are_walked_with_their_metadata() {
+        let pandoc = document();
+        let names = |inlines: bool, post_order: bool| [names(&pandoc.meta, inlines, post_order), names(&pandoc.blocks, inlines, post_order)].concat();
+        assert_eq!(visits(pandoc.clone(), Traversal::BottomUp), (names(false, true), names(true, true)));
+        assert_eq!(visits(pandoc.clone(), Traversal::TopDown), (names(false, false), names(true, false)));
+    }
+
+    fn para(text: &str) -> Block {
+        Para(vec![Str(text.into())])
+    }
+
+    #[test]
+    fn bottom_up_walks_see_the_walked_children() {
+        let blocks = vec![Div(Attr::default(), vec![para("a"), BlockQuote(vec![para("b")])])];
+        let mut seen = Vec::new();
+        let blocks = walk_blocks(blocks, Traversal::BottomUp, &mut |block: Block| {
+            seen.push(block_name(&block));
+            match block {
+                Para(ils) => vec![Plain(ils), HorizontalRule],
+                _ => vec![block],
+            }
+        });
+        assert_eq!(seen, ["Para", "Para", "BlockQuote", "Div"]);
+        assert_eq!(names(&blocks, false, false), ["Div", "Plain", "HorizontalRule", "BlockQuote", "Plain", "HorizontalRule"]);
+    }
+
+    #[test]
+    fn top_down_walks_descend_into_what_is_returned_unless_skipped() {
+        let blocks = vec![
+            Div(Attr::default(), vec![para("skipped")]),
+            BlockQuote(vec![para("a"), Div(Attr::default(), vec![para("b")])]),
+            para("c"),
+        ];
+        let mut seen = Vec::new();
+        let blocks = walk_blocks(blocks, Traversal::TopDown, &mut |block: Block| {
+            seen.push(stringify(&block));
+            match block {
+                Div(_, blks) if stringify(&blks) == "skipped" => Walk::Skip(vec![HorizontalRule]),
+                BlockQuote(blks) => Walk::Descend(blks),
+                Para(ils) => Walk::Descend(vec![Plain(ils)]),
+                _ => Walk::Descend(vec![block]),
+            }
+        });
+        assert_eq!(seen, ["skipped", "a b", "b", "c"]);
+        assert_eq!(names(&blocks, false, false), ["HorizontalRule", "Para", "Div", "Plain", "Plain"]);
+
+        let inlines = vec![Emph(vec![Str("a".into())]), Note(vec![para("b")])];
+        let mut seen = Vec::new();
+        let inlines = walk_inlines(inlines, Traversal::TopDown, &mut |inline: Inline| {
+            seen.push(inline_name(&inline));
+            match inline {
+                Emph(ils) => Walk::Skip(ils),
+                _ => Walk::Descend(vec![inline]),
+            }
+        });
+        assert_eq!(seen, ["Emph", "Note", "Str"]);
+        assert_eq!(names(&inlines, true, false), ["Str", "Note", "Str"]);
+    }
+
+    #[test]
+    fn lists_in_cells_and_captions_are_walked() {
+        let list = |text: &str| BulletList(vec![vec![para(text)]]);
+        let cell = |text: &str| Cell(Attr::default(), Alignment::AlignDefault, 1, 1, vec![list(text)]);
+        let table = Table(
+            Attr::default(),
+            Caption(Some(vec![Emph(vec![Str("short".into())])]), vec![list("caption")]),
+            vec![],
+            TableHead(Attr::default(), vec![Row(Attr::default(), vec![cell("head")])]),
+            vec![TableBody(Attr::default(), 0, vec![Row(Attr::default(), vec![cell("intermediate")])], vec![Row(Attr::default(), vec![cell("body")])])],
+            TableFoot(Attr::default(), vec![Row(Attr::default(), vec![cell("foot")])]),
+        );
+        for traversal in [Traversal::BottomUp, Traversal::TopDown] {
+            let mut lists = Vec::new();
+            let walked = walk_blocks(vec![table.clone()], traversal, &mut |block: Block| match block {
+                BulletList(items) => {
+                    lists.push(stringify(&items[0]));
+                    Walk::Descend(vec![Div(Attr::default(), items.concat())])
+                },
+                _ => Walk::Descend(vec![block]),
+            });
+            assert_eq!(lists, ["caption", "head", "intermediate", "body", "foot"]);
+            assert_eq!(names(&walked, false, false).iter().filter(|it| *it == "BulletList").count(), 0);
+
+            let mut strs = Vec::new();
+            walk_inlines(walked, traversal, &mut |inline: Inline| {
+                if let Str(text) = &inline {
+                    strs.push(text.clone());
+                }
+                vec![inline]
+            });
+            assert_eq!(strs, ["short", "caption", "head", "intermediate", "body", "foot"]);
+        }
+    }
+}
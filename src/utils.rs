@@ -0,0 +1,212 @@
+//! Helpers mirroring pandoc's `pandoc.utils` Lua module.
+
+use crate::definition::{Block, Caption, Inline, MetaValue, QuoteType};
+use crate::definition::Block::{BlockQuote, BulletList, DefinitionList, Div, Figure, Header, LineBlock, OrderedList, Para, Plain, Table};
+use crate::definition::Inline::{Cite, Code, Emph, Image, LineBreak, Link, Math, Quoted, SmallCaps, SoftBreak, Space, Span, Str, Strikeout, Strong, Subscript, Superscript, Underline};
+use crate::definition::MetaValue::{MetaBlocks, MetaBool, MetaInlines, MetaList, MetaMap, MetaString};
+
+/// Elements that can be reduced to their text.
+pub trait Stringify {
+    fn write_text(&self, out: &mut String);
+}
+
+/// Returns the text of an element without its formatting, like pandoc's `stringify`: breaks
+/// become spaces, quotes are rendered as curly quotes, notes and raw content are dropped and
+/// consecutive blocks are separated by a space.
+///
+/// ```
+/// use pandoc::definition::Inline::{Emph, Space, Str};
+/// use pandoc::utils::stringify;
+///
+/// let ils = vec![Str("Hello".into()), Space, Emph(vec![Str("world".into())])];
+/// assert_eq!(stringify(&ils), "Hello world");
+/// ```
+pub fn stringify<S>(element: &S) -> String where S: Stringify + ?Sized {
+    let mut result = String::new();
+    element.write_text(&mut result);
+    result
+}
+
+impl Stringify for Inline {
+    fn write_text(&self, out: &mut String) {
+        match self {
+            Str(text) | Code(_, text) | Math(_, text) => out.push_str(text),
+            Space | SoftBreak | LineBreak => out.push(' '),
+            Quoted(QuoteType::SingleQuote, ils) => {
+                out.push('‘');
+                ils.write_text(out);
+                out.push('’');
+            },
+            Quoted(QuoteType::DoubleQuote, ils) => {
+                out.push('“');
+                ils.write_text(out);
+                out.push('”');
+            },
+            Emph(ils) | Underline(ils) | Strong(ils) | Strikeout(ils) | Superscript(ils) | Subscript(ils) | SmallCaps(ils)
+            | Cite(_, ils) | Link(_, ils, _) | Image(_, ils, _) | Span(_, ils) => ils.write_text(out),
+            _ => {}
+        }
+    }
+}
+
+impl Stringify for Block {
+    fn write_text(&self, out: &mut String) {
+        match self {
+            Plain(ils) | Para(ils) | Header(_, _, ils) => ils.write_text(out),
+            LineBlock(ilss) => separated(ilss, out),
+            BlockQuote(blks) | Div(_, blks) => blks.write_text(out),
+            OrderedList(_, items) | BulletList(items) => separated(items, out),
+            DefinitionList(definitions) => {
+                for definition in definitions {
+                    separate(out);
+                    definition.0.write_text(out);
+                    separated(&definition.1, out);
+                }
+            },
+            Table(_, caption, _, t_head, t_bodies, t_foot) => {
+                caption.write_text(out);
+                let rows = t_head.1.iter()
+                    .chain(t_bodies.iter().flat_map(|it| it.2.iter().chain(it.3.iter())))
+                    .chain(t_foot.1.iter());
+                for row in rows {
+                    for cell in &row.1 {
+                        separate(out);
+                        cell.4.write_text(out);
+                    }
+                }
+            },
+            Figure(_, caption, blks) => {
+                blks.write_text(out);
+                separate(out);
+                caption.write_text(out);
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Stringify for [Inline] {
+    fn write_text(&self, out: &mut String) {
+        self.iter().for_each(|it| it.write_text(out));
+    }
+}
+
+impl Stringify for Vec<Inline> {
+    fn write_text(&self, out: &mut String) {
+        self.as_slice().write_text(out)
+    }
+}
+
+impl Stringify for [Block] {
+    fn write_text(&self, out: &mut String) {
+        for block in self {
+            separate(out);
+            block.write_text(out);
+        }
+    }
+}
+
+impl Stringify for Vec<Block> {
+    fn write_text(&self, out: &mut String) {
+        self.as_slice().write_text(out)
+    }
+}
+
+impl Stringify for Caption {
+    fn write_text(&self, out: &mut String) {
+        self.1.write_text(out)
+    }
+}
+
+impl Stringify for MetaValue {
+    fn write_text(&self, out: &mut String) {
+        match self {
+            MetaMap(map) => separated(map.values(), out),
+            MetaList(values) => separated(values, out),
+            MetaBool(value) => out.push_str(if *value { "true" } else { "false" }),
+            MetaString(text) => out.push_str(text),
+            MetaInlines(ils) => ils.write_text(out),
+            MetaBlocks(blks) => blks.write_text(out),
+        }
+    }
+}
+
+fn separated<'a, I, S>(elements: I, out: &mut String) where I: IntoIterator<Item = &'a S>, S: Stringify + 'a {
+    for element in elements {
+        separate(out);
+        element.write_text(out);
+    }
+}
+
+/// Adds a space unless `out` is empty or already ends with one.
+fn separate(out: &mut String) {
+    if !out.is_empty() && !out.ends_with(' ') {
+        out.push(' ');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Alignment, Attr, Block, Caption, Cell, Inline, MathType, Meta, QuoteType, Row, TableBody, TableFoot, TableHead};
+    use crate::definition::Block::{BulletList, CodeBlock, Div, Figure, LineBlock, Para, Plain, RawBlock, Table};
+    use crate::definition::Inline::{Code, Emph, LineBreak, Math, Note, Quoted, RawInline, SoftBreak, Space, Str};
+    use crate::definition::MetaValue::{MetaBlocks, MetaBool, MetaInlines, MetaList, MetaMap, MetaString};
+    use super::stringify;
+
+    fn str(text: &str) -> Inline {
+        Str(text.into())
+    }
+
+    fn plain(text: &str) -> Block {
+        Plain(vec![str(text)])
+    }
+
+    #[test]
+    fn inlines_lose_their_formatting() {
+        let inlines = vec![
+            Emph(vec![str("a")]), Space, Code(Attr::default(), "b c".into()), SoftBreak,
+            Math(MathType::InlineMath, "x^2".into()), LineBreak,
+            Quoted(QuoteType::SingleQuote, vec![str("d")]), Quoted(QuoteType::DoubleQuote, vec![str("e")]),
+            Note(vec![plain("note")]), RawInline("html".into(), "<br>".into()),
+        ];
+        assert_eq!(stringify(&inlines), "a b c x^2 ‘d’“e”");
+        assert_eq!(stringify(&inlines[..3]), "a b c");
+    }
+
+    #[test]
+    fn blocks_are_separated_by_a_space() {
+        let blocks = vec![
+            Para(vec![str("a"), Space]),
+            Div(Attr::default(), vec![plain("b"), plain("c")]),
+            LineBlock(vec![vec![str("d")], vec![str("e")]]),
+            BulletList(vec![vec![plain("f")], vec![plain("g")]]),
+            CodeBlock(Attr::default(), "code".into()),
+            RawBlock("html".into(), "<hr>".into()),
+            plain("h"),
+        ];
+        assert_eq!(stringify(&blocks), "a b c d e f g h");
+    }
+
+    #[test]
+    fn tables_and_figures_give_their_cells_and_captions() {
+        let cell = |text: &str| Cell(Attr::default(), Alignment::AlignDefault, 1, 1, vec![plain(text)]);
+        let table = Table(
+            Attr::default(),
+            Caption(Some(vec![str("short")]), vec![plain("caption")]),
+            vec![],
+            TableHead(Attr::default(), vec![Row(Attr::default(), vec![cell("a"), cell("b")])]),
+            vec![TableBody(Attr::default(), 0, vec![Row(Attr::default(), vec![cell("c")])], vec![Row(Attr::default(), vec![cell("d")])])],
+            TableFoot(Attr::default(), vec![Row(Attr::default(), vec![cell("e")])]),
+        );
+        assert_eq!(stringify(&table), "caption a b c d e");
+        let figure = Figure(Attr::default(), Caption(None, vec![plain("caption")]), vec![plain("image")]);
+        assert_eq!(stringify(&figure), "image caption");
+    }
+
+    #[test]
+    fn metadata_gives_its_values() {
+        let map: Meta = vec![("b".to_string(), MetaBool(false)), ("a".to_string(), MetaString("a".into()))].into_iter().collect();
+        let value = MetaList(vec![MetaMap(map), MetaBool(true), MetaInlines(vec![str("c")]), MetaBlocks(vec![plain("d"), plain("e")])]);
+        assert_eq!(stringify(&value), "a false true c d e");
+    }
+}
@@ -1,681 +1,537 @@
-use crate::definition::{Block, Caption, Cell, Citation, Definition, Inline, Meta, MetaValue, Pandoc, Row, TableBody, TableFoot, TableHead};
-use crate::definition::Block::{BlockQuote, BulletList, DefinitionList, Div, Figure, Header, LineBlock, OrderedList, Para, Plain, Table};
-use crate::definition::Inline::{Cite, Emph, Image, Link, Note, Quoted, SmallCaps, Span, Strikeout, Strong, Subscript, Superscript};
-use crate::definition::MetaValue::{MetaBlocks, MetaInlines, MetaList, MetaMap};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use crate::definition::{Attr, Block, Caption, Cell, Citation, Inline, Meta, MetaValue, Pandoc, Row, Target};
+use crate::definition::MetaValue::{MetaBlocks, MetaBool, MetaInlines, MetaList, MetaMap, MetaString};
+use crate::traversal::Walk;
+
+pub use pandoc_derive::Walkable;
 
 pub type Inlines = Vec<Inline>;
 pub type Blocks = Vec<Block>;
 
-pub trait Walkable<T, U = T> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(T) -> U;
-}
-
-impl Walkable<Pandoc> for Pandoc {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Pandoc) -> Pandoc {
-        f(self)
-    }
-}
-
-impl Walkable<Inline> for Pandoc {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        Pandoc {
-            meta: self.meta.walk(f),
-            blocks: self.blocks.walk(f),
-            ..self
+/// Rebuilds a value, replacing each `T` it contains by the result of the callback. `T` is one
+/// of `Pandoc`, `Meta`, `Block`, `Inline`, `Attr`, `Target`, `Caption`, `Row`, `Cell` and
+/// `Citation`, and the callback of a `Block` or an `Inline` may also return a list to splice.
+///
+/// The walk is bottom-up: the children of an element are walked before the callback sees it.
+/// A callback returning a [`Walk`] of a list instead sees the elements first, as described in
+/// [`crate::traversal`].
+///
+/// ```
+/// use pandoc::builder::{doc, figure, para, simple_caption, underline};
+/// use pandoc::definition::Inline;
+/// use pandoc::utils::stringify;
+/// use pandoc::walkable::Walkable;
+///
+/// let pandoc = doc(figure(simple_caption(underline("caption")), para("body")));
+/// let pandoc = pandoc.walk(&mut |it: Inline| match it {
+///     Inline::Str(s) => Inline::Str(s.to_uppercase()),
+///     _ => it,
+/// });
+/// assert_eq!(stringify(&pandoc.blocks), "BODY CAPTION");
+/// ```
+pub trait Walkable<T, U = T>: Sized {
+    /// Fallible walk: the first error of `f` stops the walk and is returned.
+    fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(T) -> Result<U, E>;
+
+    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(T) -> U {
+        match self.try_walk(&mut |it| Ok::<U, Infallible>(f(it))) {
+            Ok(walked) => walked,
+            Err(never) => match never {},
         }
     }
-}
-
-impl Walkable<Inline, Inlines> for Pandoc {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        Pandoc {
-            meta: self.meta.walk(f),
-            blocks: self.blocks.walk(f),
-            ..self
-        }
-    }
-}
-
-impl Walkable<Block> for Pandoc {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        Pandoc {
-            meta: self.meta.walk(f),
-            blocks: self.blocks.walk(f),
-            ..self
-        }
-    }
-}
-
-impl Walkable<Block, Blocks> for Pandoc {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        Pandoc {
-            meta: self.meta.walk(f),
-            blocks: self.blocks.walk(f),
-            ..self
-        }
-    }
-}
-
-impl Walkable<Inline> for Meta {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|(k, v)| (k, v.walk(f))).collect()
-    }
-}
-
-impl Walkable<Inline, Inlines> for Meta {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter().map(|(k, v)| (k, v.walk(f))).collect()
-    }
-}
-
-impl Walkable<Block> for Meta {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|(k, v)| (k, v.walk(f))).collect()
-    }
-}
 
-impl Walkable<Block, Blocks> for Meta {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter().map(|(k, v)| (k, v.walk(f))).collect()
+    /// Walks a list of `Self`, one element after the other. The nodes whose callback returns
+    /// the list to splice in their place override it.
+    fn try_walk_vec<F, E>(list: Vec<Self>, f: &mut F) -> Result<Vec<Self>, E> where F: FnMut(T) -> Result<U, E> {
+        list.into_iter().map(|it| it.try_walk(f)).collect()
     }
 }
 
-impl Walkable<Meta> for Meta {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Meta) -> Meta {
-        let meta = self.into_iter().map(|(k, v)| (k, v.walk(f))).collect();
-        f(meta)
-    }
+/// Same as [`Walkable::try_walk`], the first error of `f` leaving the rest of the tree unvisited.
+pub fn try_walk<W, T, U, E, F>(walkable: W, f: &mut F) -> Result<W, E> where W: Walkable<T, U>, F: FnMut(T) -> Result<U, E> {
+    walkable.try_walk(f)
 }
 
-impl Walkable<Inline> for MetaValue {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        match self {
-            MetaMap(map) => MetaMap(map.walk(f)),
-            MetaList(values) => MetaList(values.walk(f)),
-            MetaInlines(values) => MetaInlines(values.walk(f)),
-            MetaBlocks(values) => MetaBlocks(values.walk(f)),
-            _ => self
-        }
-    }
+/// The walk of the children of a node, derived with `#[walkable(node)]`. The walks of the node
+/// itself, below, call it before giving the node to the callback, or instead of it when the
+/// node is not the target.
+pub(crate) trait WalkChildren<T, U>: Sized {
+    fn try_walk_children<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(T) -> Result<U, E>;
 }
 
-impl Walkable<Inline, Inlines> for MetaValue {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        match self {
-            MetaMap(map) => MetaMap(map.walk(f)),
-            MetaList(values) => MetaList(values.walk(f)),
-            MetaInlines(values) => MetaInlines(values.walk(f)),
-            MetaBlocks(values) => MetaBlocks(values.walk(f)),
-            _ => self
-        }
+impl<K, V, T, U> WalkChildren<T, U> for BTreeMap<K, V> where K: Ord, V: Walkable<T, U> {
+    fn try_walk_children<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(T) -> Result<U, E> {
+        self.into_iter().map(|(k, v)| Ok((k, v.try_walk(f)?))).collect()
     }
 }
 
-impl Walkable<Block> for MetaValue {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        match self {
-            MetaMap(map) => MetaMap(map.walk(f)),
-            MetaList(values) => MetaList(values.walk(f)),
-            MetaInlines(values) => MetaInlines(values.walk(f)),
-            MetaBlocks(values) => MetaBlocks(values.walk(f)),
-            _ => self
-        }
+impl Walkable<Pandoc> for Pandoc {
+    fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(Pandoc) -> Result<Pandoc, E> {
+        f(self)
     }
 }
 
-impl Walkable<Block, Blocks> for MetaValue {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        match self {
-            MetaMap(map) => MetaMap(map.walk(f)),
-            MetaList(values) => MetaList(values.walk(f)),
-            MetaInlines(values) => MetaInlines(values.walk(f)),
-            MetaBlocks(values) => MetaBlocks(values.walk(f)),
-            _ => self
-        }
+impl Walkable<Meta> for Pandoc {
+    fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(Meta) -> Result<Meta, E> {
+        Ok(Pandoc { meta: self.meta.try_walk(f)?, ..self })
     }
 }
 
 impl Walkable<Meta> for MetaValue {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Meta) -> Meta {
-        match self {
-            MetaMap(map) => MetaMap(map.walk(f)),
-            MetaList(values) => MetaList(values.walk(f)),
-            _ => self
+    fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(Meta) -> Result<Meta, E> {
+        Ok(match self {
+            MetaMap(map) => MetaMap(map.try_walk(f)?),
+            MetaList(values) => MetaList(values.try_walk(f)?),
+            MetaBool(_) | MetaString(_) | MetaInlines(_) | MetaBlocks(_) => self
+        })
+    }
+}
+
+/// Replaces each element by the elements returned by `f`, then walks the children of those
+/// returned as [`Walk::Descend`].
+pub(crate) fn descend<N, F, E>(nodes: Vec<N>, f: &mut F) -> Result<Vec<N>, E> where N: Walkable<N, Walk<Vec<N>>>, F: FnMut(N) -> Result<Walk<Vec<N>>, E> {
+    let mut descended = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        match f(node)? {
+            Walk::Descend(nodes) => for node in nodes {
+                descended.push(node.try_walk(f)?);
+            },
+            Walk::Skip(nodes) => descended.extend(nodes),
         }
     }
+    Ok(descended)
 }
 
-impl Walkable<Meta> for Vec<MetaValue> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Meta) -> Meta {
-        self.into_iter().map(|it| it.walk(f)).collect()
+/// Walks the children of each element, then replaces it by the elements returned by `f`.
+pub(crate) fn splice<N, F, E>(nodes: Vec<N>, f: &mut F) -> Result<Vec<N>, E> where N: Walkable<N, Vec<N>>, F: FnMut(N) -> Result<Vec<N>, E> {
+    let mut spliced = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let node = node.try_walk(f)?;
+        spliced.extend(f(node)?);
     }
+    Ok(spliced)
 }
 
-impl Walkable<Inline> for Vec<MetaValue> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|it| it.walk(f)).collect()
+impl<T, U, W> Walkable<T, U> for Vec<W> where W: Walkable<T, U> {
+    fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(T) -> Result<U, E> {
+        W::try_walk_vec(self, f)
     }
 }
 
-impl Walkable<Inline, Inlines> for Vec<MetaValue> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter().map(|it| it.walk(f)).collect()
+impl<T, U, W> Walkable<T, U> for Option<W> where W: Walkable<T, U> {
+    fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut(T) -> Result<U, E> {
+        self.map(|it| it.try_walk(f)).transpose()
     }
 }
 
-impl Walkable<Block> for Vec<MetaValue> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
+// The macros below implement the walks of the nodes from their `WalkChildren`, for the owned
+// types here and the borrowed ones of `crate::borrowed`. Their first argument lists the lifetimes
+// of the types, `[]` or `['a]`.
 
-impl Walkable<Block, Blocks> for Vec<MetaValue> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Inline> for Block {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        match self {
-            Plain(ils) => Plain(ils.walk(f)),
-            Para(ils) => Para(ils.walk(f)),
-            LineBlock(ilss) => LineBlock(ilss.into_iter().map(|it| it.walk(f)).collect()),
-            BlockQuote(blks) => BlockQuote(blks.walk(f)),
-            OrderedList(list_attributes, blkss) => OrderedList(list_attributes, blkss.into_iter().map(|it| it.walk(f)).collect()),
-            BulletList(blkss) => BulletList(blkss.into_iter().map(|it| it.walk(f)).collect()),
-            DefinitionList(definitions) => DefinitionList(definitions.into_iter().map(|Definition(ils, blkss)| Definition(ils.walk(f), blkss.into_iter().map(|it| it.walk(f)).collect())).collect()),
-            Header(lvl, attr, ils) => Header(lvl, attr, ils.walk(f)),
-            Table(attr, caption, spec, t_head, t_body, t_foot) => {
-                Table(attr, caption.walk(f), spec, t_head.walk(f), t_body.walk(f), t_foot.walk(f))
-            },
-            Figure(attr, caption, ils) => Figure(attr, caption, ils.walk(f)),
-            Div(attr, blks) => Div(attr, blks.walk(f)),
-            _ => self
+/// The nodes without children.
+macro_rules! walk_leaf {
+    (@ [$($lt:lifetime),*] $t:ty) => {
+        impl<$($lt,)* T, U> $crate::walkable::WalkChildren<T, U> for $t {
+            fn try_walk_children<F, E>(self, _: &mut F) -> Result<Self, E> where F: FnMut(T) -> Result<U, E> {
+                Ok(self)
+            }
         }
-    }
-}
-
-impl Walkable<Inline, Inlines> for Block {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        match self {
-            Plain(ils) => Plain(ils.walk(f)),
-            Para(ils) => Para(ils.walk(f)),
-            LineBlock(ilss) => LineBlock(ilss.into_iter().map(|it| it.walk(f)).collect()),
-            BlockQuote(blks) => BlockQuote(blks.walk(f)),
-            OrderedList(list_attributes, blkss) => OrderedList(list_attributes, blkss.into_iter().map(|it| it.walk(f)).collect()),
-            BulletList(blkss) => BulletList(blkss.into_iter().map(|it| it.walk(f)).collect()),
-            DefinitionList(definitions) => DefinitionList(definitions.into_iter().map(|Definition(ils, blkss)| Definition(ils.walk(f), blkss.into_iter().map(|it| it.walk(f)).collect())).collect()),
-            Header(lvl, attr, ils) => Header(lvl, attr, ils.walk(f)),
-            Table(attr, caption, spec, t_head, t_body, t_foot) => {
-                Table(attr, caption.walk(f), spec, t_head.walk(f), t_body.walk(f), t_foot.walk(f))
-            },
-            Figure(attr, caption, ils) => Figure(attr, caption, ils.walk(f)),
-            Div(attr, blks) => Div(attr, blks.walk(f)),
-            _ => self
+    };
+    ($g:tt $($t:ty),*) => {
+        $($crate::walkable::walk_leaf!(@ $g $t);)*
+    };
+}
+
+/// Walks the children of a `$t`, then `$t` itself.
+macro_rules! walk_node {
+    (@ [$($lt:lifetime),*] $t:ty) => {
+        impl<$($lt),*> $crate::walkable::Walkable<$t> for $t {
+            fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut($t) -> Result<$t, E> {
+                let node = $crate::walkable::WalkChildren::try_walk_children(self, f)?;
+                f(node)
+            }
         }
-    }
-}
-
-impl Walkable<Block> for Block {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        let block = match self {
-            Plain(ils) => Plain(ils.walk(f)),
-            Para(ils) => Para(ils.walk(f)),
-            LineBlock(ilss) => LineBlock(ilss.into_iter().map(|it| it.walk(f)).collect()),
-            BlockQuote(blks) => Block::BlockQuote(blks.walk(f)),
-            OrderedList(list_attributes, blkss) => OrderedList(list_attributes, blkss.into_iter().map(|it| it.walk(f)).collect()),
-            BulletList(blkss) => BulletList(blkss.into_iter().map(|it| it.walk(f)).collect()),
-            DefinitionList(definitions) => DefinitionList(definitions.into_iter().map(|Definition(ils, blkss)| Definition(ils.walk(f), blkss.into_iter().map(|it| it.walk(f)).collect())).collect()),
-            Header(lvl, attr, ils) => Header(lvl, attr, ils.walk(f)),
-            Table(attr, caption, spec, t_head, t_body, t_foot) => {
-                Table(attr, caption.walk(f), spec, t_head.walk(f), t_body.walk(f), t_foot.walk(f))
-            },
-            Figure(attr, caption, ils) => Figure(attr, caption, ils.walk(f)),
-            Div(attr, blks) => Div(attr, blks.walk(f)),
-            _ => self
-        };
-        f(block)
-    }
-}
-
-impl Walkable<Block, Blocks> for Block {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        match self {
-            Plain(ils) => Plain(ils.walk(f)),
-            Para(ils) => Para(ils.walk(f)),
-            LineBlock(ilss) => LineBlock(ilss.into_iter().map(|it| it.walk(f)).collect()),
-            BlockQuote(blks) => Block::BlockQuote(blks.walk(f)),
-            OrderedList(list_attributes, blkss) => OrderedList(list_attributes, blkss.into_iter().map(|it| it.walk(f)).collect()),
-            BulletList(blkss) => BulletList(blkss.into_iter().map(|it| it.walk(f)).collect()),
-            DefinitionList(definitions) => DefinitionList(definitions.into_iter().map(|Definition(ils, blkss)| Definition(ils.walk(f), blkss.into_iter().map(|it| it.walk(f)).collect())).collect()),
-            Header(lvl, attr, ils) => Header(lvl, attr, ils.walk(f)),
-            Table(attr, caption, spec, t_head, t_body, t_foot) => {
-                Table(attr, caption.walk(f), spec, t_head.walk(f), t_body.walk(f), t_foot.walk(f))
-            },
-            Figure(attr, caption, ils) => Figure(attr, caption, ils.walk(f)),
-            Div(attr, blks) => Div(attr, blks.walk(f)),
-            _ => self
+    };
+    ($g:tt $($t:ty),*) => {
+        $($crate::walkable::walk_node!(@ $g $t);)*
+    };
+}
+
+/// The walks of the callbacks returning a list of `$t` to splice, bottom-up, or a [`Walk`] of
+/// one, top-down.
+macro_rules! walk_splice {
+    (@ [$($lt:lifetime),*] $t:ty) => {
+        impl<$($lt),*> $crate::walkable::Walkable<$t, Vec<$t>> for $t {
+            fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut($t) -> Result<Vec<$t>, E> {
+                $crate::walkable::WalkChildren::try_walk_children(self, f)
+            }
+
+            fn try_walk_vec<F, E>(list: Vec<Self>, f: &mut F) -> Result<Vec<Self>, E> where F: FnMut($t) -> Result<Vec<$t>, E> {
+                $crate::walkable::splice(list, f)
+            }
         }
-    }
-}
-
-impl Walkable<Inline> for Blocks {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
 
-impl Walkable<Inline, Inlines> for Blocks {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Block> for Blocks {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
+        impl<$($lt),*> $crate::walkable::Walkable<$t, $crate::traversal::Walk<Vec<$t>>> for $t {
+            fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut($t) -> Result<$crate::traversal::Walk<Vec<$t>>, E> {
+                $crate::walkable::WalkChildren::try_walk_children(self, f)
+            }
 
-impl Walkable<Block, Blocks> for Blocks {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter()
-            .flat_map(|it| {
-                let block = it.walk(f);
-                f(block)
-            }).collect()
-    }
-}
-
-impl Walkable<Inline> for Inline {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        let inline = match self {
-            Emph(ils) => Emph(ils.walk(f)),
-            Strong(ils) => Strong(ils.walk(f)),
-            Strikeout(ils) => Strikeout(ils.walk(f)),
-            Superscript(ils) => Superscript(ils.walk(f)),
-            Subscript(ils) => Subscript(ils.walk(f)),
-            SmallCaps(ils) => SmallCaps(ils.walk(f)),
-            Quoted(t, ils) => Quoted(t, ils.walk(f)),
-            Cite(citations, ils) => Cite(citations.walk(f), ils.walk(f)),
-            Link(attr, ils, target) => Link(attr, ils.walk(f), target),
-            Image(attr, ils, target) => Image(attr, ils.walk(f), target),
-            Note(blks) => Note(blks.walk(f)),
-            Span(attr, ils) => Span(attr, ils.walk(f)),
-            _ => self
-        };
-        f(inline)
-    }
-}
-
-impl Walkable<Inline, Inlines> for Inline {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        match self {
-            Emph(ils) => Emph(ils.walk(f)),
-            Strong(ils) => Strong(ils.walk(f)),
-            Strikeout(ils) => Strikeout(ils.walk(f)),
-            Superscript(ils) => Superscript(ils.walk(f)),
-            Subscript(ils) => Subscript(ils.walk(f)),
-            SmallCaps(ils) => SmallCaps(ils.walk(f)),
-            Quoted(t, ils) => Quoted(t, ils.walk(f)),
-            Cite(citations, ils) => Cite(citations.walk(f), ils.walk(f)),
-            Link(attr, ils, target) => Link(attr, ils.walk(f), target),
-            Image(attr, ils, target) => Image(attr, ils.walk(f), target),
-            Note(blks) => Note(blks.walk(f)),
-            Span(attr, ils) => Span(attr, ils.walk(f)),
-            _ => self
+            fn try_walk_vec<F, E>(list: Vec<Self>, f: &mut F) -> Result<Vec<Self>, E> where F: FnMut($t) -> Result<$crate::traversal::Walk<Vec<$t>>, E> {
+                $crate::walkable::descend(list, f)
+            }
         }
-    }
-}
-
-impl Walkable<Block> for Inline {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        match self {
-            Emph(ils) => Emph(ils.walk(f)),
-            Strong(ils) => Strong(ils.walk(f)),
-            Strikeout(ils) => Strikeout(ils.walk(f)),
-            Superscript(ils) => Superscript(ils.walk(f)),
-            Subscript(ils) => Subscript(ils.walk(f)),
-            SmallCaps(ils) => SmallCaps(ils.walk(f)),
-            Quoted(t, ils) => Quoted(t, ils.walk(f)),
-            Cite(citations, ils) => Cite(citations.walk(f), ils.walk(f)),
-            Link(attr, ils, target) => Link(attr, ils.walk(f), target),
-            Image(attr, ils, target) => Image(attr, ils.walk(f), target),
-            Note(blks) => Note(blks.walk(f)),
-            Span(attr, ils) => Span(attr, ils.walk(f)),
-            _ => self
+    };
+    ($g:tt $($t:ty),*) => {
+        $($crate::walkable::walk_splice!(@ $g $t);)*
+    };
+}
+
+/// Walks the nodes `$other` for the target `$t`: only their children are walked.
+macro_rules! walk_across {
+    (@ [$($lt:lifetime),*] $t:ty, $u:ty; $other:ty) => {
+        impl<$($lt),*> $crate::walkable::Walkable<$t, $u> for $other {
+            fn try_walk<F, E>(self, f: &mut F) -> Result<Self, E> where F: FnMut($t) -> Result<$u, E> {
+                $crate::walkable::WalkChildren::try_walk_children(self, f)
+            }
         }
-    }
-}
-
-
-impl Walkable<Block,Blocks> for Inline {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        match self {
-            Emph(ils) => Emph(ils.walk(f)),
-            Strong(ils) => Strong(ils.walk(f)),
-            Strikeout(ils) => Strikeout(ils.walk(f)),
-            Superscript(ils) => Superscript(ils.walk(f)),
-            Subscript(ils) => Subscript(ils.walk(f)),
-            SmallCaps(ils) => SmallCaps(ils.walk(f)),
-            Quoted(t, ils) => Quoted(t, ils.walk(f)),
-            Cite(citations, ils) => Cite(citations.walk(f), ils.walk(f)),
-            Link(attr, ils, target) => Link(attr, ils.walk(f), target),
-            Image(attr, ils, target) => Image(attr, ils.walk(f), target),
-            Note(blks) => Note(blks.walk(f)),
-            Span(attr, ils) => Span(attr, ils.walk(f)),
-            _ => self
+    };
+    ($g:tt $t:ty, $u:ty; $($other:ty),*) => {
+        $($crate::walkable::walk_across!(@ $g $t, $u; $other);)*
+    };
+}
+
+pub(crate) use {walk_across, walk_leaf, walk_node, walk_splice};
+
+walk_leaf!([] Attr, Target);
+walk_node!([] Meta, Block, Inline, Attr, Target, Caption, Row, Cell, Citation);
+walk_splice!([] Block, Inline);
+
+walk_across!([] Inline, Inline; Pandoc, Meta, MetaValue, Block, Attr, Target, Caption, Row, Cell, Citation);
+walk_across!([] Inline, Inlines; Pandoc, Meta, MetaValue, Block, Attr, Target, Caption, Row, Cell, Citation);
+walk_across!([] Block, Block; Pandoc, Meta, MetaValue, Inline, Attr, Target, Caption, Row, Cell, Citation);
+walk_across!([] Block, Blocks; Pandoc, Meta, MetaValue, Inline, Attr, Target, Caption, Row, Cell, Citation);
+walk_across!([] Inline, Walk<Inlines>; Pandoc, Meta, MetaValue, Block, Attr, Target, Caption, Row, Cell, Citation);
+walk_across!([] Block, Walk<Blocks>; Pandoc, Meta, MetaValue, Inline, Attr, Target, Caption, Row, Cell, Citation);
+walk_across!([] Attr, Attr; Pandoc, Meta, MetaValue, Block, Inline, Target, Caption, Row, Cell, Citation);
+walk_across!([] Target, Target; Pandoc, Meta, MetaValue, Block, Inline, Attr, Caption, Row, Cell, Citation);
+walk_across!([] Caption, Caption; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Row, Cell, Citation);
+walk_across!([] Row, Row; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Cell, Citation);
+walk_across!([] Cell, Cell; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Row, Citation);
+walk_across!([] Citation, Citation; Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Row, Cell);
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde::Serialize;
+    use serde_json::Value;
+    use crate::definition::{Alignment, Attr, Block, BlockKind, Caption, Cell, Citation, CitationMode, ColSpec, ColWidth, Definition, Inline, InlineKind, ListNumberDelim, ListNumberStyle, MathType, Meta, MetaValue, Pandoc, QuoteType, Row, TableBody, TableFoot, TableHead, Target};
+    use crate::definition::Block::*;
+    use crate::definition::Inline::*;
+    use super::{try_walk, Blocks, Inlines, Walkable};
+
+    pub(crate) const BLOCKS: [BlockKind; 14] = [
+        BlockKind::Plain, BlockKind::Para, BlockKind::LineBlock, BlockKind::CodeBlock, BlockKind::RawBlock, BlockKind::BlockQuote, BlockKind::OrderedList,
+        BlockKind::BulletList, BlockKind::DefinitionList, BlockKind::Header, BlockKind::HorizontalRule, BlockKind::Table, BlockKind::Figure, BlockKind::Div,
+    ];
+
+    pub(crate) const INLINES: [InlineKind; 20] = [
+        InlineKind::Str, InlineKind::Emph, InlineKind::Underline, InlineKind::Strong, InlineKind::Strikeout, InlineKind::Superscript, InlineKind::Subscript,
+        InlineKind::SmallCaps, InlineKind::Quoted, InlineKind::Cite, InlineKind::Code, InlineKind::Space, InlineKind::SoftBreak, InlineKind::LineBreak,
+        InlineKind::Math, InlineKind::RawInline, InlineKind::Link, InlineKind::Image, InlineKind::Note, InlineKind::Span,
+    ];
+
+    /// The content of every inline list: a `Str` and a `Note`, so that both inlines and blocks
+    /// are found below each list.
+    fn inlines() -> Inlines {
+        vec![Str("x".into()), Note(vec![Plain(vec![Str("y".into())])])]
+    }
+
+    /// The content of every block list, holding both a block and inlines.
+    fn blocks() -> Blocks {
+        vec![Para(inlines())]
+    }
+
+    fn attr(id: &str) -> Attr {
+        Attr { id: id.into(), ..Attr::default() }
+    }
+
+    fn target() -> Target {
+        ("url".into(), "title".into())
+    }
+
+    fn caption() -> Caption {
+        Caption(Some(inlines()), blocks())
+    }
+
+    fn row() -> Row {
+        Row(attr("row"), vec![Cell(attr("cell"), Alignment::AlignDefault, 1, 1, blocks())])
+    }
+
+    fn citation() -> Citation {
+        Citation { citation_id: "cite".into(), citation_prefix: inlines(), citation_suffix: inlines(), citation_mode: CitationMode::NormalCitation, citation_note_num: 0, citation_hash: 0 }
+    }
+
+    /// A `Block` of the given kind with every list it has holding both blocks and inlines.
+    pub(crate) fn block(kind: BlockKind) -> Block {
+        match kind {
+            BlockKind::Plain => Plain(inlines()),
+            BlockKind::Para => Para(inlines()),
+            BlockKind::LineBlock => LineBlock(vec![inlines(), inlines()]),
+            BlockKind::CodeBlock => CodeBlock(attr("code"), "main()".into()),
+            BlockKind::RawBlock => RawBlock("html".into(), "<hr>".into()),
+            BlockKind::BlockQuote => BlockQuote(blocks()),
+            BlockKind::OrderedList => OrderedList((1, ListNumberStyle::Decimal, ListNumberDelim::Period), vec![blocks(), blocks()]),
+            BlockKind::BulletList => BulletList(vec![blocks(), blocks()]),
+            BlockKind::DefinitionList => DefinitionList(vec![Definition(inlines(), vec![blocks(), blocks()])]),
+            BlockKind::Header => Header(1, attr("header"), inlines()),
+            BlockKind::HorizontalRule => HorizontalRule,
+            BlockKind::Table => Table(
+                attr("table"),
+                caption(),
+                vec![ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault)],
+                TableHead(attr("head"), vec![row()]),
+                vec![TableBody(attr("body"), 0, vec![row()], vec![row(), row()])],
+                TableFoot(attr("foot"), vec![row()]),
+            ),
+            BlockKind::Figure => Figure(attr("figure"), caption(), blocks()),
+            BlockKind::Div => Div(attr("div"), blocks()),
         }
     }
-}
-
-impl Walkable<Inline> for Inlines {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Inline, Inlines> for Inlines {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter()
-            .flat_map(|it| {
-                let tmp = it.walk(f);
-                f(tmp)
-            }).collect()
-    }
-}
-
-impl Walkable<Block> for Inlines {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Block,Blocks> for Inlines {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Inline> for Caption {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        Caption(self.0.map(|it| it.walk(f)), self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline, Inlines> for Caption {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        Caption(self.0.map(|it| it.walk(f)), self.1.walk(f))
-    }
-}
-
-impl Walkable<Block> for Caption {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        Caption(self.0.map(|it| it.walk(f)), self.1.walk(f))
-    }
-}
-
-impl Walkable<Block,Blocks> for Caption {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        Caption(self.0.map(|it| it.walk(f)), self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline> for Row {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        Row(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline, Inlines> for Row {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        Row(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Block> for Row {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        Row(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Block,Blocks> for Row {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        Row(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline> for Vec<Row> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Inline, Inlines> for Vec<Row> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Block> for Vec<Row> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Block,Blocks> for Vec<Row> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-
-impl Walkable<Inline> for TableHead {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        TableHead(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline, Inlines> for TableHead {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        TableHead(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Block> for TableHead {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        TableHead(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Block,Blocks> for TableHead {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        TableHead(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline> for TableBody {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        TableBody(self.0, self.1, self.2.walk(f), self.3.walk(f))
-    }
-}
-
-impl Walkable<Inline, Inlines> for TableBody {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        TableBody(self.0, self.1, self.2.walk(f), self.3.walk(f))
-    }
-}
-
-impl Walkable<Block> for TableBody {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        TableBody(self.0, self.1, self.2.walk(f), self.3.walk(f))
-    }
-}
-
-impl Walkable<Block,Blocks> for TableBody {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        TableBody(self.0, self.1, self.2.walk(f), self.3.walk(f))
-    }
-}
-
-
-impl Walkable<Inline> for Vec<TableBody> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Inline, Inlines> for Vec<TableBody> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Block> for Vec<TableBody> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Block, Blocks> for Vec<TableBody> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Inline> for TableFoot {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        TableFoot(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline, Inlines> for TableFoot {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        TableFoot(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Block> for TableFoot {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        TableFoot(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Block,Blocks> for TableFoot {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        TableFoot(self.0, self.1.walk(f))
-    }
-}
-
-impl Walkable<Inline> for Cell {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        Cell(self.0, self.1, self.2, self.3, self.4.walk(f))
-    }
-}
-
-impl Walkable<Inline, Inlines> for Cell {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        Cell(self.0, self.1, self.2, self.3, self.4.walk(f))
-    }
-}
-
-impl Walkable<Block> for Cell {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        Cell(self.0, self.1, self.2, self.3, self.4.walk(f))
-    }
-}
-
-impl Walkable<Block,Blocks> for Cell {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        Cell(self.0, self.1, self.2, self.3, self.4.walk(f))
-    }
-}
 
-impl Walkable<Inline> for Vec<Cell> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|it| it.walk(f)).collect()
+    /// An `Inline` of the given kind with every list it has holding both blocks and inlines.
+    pub(crate) fn inline(kind: InlineKind) -> Inline {
+        match kind {
+            InlineKind::Str => Str("str".into()),
+            InlineKind::Emph => Emph(inlines()),
+            InlineKind::Underline => Underline(inlines()),
+            InlineKind::Strong => Strong(inlines()),
+            InlineKind::Strikeout => Strikeout(inlines()),
+            InlineKind::Superscript => Superscript(inlines()),
+            InlineKind::Subscript => Subscript(inlines()),
+            InlineKind::SmallCaps => SmallCaps(inlines()),
+            InlineKind::Quoted => Quoted(QuoteType::DoubleQuote, inlines()),
+            InlineKind::Cite => Cite(vec![citation(), citation()], inlines()),
+            InlineKind::Code => Code(attr("code"), "main()".into()),
+            InlineKind::Space => Space,
+            InlineKind::SoftBreak => SoftBreak,
+            InlineKind::LineBreak => LineBreak,
+            InlineKind::Math => Math(MathType::InlineMath, "e = mc^2".into()),
+            InlineKind::RawInline => RawInline("html".into(), "<br>".into()),
+            InlineKind::Link => Link(attr("link"), inlines(), target()),
+            InlineKind::Image => Image(attr("image"), inlines(), target()),
+            InlineKind::Note => Note(blocks()),
+            InlineKind::Span => Span(attr("span"), inlines()),
+        }
     }
-}
 
-impl Walkable<Inline, Inlines> for Vec<Cell> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter().map(|it| it.walk(f)).collect()
+    /// One sample of each variant, the inlines in a `Plain`: the lists every walk, whatever its
+    /// target, is checked on.
+    pub(crate) fn samples() -> Vec<(String, Blocks)> {
+        let blocks = BLOCKS.iter().map(|kind| (format!("{:?}", kind), vec![block(*kind)]));
+        let inlines = INLINES.iter().map(|kind| (format!("{:?}", kind), vec![Plain(vec![inline(*kind)])]));
+        blocks.chain(inlines).collect()
+    }
+
+    /// A document with inlines and blocks in every kind of metadata value.
+    pub(crate) fn document() -> Pandoc {
+        let mut meta = Meta::new();
+        meta.insert("inlines".into(), MetaValue::MetaInlines(inlines()));
+        meta.insert("blocks".into(), MetaValue::MetaBlocks(blocks()));
+        meta.insert("list".into(), MetaValue::MetaList(vec![MetaValue::MetaInlines(inlines()), MetaValue::MetaBool(true)]));
+        let nested: Meta = vec![("string".to_string(), MetaValue::MetaString("s".into())), ("blocks".to_string(), MetaValue::MetaBlocks(blocks()))].into_iter().collect();
+        meta.insert("map".into(), MetaValue::MetaMap(nested));
+        Pandoc { version: crate::compat::API_VERSION.to_vec(), meta, blocks: samples().into_iter().flat_map(|(_, blocks)| blocks).collect() }
+    }
+
+    /// The names of the blocks or of the inlines of `value`, from its JSON, in pre-order or
+    /// post-order.
+    pub(crate) fn names<V>(value: &V, inlines: bool, post_order: bool) -> Vec<String> where V: Serialize {
+        fn collect(value: &Value, kinds: &[String], post_order: bool, names: &mut Vec<String>) {
+            match value {
+                Value::Object(fields) => {
+                    let name = fields.get("t").and_then(Value::as_str).filter(|it| kinds.iter().any(|kind| kind == it));
+                    if let (Some(name), false) = (name, post_order) {
+                        names.push(name.to_string());
+                    }
+                    fields.values().for_each(|it| collect(it, kinds, post_order, names));
+                    if let (Some(name), true) = (name, post_order) {
+                        names.push(name.to_string());
+                    }
+                },
+                Value::Array(values) => values.iter().for_each(|it| collect(it, kinds, post_order, names)),
+                _ => {},
+            }
+        }
+        let kinds: Vec<String> = if inlines {
+            INLINES.iter().map(|it| format!("{:?}", it)).collect()
+        } else {
+            BLOCKS.iter().map(|it| format!("{:?}", it)).collect()
+        };
+        let mut names = Vec::new();
+        collect(&serde_json::to_value(value).unwrap(), &kinds, post_order, &mut names);
+        names
+    }
+
+    /// The number of strings of the JSON of `value` equal to `text`.
+    pub(crate) fn occurrences<V>(value: &V, text: &str) -> usize where V: Serialize {
+        fn count(value: &Value, text: &str) -> usize {
+            match value {
+                Value::String(it) => (it == text) as usize,
+                Value::Array(values) => values.iter().map(|it| count(it, text)).sum(),
+                Value::Object(fields) => fields.values().map(|it| count(it, text)).sum(),
+                _ => 0,
+            }
+        }
+        count(&serde_json::to_value(value).unwrap(), text)
     }
-}
 
-impl Walkable<Block> for Vec<Cell> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|it| it.walk(f)).collect()
+    pub(crate) fn inline_name(inline: &Inline) -> String {
+        format!("{:?}", inline.kind())
     }
-}
 
-impl Walkable<Block,Blocks> for Vec<Cell> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter().map(|it| it.walk(f)).collect()
+    pub(crate) fn block_name(block: &Block) -> String {
+        format!("{:?}", block.kind())
     }
-}
 
-impl Walkable<Inline> for Citation {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        Citation {
-            citation_prefix: self.citation_prefix.walk(f),
-            citation_suffix: self.citation_suffix.walk(f),
-            ..self
+    #[test]
+    fn every_variant_has_a_sample() {
+        let names: Vec<String> = samples().into_iter().map(|(name, _)| name).collect();
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[..i].contains(name), "{} twice", name);
         }
-    }
-}
-
-impl Walkable<Inline, Inlines> for Citation {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        Citation {
-            citation_prefix: self.citation_prefix.walk(f),
-            citation_suffix: self.citation_suffix.walk(f),
-            ..self
+        assert_eq!(names.len(), 34);
+    }
+
+    #[test]
+    fn inlines_are_walked_in_every_variant() {
+        for (name, sample) in samples() {
+            let mut visited = Vec::new();
+            let walked = sample.clone().walk(&mut |inline: Inline| {
+                visited.push(inline_name(&inline));
+                match inline {
+                    Str(text) => Str(text + "!"),
+                    _ => inline,
+                }
+            });
+            assert_eq!(visited, names(&sample, true, true), "{}", name);
+            assert_eq!(occurrences(&walked, "x!"), occurrences(&sample, "x"), "{}", name);
+            assert_eq!(occurrences(&walked, "x"), 0, "{}", name);
+
+            let mut visited = Vec::new();
+            let walked = sample.clone().walk(&mut |inline: Inline| {
+                visited.push(inline_name(&inline));
+                match inline {
+                    Str(_) => vec![inline.clone(), inline],
+                    _ => vec![inline],
+                }
+            });
+            assert_eq!(visited, names(&sample, true, true), "{}", name);
+            assert_eq!(occurrences(&walked, "x"), 2 * occurrences(&sample, "x"), "{}", name);
         }
     }
-}
 
-impl Walkable<Block> for Citation {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        Citation {
-            citation_prefix: self.citation_prefix.walk(f),
-            citation_suffix: self.citation_suffix.walk(f),
-            ..self
+    #[test]
+    fn blocks_are_walked_in_every_variant() {
+        for (name, sample) in samples() {
+            let mut visited = Vec::new();
+            let walked = sample.clone().walk(&mut |block: Block| {
+                visited.push(block_name(&block));
+                match block {
+                    Para(ils) => Plain(ils),
+                    _ => block,
+                }
+            });
+            assert_eq!(visited, names(&sample, false, true), "{}", name);
+            assert_eq!(names(&walked, false, true).iter().filter(|it| *it == "Para").count(), 0, "{}", name);
+
+            let mut visited = Vec::new();
+            let walked = sample.clone().walk(&mut |block: Block| {
+                visited.push(block_name(&block));
+                match block {
+                    Para(_) => vec![block, HorizontalRule],
+                    _ => vec![block],
+                }
+            });
+            assert_eq!(visited, names(&sample, false, true), "{}", name);
+            let rules = |blocks: &Blocks| names(blocks, false, true).iter().filter(|it| *it == "HorizontalRule").count();
+            let paras = names(&sample, false, true).iter().filter(|it| *it == "Para").count();
+            assert_eq!(rules(&walked), rules(&sample) + paras, "{}", name);
         }
     }
-}
 
-impl Walkable<Block,Blocks> for Citation {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        Citation {
-            citation_prefix: self.citation_prefix.walk(f),
-            citation_suffix: self.citation_suffix.walk(f),
-            ..self
+    /// The number of `Attr`, `Target`, `Caption`, `Row`, `Cell` and `Citation` of a sample.
+    pub(crate) fn other_targets(sample: &Blocks) -> [usize; 6] {
+        let attrs = ["row", "cell", "table", "head", "body", "foot", "figure", "div", "header", "code", "link", "image", "span"];
+        [
+            attrs.iter().map(|it| occurrences(sample, it)).sum(),
+            occurrences(sample, "url"),
+            occurrences(sample, "table") + occurrences(sample, "figure"),
+            occurrences(sample, "row"),
+            occurrences(sample, "cell"),
+            occurrences(sample, "cite"),
+        ]
+    }
+
+    #[test]
+    fn other_targets_are_walked_in_every_variant() {
+        fn visits<T>(blocks: &Blocks) -> usize where Blocks: Walkable<T> {
+            let mut visits = 0;
+            blocks.clone().walk(&mut |it: T| {
+                visits += 1;
+                it
+            });
+            visits
+        }
+        for (name, sample) in samples() {
+            let visited = [visits::<Attr>(&sample), visits::<Target>(&sample), visits::<Caption>(&sample), visits::<Row>(&sample), visits::<Cell>(&sample), visits::<Citation>(&sample)];
+            assert_eq!(visited, other_targets(&sample), "{}", name);
         }
     }
-}
-
-impl Walkable<Inline> for Vec<Citation> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inline {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
-
-impl Walkable<Inline, Inlines> for Vec<Citation> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Inline) -> Inlines {
-        self.into_iter().map(|it| it.walk(f)).collect()
-    }
-}
 
-impl Walkable<Block> for Vec<Citation> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Block {
-        self.into_iter().map(|it| it.walk(f)).collect()
+    #[test]
+    fn documents_are_walked_with_their_metadata() {
+        let pandoc = document();
+        let mut inlines = 0;
+        let walked = pandoc.clone().walk(&mut |inline: Inline| {
+            inlines += 1;
+            vec![inline]
+        });
+        assert_eq!(inlines, names(&pandoc, true, true).len());
+        assert_eq!(occurrences(&walked, "x"), occurrences(&pandoc, "x"));
+        let mut blocks = 0;
+        pandoc.clone().walk(&mut |block: Block| {
+            blocks += 1;
+            block
+        });
+        assert_eq!(blocks, names(&pandoc, false, true).len());
+        let mut metas = Vec::new();
+        pandoc.walk(&mut |meta: Meta| {
+            metas.push(meta.len());
+            meta
+        });
+        assert_eq!(metas, [2, 4]);
+    }
+
+    #[test]
+    fn try_walk_stops_at_the_first_error() {
+        let blocks: Vec<Block> = (0..4).map(|i| Block::Para(vec![Inline::Str(i.to_string())])).collect();
+        let mut visited = Vec::new();
+        let result = try_walk(blocks, &mut |inline: Inline| match inline {
+            Inline::Str(text) if text == "1" => Err(text),
+            Inline::Str(text) => {
+                visited.push(text.clone());
+                Ok(vec![Inline::Str(text)])
+            },
+            _ => Ok(vec![inline]),
+        });
+        assert_eq!(result.unwrap_err(), "1");
+        assert_eq!(visited, ["0"]);
     }
-}
 
-impl Walkable<Block,Blocks> for Vec<Citation> {
-    fn walk<F>(self, f: &mut F) -> Self where F: FnMut(Block) -> Blocks {
-        self.into_iter().map(|it| it.walk(f)).collect()
+    #[test]
+    fn walk_is_try_walk_without_errors() {
+        let blocks = vec![Block::Para(vec![Inline::Str("a".into())])];
+        let blocks = blocks.walk(&mut |block: Block| vec![block.clone(), block]);
+        assert_eq!(blocks.len(), 2);
     }
 }
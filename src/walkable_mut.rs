@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use crate::definition::{Attr, Block, Caption, Cell, Citation, Inline, Meta, MetaValue, Pandoc, Row, Target};
+use crate::definition::MetaValue::{MetaList, MetaMap};
+
+pub use pandoc_derive::WalkableMut;
+
+/// Result of a splicing callback: `None` keeps the (possibly modified) element in place,
+/// `Some(elements)` replaces it with `elements`.
+pub type Splice<T> = Option<Vec<T>>;
+
+/// In-place counterpart of [`Walkable`](crate::walkable::Walkable), for the same targets.
+///
+/// With `U = ()` the callback mutates every `T` of the tree, children first. With
+/// `U = Splice<T>` the callback may also replace an element of a list by several ones;
+/// the untouched elements are left where they are.
+///
+/// ```
+/// use pandoc::definition::{Block, Inline};
+/// use pandoc::walkable_mut::{Splice, WalkableMut};
+///
+/// let mut blocks = vec![Block::Para(vec![Inline::Str("a".into()), Inline::SoftBreak, Inline::Str("b".into())])];
+/// blocks.walk_mut(&mut |inline: &mut Inline| -> Splice<Inline> {
+///     match inline {
+///         Inline::Str(text) => {
+///             text.make_ascii_uppercase();
+///             None
+///         },
+///         Inline::SoftBreak => Some(vec![]),
+///         _ => None
+///     }
+/// });
+/// assert!(matches!(&blocks[0], Block::Para(ils) if ils.len() == 2));
+/// ```
+pub trait WalkableMut<T, U = ()> {
+    fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut T) -> U;
+
+    /// Walks a list of `Self`, one element after the other. The nodes whose callback returns a
+    /// `Splice` override it.
+    fn walk_vec_mut<F>(list: &mut Vec<Self>, f: &mut F) where Self: Sized, F: FnMut(&mut T) -> U {
+        list.iter_mut().for_each(|it| it.walk_mut(f))
+    }
+}
+
+/// The walk of the children of a node, derived with `#[walkable(node)]`, as
+/// [`WalkChildren`](crate::walkable::WalkChildren) is for `Walkable`.
+pub(crate) trait WalkChildrenMut<T, U> {
+    fn walk_children_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut T) -> U;
+}
+
+impl<K, V, T, U> WalkChildrenMut<T, U> for BTreeMap<K, V> where V: WalkableMut<T, U> {
+    fn walk_children_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut T) -> U {
+        self.values_mut().for_each(|it| it.walk_mut(f))
+    }
+}
+
+impl WalkableMut<Pandoc> for Pandoc {
+    fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut Pandoc) {
+        f(self)
+    }
+}
+
+impl WalkableMut<Meta> for Pandoc {
+    fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut Meta) {
+        self.meta.walk_mut(f)
+    }
+}
+
+impl WalkableMut<Meta> for MetaValue {
+    fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut Meta) {
+        match self {
+            MetaMap(map) => map.walk_mut(f),
+            MetaList(values) => values.walk_mut(f),
+            _ => {}
+        }
+    }
+}
+
+/// Walks `elements`, replacing them in a single pass: nothing is moved until `f` first returns a
+/// replacement, then the remaining elements are walked and appended after it.
+fn splice<T, F>(elements: &mut Vec<T>, f: &mut F) where T: WalkableMut<T, Splice<T>>, F: FnMut(&mut T) -> Splice<T> {
+    for i in 0..elements.len() {
+        elements[i].walk_mut(f);
+        if let Some(replacement) = f(&mut elements[i]) {
+            let rest = elements.split_off(i + 1);
+            elements.pop();
+            elements.extend(replacement);
+            for mut element in rest {
+                element.walk_mut(f);
+                match f(&mut element) {
+                    Some(replacement) => elements.extend(replacement),
+                    None => elements.push(element),
+                }
+            }
+            return;
+        }
+    }
+}
+
+impl<T, U, W> WalkableMut<T, U> for Vec<W> where W: WalkableMut<T, U> {
+    fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut T) -> U {
+        W::walk_vec_mut(self, f)
+    }
+}
+
+impl<T, U, W> WalkableMut<T, U> for Option<W> where W: WalkableMut<T, U> {
+    fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut T) -> U {
+        if let Some(it) = self {
+            it.walk_mut(f)
+        }
+    }
+}
+
+/// The nodes without children.
+macro_rules! walk_mut_leaf {
+    ($($t:ty),*) => {
+        $(impl<T, U> WalkChildrenMut<T, U> for $t {
+            fn walk_children_mut<F>(&mut self, _: &mut F) where F: FnMut(&mut T) -> U {}
+        })*
+    };
+}
+
+/// Walks the children of a `$t`, then `$t` itself.
+macro_rules! walk_mut_node {
+    ($($t:ty),*) => {
+        $(impl WalkableMut<$t> for $t {
+            fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut $t) {
+                self.walk_children_mut(f);
+                f(self)
+            }
+        })*
+    };
+}
+
+/// The walks of the callbacks returning a `Splice<$t>`.
+macro_rules! walk_mut_splice {
+    ($($t:ty),*) => {
+        $(impl WalkableMut<$t, Splice<$t>> for $t {
+            fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut $t) -> Splice<$t> {
+                self.walk_children_mut(f)
+            }
+
+            fn walk_vec_mut<F>(list: &mut Vec<Self>, f: &mut F) where F: FnMut(&mut $t) -> Splice<$t> {
+                splice(list, f)
+            }
+        })*
+    };
+}
+
+/// Walks the nodes `$other` for the target `$t`: only their children are walked.
+macro_rules! walk_mut_across {
+    ($t:ty, $u:ty; $($other:ty),*) => {
+        $(#[allow(clippy::unused_unit)]
+        impl WalkableMut<$t, $u> for $other {
+            fn walk_mut<F>(&mut self, f: &mut F) where F: FnMut(&mut $t) -> $u {
+                self.walk_children_mut(f)
+            }
+        })*
+    };
+}
+
+walk_mut_leaf!(Attr, Target);
+walk_mut_node!(Meta, Block, Inline, Attr, Target, Caption, Row, Cell, Citation);
+walk_mut_splice!(Block, Inline);
+
+walk_mut_across!(Inline, (); Pandoc, Meta, MetaValue, Block, Attr, Target, Caption, Row, Cell, Citation);
+walk_mut_across!(Inline, Splice<Inline>; Pandoc, Meta, MetaValue, Block, Attr, Target, Caption, Row, Cell, Citation);
+walk_mut_across!(Block, (); Pandoc, Meta, MetaValue, Inline, Attr, Target, Caption, Row, Cell, Citation);
+walk_mut_across!(Block, Splice<Block>; Pandoc, Meta, MetaValue, Inline, Attr, Target, Caption, Row, Cell, Citation);
+walk_mut_across!(Attr, (); Pandoc, Meta, MetaValue, Block, Inline, Target, Caption, Row, Cell, Citation);
+walk_mut_across!(Target, (); Pandoc, Meta, MetaValue, Block, Inline, Attr, Caption, Row, Cell, Citation);
+walk_mut_across!(Caption, (); Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Row, Cell, Citation);
+walk_mut_across!(Row, (); Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Cell, Citation);
+walk_mut_across!(Cell, (); Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Row, Citation);
+walk_mut_across!(Citation, (); Pandoc, Meta, MetaValue, Block, Inline, Attr, Target, Caption, Row, Cell);
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Block, Inline};
+    use crate::definition::{Attr, Caption, Cell, Citation, Row, Target};
+    use crate::walkable::Blocks;
+    use crate::walkable::tests::{block_name, document, inline_name, names, occurrences, other_targets, samples};
+    use super::{Splice, WalkableMut};
+
+    #[test]
+    fn inlines_are_walked_in_every_variant() {
+        for (name, sample) in samples() {
+            let mut walked = sample.clone();
+            let mut visited = Vec::new();
+            walked.walk_mut(&mut |inline: &mut Inline| {
+                visited.push(inline_name(inline));
+                if let Inline::Str(text) = inline {
+                    text.push('!');
+                }
+            });
+            assert_eq!(visited, names(&sample, true, true), "{}", name);
+            assert_eq!(occurrences(&walked, "x!"), occurrences(&sample, "x"), "{}", name);
+
+            let mut walked = sample.clone();
+            let mut visited = Vec::new();
+            walked.walk_mut(&mut |inline: &mut Inline| -> Splice<Inline> {
+                visited.push(inline_name(inline));
+                match inline {
+                    Inline::Str(_) => Some(vec![inline.clone(), inline.clone()]),
+                    _ => None,
+                }
+            });
+            assert_eq!(visited, names(&sample, true, true), "{}", name);
+            assert_eq!(occurrences(&walked, "x"), 2 * occurrences(&sample, "x"), "{}", name);
+        }
+    }
+
+    #[test]
+    fn blocks_are_walked_in_every_variant() {
+        for (name, sample) in samples() {
+            let mut walked = sample.clone();
+            let mut visited = Vec::new();
+            walked.walk_mut(&mut |block: &mut Block| {
+                visited.push(block_name(block));
+                if let Block::Para(ils) = block {
+                    *block = Block::Plain(std::mem::take(ils));
+                }
+            });
+            assert_eq!(visited, names(&sample, false, true), "{}", name);
+            assert!(!names(&walked, false, true).iter().any(|it| it == "Para"), "{}", name);
+
+            let mut walked = sample.clone();
+            let mut visited = Vec::new();
+            walked.walk_mut(&mut |block: &mut Block| -> Splice<Block> {
+                visited.push(block_name(block));
+                match block {
+                    Block::Para(_) => Some(vec![block.clone(), Block::HorizontalRule]),
+                    _ => None,
+                }
+            });
+            assert_eq!(visited, names(&sample, false, true), "{}", name);
+            let count = |blocks: &Vec<Block>, kind: &str| names(blocks, false, true).iter().filter(|it| *it == kind).count();
+            assert_eq!(count(&walked, "HorizontalRule"), count(&sample, "HorizontalRule") + count(&sample, "Para"), "{}", name);
+        }
+    }
+
+    #[test]
+    fn other_targets_are_walked_in_every_variant() {
+        fn visits<T>(blocks: &mut Blocks) -> usize where Blocks: WalkableMut<T> {
+            let mut visits = 0;
+            blocks.walk_mut(&mut |_: &mut T| visits += 1);
+            visits
+        }
+        for (name, mut sample) in samples() {
+            let visited = [visits::<Attr>(&mut sample), visits::<Target>(&mut sample), visits::<Caption>(&mut sample), visits::<Row>(&mut sample), visits::<Cell>(&mut sample), visits::<Citation>(&mut sample)];
+            assert_eq!(visited, other_targets(&sample), "{}", name);
+        }
+    }
+
+    #[test]
+    fn documents_are_walked_with_their_metadata() {
+        let mut pandoc = document();
+        let mut inlines = 0;
+        pandoc.walk_mut(&mut |_: &mut Inline| inlines += 1);
+        let mut blocks = 0;
+        pandoc.walk_mut(&mut |_: &mut Block| blocks += 1);
+        assert_eq!((inlines, blocks), (names(&pandoc, true, true).len(), names(&pandoc, false, true).len()));
+    }
+
+    #[test]
+    fn splice_replaces_elements_in_order() {
+        let mut inlines: Vec<Inline> = ["a", "b", "c", "d"].iter().map(|it| Inline::Str(it.to_string())).collect();
+        inlines.walk_mut(&mut |inline: &mut Inline| -> Splice<Inline> {
+            match inline {
+                Inline::Str(text) if text == "b" => Some(vec![Inline::Str("b1".into()), Inline::Str("b2".into())]),
+                Inline::Str(text) if text == "c" => Some(vec![]),
+                _ => None,
+            }
+        });
+        let texts: Vec<_> = inlines.iter().map(|it| match it { Inline::Str(text) => text.as_str(), _ => "" }).collect();
+        assert_eq!(texts, ["a", "b1", "b2", "d"]);
+    }
+
+    #[test]
+    fn splice_walks_children_of_later_elements() {
+        let mut blocks = vec![
+            Block::HorizontalRule,
+            Block::BlockQuote(vec![Block::HorizontalRule, Block::Para(vec![])]),
+        ];
+        blocks.walk_mut(&mut |block: &mut Block| -> Splice<Block> {
+            match block {
+                Block::HorizontalRule => Some(vec![]),
+                _ => None,
+            }
+        });
+        match blocks.as_slice() {
+            [Block::BlockQuote(inner)] => assert!(matches!(inner.as_slice(), [Block::Para(_)])),
+            _ => panic!("unexpected blocks: {:?}", blocks),
+        }
+    }
+}
@@ -0,0 +1,512 @@
+//! HTML5 writer.
+//!
+//! ```
+//! use pandoc::definition::{Block, Inline};
+//! use pandoc::writers::html;
+//!
+//! let para = Block::Para(vec![Inline::Str("1 < 2".into())]);
+//! assert_eq!(html::write_blocks(&[para], &html::Options::default()), "<p>1 &lt; 2</p>");
+//! ```
+
+use crate::definition::{Alignment, Attr, Block, Caption, Cell, ColSpec, ColWidth, Format, Inline, ListAttributes, ListNumberStyle, MathType, Pandoc, QuoteType, Row, TableBody, TableFoot, TableHead};
+use crate::definition::Block::{BlockQuote, BulletList, CodeBlock, DefinitionList, Div, Figure, Header, HorizontalRule, LineBlock, OrderedList, Para, Plain, RawBlock, Table};
+use crate::definition::Inline::{Cite, Code, Emph, Image, LineBreak, Link, Math, Note, Quoted, RawInline, SmallCaps, SoftBreak, Space, Span, Str, Strikeout, Strong, Subscript, Superscript, Underline};
+use crate::definition::MetaValue::{MetaInlines, MetaString};
+use crate::utils::stringify;
+
+/// What to do with the content of a `RawBlock` or `RawInline`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Raw {
+    /// Output as is.
+    Verbatim,
+    /// Output as escaped text.
+    Escape,
+    /// Omit.
+    Drop,
+}
+
+/// Writer settings; the default writes a fragment, keeps `html` raw content and drops the others.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Wrap the body in a complete HTML document.
+    pub standalone: bool,
+    /// Treatment of the raw content per format, `default_raw` applying to the unlisted ones.
+    pub raw: Vec<(Format, Raw)>,
+    pub default_raw: Raw,
+    /// Script loaded by standalone documents containing math.
+    pub math_script: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            standalone: false,
+            raw: vec![("html".to_string(), Raw::Verbatim), ("html5".to_string(), Raw::Verbatim), ("html4".to_string(), Raw::Verbatim)],
+            default_raw: Raw::Drop,
+            math_script: Some("https://cdn.jsdelivr.net/npm/mathjax@3/es5/tex-chtml-full.js".to_string()),
+        }
+    }
+}
+
+impl Options {
+    pub fn raw(&self, format: &str) -> Raw {
+        self.raw.iter()
+            .find(|(it, _)| it.eq_ignore_ascii_case(format))
+            .map_or(self.default_raw, |(_, raw)| *raw)
+    }
+}
+
+pub fn write(pandoc: &Pandoc, options: &Options) -> String {
+    let mut writer = Writer::new(options);
+    let body = writer.blocks(&pandoc.blocks) + &writer.notes();
+    if !options.standalone {
+        return body;
+    }
+    let title = match pandoc.meta.get("title") {
+        Some(MetaInlines(ils)) => escape(&stringify(ils)),
+        Some(MetaString(text)) => escape(text),
+        _ => String::new(),
+    };
+    let math = match (&options.math_script, writer.has_math) {
+        (Some(script), true) => format!("\n  <script defer src=\"{}\" type=\"text/javascript\"></script>", escape(script)),
+        _ => String::new(),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"utf-8\" />\n  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0, user-scalable=yes\" />\n  <title>{}</title>{}\n</head>\n<body>\n{}\n</body>\n</html>",
+        title, math, body
+    )
+}
+
+pub fn write_blocks(blocks: &[Block], options: &Options) -> String {
+    let mut writer = Writer::new(options);
+    let result = writer.blocks(blocks);
+    result + &writer.notes()
+}
+
+pub fn write_inlines(inlines: &[Inline], options: &Options) -> String {
+    let mut writer = Writer::new(options);
+    let result = writer.inlines(inlines);
+    result + &writer.notes()
+}
+
+struct Writer<'a> {
+    options: &'a Options,
+    notes: Vec<String>,
+    has_math: bool,
+}
+
+impl<'a> Writer<'a> {
+    fn new(options: &'a Options) -> Self {
+        Writer { options, notes: Vec::new(), has_math: false }
+    }
+
+    fn notes(&self) -> String {
+        if self.notes.is_empty() {
+            return String::new();
+        }
+        let items: Vec<String> = self.notes.iter().enumerate()
+            .map(|(i, note)| {
+                let back_link = format!("<a href=\"#fnref{}\" class=\"footnote-back\" role=\"doc-backlink\">↩︎</a>", i + 1);
+                let note = match note.strip_suffix("</p>") {
+                    Some(note) => format!("{}{}</p>", note, back_link),
+                    None => format!("{}\n<p>{}</p>", note, back_link),
+                };
+                format!("<li id=\"fn{}\">{}</li>", i + 1, note)
+            }).collect();
+        format!(
+            "\n<section id=\"footnotes\" class=\"footnotes footnotes-end-of-document\" role=\"doc-endnotes\">\n<hr />\n<ol>\n{}\n</ol>\n</section>",
+            items.join("\n")
+        )
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) -> String {
+        blocks.iter()
+            .map(|it| self.block(it))
+            .filter(|it| !it.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn block(&mut self, block: &Block) -> String {
+        match block {
+            Plain(ils) => self.inlines(ils),
+            Para(ils) => format!("<p>{}</p>", self.inlines(ils)),
+            LineBlock(ilss) => {
+                let lines: Vec<String> = ilss.iter().map(|it| self.inlines(it)).collect();
+                format!("<div class=\"line-block\">{}</div>", lines.join("<br />\n"))
+            },
+            CodeBlock(attr, code) => format!("<pre{}><code>{}</code></pre>", attributes(attr), escape(code)),
+            RawBlock(format, text) => self.raw(format, text, true),
+            BlockQuote(blks) => format!("<blockquote>\n{}\n</blockquote>", self.blocks(blks)),
+            OrderedList(list_attributes, items) => self.ordered_list(list_attributes, items),
+            BulletList(items) => format!("<ul>\n{}\n</ul>", self.list_items(items)),
+            DefinitionList(definitions) => {
+                let definitions: Vec<String> = definitions.iter()
+                    .map(|definition| {
+                        let mut parts = vec![format!("<dt>{}</dt>", self.inlines(&definition.0))];
+                        parts.extend(definition.1.iter().map(|blks| format!("<dd>\n{}\n</dd>", self.blocks(blks))));
+                        parts.join("\n")
+                    }).collect();
+                format!("<dl>\n{}\n</dl>", definitions.join("\n"))
+            },
+            Header(lvl, attr, ils) => format!("<h{}{}>{}</h{}>", lvl, attributes(attr), self.inlines(ils), lvl),
+            HorizontalRule => "<hr />".to_string(),
+            Table(attr, caption, specs, t_head, t_bodies, t_foot) => self.table(attr, caption, specs, t_head, t_bodies, t_foot),
+            Figure(attr, caption, blks) => {
+                let caption = if caption.1.is_empty() {
+                    String::new()
+                } else {
+                    format!("\n<figcaption>{}</figcaption>", self.blocks(&caption.1))
+                };
+                format!("<figure{}>\n{}{}\n</figure>", attributes(attr), self.blocks(blks), caption)
+            },
+            Div(attr, blks) => format!("<div{}>\n{}\n</div>", attributes(attr), self.blocks(blks)),
+        }
+    }
+
+    fn ordered_list(&mut self, (start, style, _): &ListAttributes, items: &[Vec<Block>]) -> String {
+        let mut list_attributes = String::new();
+        if *start != 1 {
+            list_attributes.push_str(&format!(" start=\"{}\"", start));
+        }
+        match style {
+            ListNumberStyle::LowerAlpha => list_attributes.push_str(" type=\"a\""),
+            ListNumberStyle::UpperAlpha => list_attributes.push_str(" type=\"A\""),
+            ListNumberStyle::LowerRoman => list_attributes.push_str(" type=\"i\""),
+            ListNumberStyle::UpperRoman => list_attributes.push_str(" type=\"I\""),
+            ListNumberStyle::Example => list_attributes.push_str(" class=\"example\""),
+            _ => {}
+        }
+        format!("<ol{}>\n{}\n</ol>", list_attributes, self.list_items(items))
+    }
+
+    fn list_items(&mut self, items: &[Vec<Block>]) -> String {
+        items.iter()
+            .map(|item| match item.as_slice() {
+                [Plain(ils)] => format!("<li>{}</li>", self.inlines(ils)),
+                _ => format!("<li>{}</li>", self.blocks(item)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn table(&mut self, attr: &Attr, caption: &Caption, specs: &[ColSpec], t_head: &TableHead, t_bodies: &[TableBody], t_foot: &TableFoot) -> String {
+        let aligns: Vec<&Alignment> = specs.iter().map(|it| &it.0).collect();
+        let mut parts = vec![format!("<table{}>", attributes(attr))];
+        if !caption.1.is_empty() {
+            parts.push(format!("<caption>{}</caption>", self.blocks(&caption.1)));
+        }
+        if specs.iter().any(|it| matches!(it.1, ColWidth::ColWidth(_))) {
+            let cols: Vec<String> = specs.iter()
+                .map(|it| match it.1 {
+                    ColWidth::ColWidth(width) => format!("<col style=\"width: {}%\" />", (width * 100.0).round()),
+                    ColWidth::ColWidthDefault => "<col />".to_string(),
+                }).collect();
+            parts.push(format!("<colgroup>\n{}\n</colgroup>", cols.join("\n")));
+        }
+        if !t_head.1.is_empty() {
+            parts.push(format!("<thead{}>\n{}\n</thead>", attributes(&t_head.0), self.rows(&t_head.1, &aligns, usize::MAX)));
+        }
+        for TableBody(body_attr, row_head_columns, head, body) in t_bodies {
+            let mut rows = Vec::new();
+            if !head.is_empty() {
+                rows.push(self.rows(head, &aligns, usize::MAX));
+            }
+            if !body.is_empty() {
+                rows.push(self.rows(body, &aligns, *row_head_columns as usize));
+            }
+            parts.push(format!("<tbody{}>\n{}\n</tbody>", attributes(body_attr), rows.join("\n")));
+        }
+        if !t_foot.1.is_empty() {
+            parts.push(format!("<tfoot{}>\n{}\n</tfoot>", attributes(&t_foot.0), self.rows(&t_foot.1, &aligns, 0)));
+        }
+        parts.push("</table>".to_string());
+        parts.join("\n")
+    }
+
+    /// Renders the rows of a table section, the cells in the `header_columns` first columns being
+    /// header cells. Spanned columns are tracked to find the column, and alignment, of each cell.
+    fn rows(&mut self, rows: &[Row], aligns: &[&Alignment], header_columns: usize) -> String {
+        let mut occupied: Vec<u32> = vec![0; aligns.len()];
+        let mut result = Vec::new();
+        for Row(row_attr, cells) in rows {
+            let mut column = 0;
+            let mut cells_html = Vec::new();
+            for Cell(cell_attr, align, row_span, col_span, blks) in cells {
+                while column < occupied.len() && occupied[column] > 0 {
+                    column += 1;
+                }
+                let align = match align {
+                    Alignment::AlignDefault => aligns.get(column).copied().unwrap_or(&Alignment::AlignDefault),
+                    _ => align,
+                };
+                let tag = if column < header_columns { "th" } else { "td" };
+                let mut cell_attributes = attributes(cell_attr);
+                let style = match align {
+                    Alignment::AlignLeft => Some("left"),
+                    Alignment::AlignRight => Some("right"),
+                    Alignment::AlignCenter => Some("center"),
+                    Alignment::AlignDefault => None,
+                };
+                if let Some(style) = style {
+                    cell_attributes.push_str(&format!(" style=\"text-align: {};\"", style));
+                }
+                if *row_span > 1 {
+                    cell_attributes.push_str(&format!(" rowspan=\"{}\"", row_span));
+                }
+                if *col_span > 1 {
+                    cell_attributes.push_str(&format!(" colspan=\"{}\"", col_span));
+                }
+                let content = match blks.as_slice() {
+                    [Plain(ils)] => self.inlines(ils),
+                    _ => self.blocks(blks),
+                };
+                cells_html.push(format!("<{}{}>{}</{}>", tag, cell_attributes, content, tag));
+                for spanned in column..(column + (*col_span).max(1) as usize) {
+                    if spanned >= occupied.len() {
+                        occupied.resize(spanned + 1, 0);
+                    }
+                    occupied[spanned] = (*row_span).max(1);
+                }
+                column += (*col_span).max(1) as usize;
+            }
+            occupied.iter_mut().for_each(|it| *it = it.saturating_sub(1));
+            result.push(format!("<tr{}>\n{}\n</tr>", attributes(row_attr), cells_html.join("\n")));
+        }
+        result.join("\n")
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) -> String {
+        let mut result = String::new();
+        for inline in inlines {
+            result.push_str(&self.inline(inline));
+        }
+        result
+    }
+
+    fn inline(&mut self, inline: &Inline) -> String {
+        match inline {
+            Str(text) => escape(text),
+            Emph(ils) => format!("<em>{}</em>", self.inlines(ils)),
+            Underline(ils) => format!("<u>{}</u>", self.inlines(ils)),
+            Strong(ils) => format!("<strong>{}</strong>", self.inlines(ils)),
+            Strikeout(ils) => format!("<del>{}</del>", self.inlines(ils)),
+            Superscript(ils) => format!("<sup>{}</sup>", self.inlines(ils)),
+            Subscript(ils) => format!("<sub>{}</sub>", self.inlines(ils)),
+            SmallCaps(ils) => format!("<span class=\"smallcaps\">{}</span>", self.inlines(ils)),
+            Quoted(QuoteType::SingleQuote, ils) => format!("‘{}’", self.inlines(ils)),
+            Quoted(QuoteType::DoubleQuote, ils) => format!("“{}”", self.inlines(ils)),
+            Cite(citations, ils) => {
+                let ids: Vec<&str> = citations.iter().map(|it| it.citation_id.as_str()).collect();
+                format!("<span class=\"citation\" data-cites=\"{}\">{}</span>", escape(&ids.join(" ")), self.inlines(ils))
+            },
+            Code(attr, code) => format!("<code{}>{}</code>", attributes(attr), escape(code)),
+            Space => " ".to_string(),
+            SoftBreak => "\n".to_string(),
+            LineBreak => "<br />\n".to_string(),
+            Math(MathType::InlineMath, math) => {
+                self.has_math = true;
+                format!("<span class=\"math inline\">\\({}\\)</span>", escape(math))
+            },
+            Math(MathType::DisplayMath, math) => {
+                self.has_math = true;
+                format!("<span class=\"math display\">\\[{}\\]</span>", escape(math))
+            },
+            RawInline(format, text) => self.raw(format, text, false),
+            Link(attr, ils, (url, title)) => {
+                let title = if title.is_empty() { String::new() } else { format!(" title=\"{}\"", escape(title)) };
+                format!("<a href=\"{}\"{}{}>{}</a>", escape(url), attributes(attr), title, self.inlines(ils))
+            },
+            Image(attr, ils, (url, title)) => {
+                let title = if title.is_empty() { String::new() } else { format!(" title=\"{}\"", escape(title)) };
+                format!("<img src=\"{}\"{}{} alt=\"{}\" />", escape(url), attributes(attr), title, escape(&stringify(ils)))
+            },
+            Note(blks) => {
+                self.notes.push(String::new());
+                let n = self.notes.len();
+                self.notes[n - 1] = self.blocks(blks);
+                format!("<a href=\"#fn{}\" class=\"footnote-ref\" id=\"fnref{}\" role=\"doc-noteref\"><sup>{}</sup></a>", n, n, n)
+            },
+            Span(attr, ils) => format!("<span{}>{}</span>", attributes(attr), self.inlines(ils)),
+        }
+    }
+
+    fn raw(&self, format: &str, text: &str, block: bool) -> String {
+        match self.options.raw(format) {
+            Raw::Verbatim => text.to_string(),
+            Raw::Escape if block => format!("<pre class=\"raw {}\"><code>{}</code></pre>", escape(format), escape(text)),
+            Raw::Escape => format!("<code class=\"raw {}\">{}</code>", escape(format), escape(text)),
+            Raw::Drop => String::new(),
+        }
+    }
+}
+
+const HTML_ATTRIBUTES: [&str; 20] = [
+    "accesskey", "alt", "contenteditable", "dir", "draggable", "height", "hidden", "href", "lang", "name",
+    "rel", "role", "spellcheck", "src", "style", "tabindex", "target", "title", "translate", "width",
+];
+
+fn attributes(attr: &Attr) -> String {
+    let mut result = String::new();
+    if !attr.id.is_empty() {
+        result.push_str(&format!(" id=\"{}\"", escape(&attr.id)));
+    }
+    if !attr.classes.is_empty() {
+        result.push_str(&format!(" class=\"{}\"", escape(&attr.classes.join(" "))));
+    }
+    for (key, value) in &attr.attributes {
+        let key = if HTML_ATTRIBUTES.contains(&key.as_str()) || key.starts_with("data-") || key.starts_with("aria-") {
+            key.clone()
+        } else {
+            format!("data-{}", key)
+        };
+        result.push_str(&format!(" {}=\"{}\"", escape(&key), escape(value)));
+    }
+    result
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Alignment, Attr, Block, Caption, Cell, ColSpec, ColWidth, Definition, Inline, MathType, Meta, Pandoc, Row, TableBody, TableFoot, TableHead};
+    use crate::definition::Block::{DefinitionList, Figure, LineBlock, Para, Plain, RawBlock};
+    use crate::definition::Inline::{Code, Image, Link, Math, Note, RawInline, Space, Span, Str};
+    use crate::definition::MetaValue::MetaInlines;
+    use super::{write, write_blocks, write_inlines, Options, Raw};
+
+    fn str(text: &str) -> Inline {
+        Str(text.into())
+    }
+
+    fn cell(text: &str, row_span: u32) -> Cell {
+        Cell(Attr::default(), Alignment::AlignDefault, row_span, 1, vec![Block::Plain(vec![Inline::Str(text.into())])])
+    }
+
+    #[test]
+    fn row_head_columns_follow_row_spans() {
+        let table = Block::Table(
+            Attr::default(),
+            Caption(None, vec![]),
+            vec![ColSpec(Alignment::AlignDefault, ColWidth::ColWidthDefault); 2],
+            TableHead(Attr::default(), vec![]),
+            vec![TableBody(Attr::default(), 1, vec![], vec![
+                Row(Attr::default(), vec![cell("a", 2), cell("1", 1)]),
+                Row(Attr::default(), vec![cell("2", 1)]),
+            ])],
+            TableFoot(Attr::default(), vec![]),
+        );
+        assert_eq!(
+            write_blocks(&[table], &Options::default()),
+            "<table>\n<tbody>\n<tr>\n<th rowspan=\"2\">a</th>\n<td>1</td>\n</tr>\n<tr>\n<td>2</td>\n</tr>\n</tbody>\n</table>"
+        );
+    }
+
+    #[test]
+    fn text_and_attributes_are_escaped() {
+        assert_eq!(write_blocks(&[Para(vec![str("a < b & \"c\"")])], &Options::default()), "<p>a &lt; b &amp; &quot;c&quot;</p>");
+        let attr = Attr {
+            id: "x\"y".into(),
+            classes: vec!["a".into(), "b<".into()],
+            attributes: vec![("title".into(), "1 > 0".into()), ("custom".into(), "v&".into()), ("data-x".into(), "y".into())],
+        };
+        assert_eq!(
+            write_inlines(&[Span(attr, vec![str("t")])], &Options::default()),
+            "<span id=\"x&quot;y\" class=\"a b&lt;\" title=\"1 &gt; 0\" data-custom=\"v&amp;\" data-x=\"y\">t</span>"
+        );
+        let inlines = vec![
+            Link(Attr::default(), vec![str("l")], ("/?a=1&b=2".into(), "\"t\"".into())),
+            Code(Attr::default(), "<b>".into()),
+            Image(Attr::default(), vec![str("a&b")], ("i.png".into(), String::new())),
+        ];
+        assert_eq!(
+            write_inlines(&inlines, &Options::default()),
+            "<a href=\"/?a=1&amp;b=2\" title=\"&quot;t&quot;\">l</a><code>&lt;b&gt;</code><img src=\"i.png\" alt=\"a&amp;b\" />"
+        );
+    }
+
+    #[test]
+    fn notes_are_listed_in_a_footnote_section() {
+        let para = Para(vec![str("a"), Note(vec![Para(vec![str("first")])]), Note(vec![Plain(vec![str("second")])])]);
+        assert_eq!(write_blocks(&[para], &Options::default()), [
+            "<p>a<a href=\"#fn1\" class=\"footnote-ref\" id=\"fnref1\" role=\"doc-noteref\"><sup>1</sup></a>",
+            "<a href=\"#fn2\" class=\"footnote-ref\" id=\"fnref2\" role=\"doc-noteref\"><sup>2</sup></a></p>\n",
+            "<section id=\"footnotes\" class=\"footnotes footnotes-end-of-document\" role=\"doc-endnotes\">\n<hr />\n<ol>\n",
+            "<li id=\"fn1\"><p>first<a href=\"#fnref1\" class=\"footnote-back\" role=\"doc-backlink\">↩︎</a></p></li>\n",
+            "<li id=\"fn2\">second\n<p><a href=\"#fnref2\" class=\"footnote-back\" role=\"doc-backlink\">↩︎</a></p></li>\n",
+            "</ol>\n</section>",
+        ].concat());
+        assert_eq!(write_blocks(&[Para(vec![str("a")])], &Options::default()), "<p>a</p>");
+    }
+
+    #[test]
+    fn figures_have_an_optional_caption() {
+        let image = Plain(vec![Image(Attr::default(), vec![str("alt")], ("a.png".into(), String::new()))]);
+        let id = Attr { id: "fig".into(), ..Attr::default() };
+        let figure = Figure(id, Caption(None, vec![Plain(vec![str("caption")])]), vec![image.clone()]);
+        assert_eq!(
+            write_blocks(&[figure], &Options::default()),
+            "<figure id=\"fig\">\n<img src=\"a.png\" alt=\"alt\" />\n<figcaption>caption</figcaption>\n</figure>"
+        );
+        let figure = Figure(Attr::default(), Caption(None, vec![]), vec![image]);
+        assert_eq!(write_blocks(&[figure], &Options::default()), "<figure>\n<img src=\"a.png\" alt=\"alt\" />\n</figure>");
+    }
+
+    #[test]
+    fn math_is_delimited_and_loads_its_script() {
+        let inlines = vec![Math(MathType::InlineMath, "x < 1".into()), Space, Math(MathType::DisplayMath, "y".into())];
+        assert_eq!(
+            write_inlines(&inlines, &Options::default()),
+            "<span class=\"math inline\">\\(x &lt; 1\\)</span> <span class=\"math display\">\\[y\\]</span>"
+        );
+        let options = Options { standalone: true, math_script: Some("math.js".into()), ..Options::default() };
+        let meta: Meta = vec![("title".to_string(), MetaInlines(vec![str("a < b")]))].into_iter().collect();
+        let with_math = write(&Pandoc { version: vec![1, 23], meta: meta.clone(), blocks: vec![Para(inlines)] }, &options);
+        assert!(with_math.contains("<title>a &lt; b</title>\n  <script defer src=\"math.js\" type=\"text/javascript\"></script>\n</head>"));
+        let without_math = write(&Pandoc { version: vec![1, 23], meta, blocks: vec![Para(vec![str("a")])] }, &options);
+        assert!(without_math.contains("<title>a &lt; b</title>\n</head>\n<body>\n<p>a</p>\n</body>"));
+    }
+
+    #[test]
+    fn raw_content_is_kept_escaped_or_dropped() {
+        let blocks = vec![RawBlock("tex".into(), "\\a<b>".into()), Para(vec![RawInline("tex".into(), "\\b".into()), RawInline("HTML".into(), "<br>".into())])];
+        let options = |raw| Options { raw: vec![("tex".into(), raw), ("html".into(), Raw::Verbatim)], ..Options::default() };
+        assert_eq!(write_blocks(&blocks, &options(Raw::Verbatim)), "\\a<b>\n<p>\\b<br></p>");
+        assert_eq!(
+            write_blocks(&blocks, &options(Raw::Escape)),
+            "<pre class=\"raw tex\"><code>\\a&lt;b&gt;</code></pre>\n<p><code class=\"raw tex\">\\b</code><br></p>"
+        );
+        assert_eq!(write_blocks(&blocks, &options(Raw::Drop)), "<p><br></p>");
+        assert_eq!(write_blocks(&blocks, &Options::default()), "<p><br></p>");
+    }
+
+    #[test]
+    fn line_blocks_break_their_lines() {
+        let block = LineBlock(vec![vec![str("a"), Space, str("b")], vec![str("c")]]);
+        assert_eq!(write_blocks(&[block], &Options::default()), "<div class=\"line-block\">a b<br />\nc</div>");
+    }
+
+    #[test]
+    fn definition_lists_give_each_description() {
+        let list = DefinitionList(vec![
+            Definition(vec![str("term")], vec![vec![Plain(vec![str("first")])], vec![Para(vec![str("second")])]]),
+            Definition(vec![str("other")], vec![]),
+        ]);
+        assert_eq!(
+            write_blocks(&[list], &Options::default()),
+            "<dl>\n<dt>term</dt>\n<dd>\nfirst\n</dd>\n<dd>\n<p>second</p>\n</dd>\n<dt>other</dt>\n</dl>"
+        );
+    }
+}
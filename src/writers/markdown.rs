@@ -0,0 +1,719 @@
+//! Pandoc-flavoured Markdown writer.
+//!
+//! ```
+//! use pandoc::definition::{Attr, Block, Inline};
+//! use pandoc::writers::markdown;
+//!
+//! let header = Block::Header(1, Attr { id: "intro".into(), ..Attr::default() }, vec![Inline::Str("Intro".into())]);
+//! assert_eq!(markdown::write_blocks(&[header]), "# Intro {#intro}");
+//! ```
+
+use crate::definition::{Alignment, Attr, Block, Caption, Cell, Citation, CitationMode, ColSpec, Inline, ListAttributes, ListNumberDelim, ListNumberStyle, MathType, Meta, MetaValue, Pandoc, QuoteType, Row, TableBody, TableFoot, TableHead};
+use crate::definition::Block::{BlockQuote, BulletList, CodeBlock, DefinitionList, Div, Figure, Header, HorizontalRule, LineBlock, OrderedList, Para, Plain, RawBlock, Table};
+use crate::definition::Inline::{Cite, Code, Emph, Image, LineBreak, Link, Math, Note, Quoted, RawInline, SmallCaps, SoftBreak, Space, Span, Str, Strikeout, Strong, Subscript, Superscript, Underline};
+use crate::definition::MetaValue::{MetaBlocks, MetaBool, MetaInlines, MetaList, MetaMap, MetaString};
+use crate::writers::{alpha, indent, is_null_attr, is_tight, roman};
+
+/// Renders the document, preceded by a YAML metadata block when it has metadata.
+pub fn write(pandoc: &Pandoc) -> String {
+    let mut writer = Writer::default();
+    let mut result = String::new();
+    if !pandoc.meta.is_empty() {
+        result.push_str("---\n");
+        result.push_str(&writer.yaml_entries(&pandoc.meta));
+        result.push_str("\n---\n\n");
+    }
+    result.push_str(&writer.blocks(&pandoc.blocks));
+    result.push_str(&writer.notes());
+    result
+}
+
+pub fn write_blocks(blocks: &[Block]) -> String {
+    let mut writer = Writer::default();
+    let result = writer.blocks(blocks);
+    result + &writer.notes()
+}
+
+pub fn write_inlines(inlines: &[Inline]) -> String {
+    let mut writer = Writer::default();
+    let result = writer.inlines(inlines);
+    result + &writer.notes()
+}
+
+#[derive(Default)]
+struct Writer {
+    notes: Vec<String>,
+}
+
+impl Writer {
+    fn notes(&self) -> String {
+        self.notes.iter().enumerate()
+            .map(|(i, note)| {
+                let marker = format!("[^{}]: ", i + 1);
+                format!("\n\n{}", indent(note, &marker, "    "))
+            }).collect()
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) -> String {
+        let mut result = String::new();
+        let mut previous: Option<&Block> = None;
+        for block in blocks {
+            match previous {
+                Some(Plain(_)) => result.push('\n'),
+                Some(OrderedList(..)) if matches!(block, OrderedList(..)) => result.push_str("\n\n<!-- -->\n\n"),
+                Some(BulletList(..)) if matches!(block, BulletList(..)) => result.push_str("\n\n<!-- -->\n\n"),
+                Some(_) => result.push_str("\n\n"),
+                None => {}
+            }
+            result.push_str(&self.block(block));
+            previous = Some(block);
+        }
+        result
+    }
+
+    fn block(&mut self, block: &Block) -> String {
+        match block {
+            Plain(ils) | Para(ils) => self.lines(ils),
+            LineBlock(ilss) => ilss.iter()
+                .map(|ils| format!("| {}", self.inlines(ils).replace('\n', " ")))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            CodeBlock(attr, code) => {
+                let fence = "`".repeat(3.max(longest_run(code, '`') + 1));
+                let info = match (attr.id.is_empty(), attr.classes.as_slice(), attr.attributes.is_empty()) {
+                    (true, [], true) => String::new(),
+                    (true, [lang], true) => lang.clone(),
+                    _ => self.attr(attr),
+                };
+                format!("{}{}\n{}\n{}", fence, info, code.trim_end_matches('\n'), fence)
+            },
+            RawBlock(format, text) if format == "markdown" => text.clone(),
+            RawBlock(format, text) => {
+                let fence = "`".repeat(3.max(longest_run(text, '`') + 1));
+                format!("{}{{={}}}\n{}\n{}", fence, format, text.trim_end_matches('\n'), fence)
+            },
+            BlockQuote(blks) => indent(&self.blocks(blks), "> ", "> "),
+            OrderedList(list_attributes, items) => self.ordered_list(list_attributes, items),
+            BulletList(items) => {
+                let separator = if is_tight(items) { "\n" } else { "\n\n" };
+                items.iter()
+                    .map(|item| indent(&self.blocks(item), "- ", "  "))
+                    .collect::<Vec<_>>()
+                    .join(separator)
+            },
+            DefinitionList(definitions) => definitions.iter()
+                .map(|definition| {
+                    let tight = is_tight(&definition.1);
+                    let term = self.lines(&definition.0).replace('\n', " ");
+                    let definitions = definition.1.iter()
+                        .map(|blks| indent(&self.blocks(blks), ":   ", "    "))
+                        .collect::<Vec<_>>()
+                        .join(if tight { "\n" } else { "\n\n" });
+                    format!("{}{}{}", term, if tight { "\n" } else { "\n\n" }, definitions)
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            Header(lvl, attr, ils) => {
+                let text = self.inlines(ils).replace('\n', " ");
+                let attr = if is_null_attr(attr) { String::new() } else { format!(" {}", self.attr(attr)) };
+                format!("{} {}{}", "#".repeat(*lvl as usize), text, attr)
+            },
+            HorizontalRule => "-".repeat(72),
+            Table(attr, caption, specs, t_head, t_bodies, t_foot) => self.table(attr, caption, specs, t_head, t_bodies, t_foot),
+            Figure(attr, caption, blks) => self.figure(attr, caption, blks),
+            Div(attr, blks) => {
+                let content = self.blocks(blks);
+                let fence = ":".repeat(3.max(longest_fence(&content, ':') + 1));
+                format!("{} {}\n{}\n{}", fence, self.attr(attr), content, fence)
+            },
+        }
+    }
+
+    fn ordered_list(&mut self, (start, style, delim): &ListAttributes, items: &[Vec<Block>]) -> String {
+        let separator = if is_tight(items) { "\n" } else { "\n\n" };
+        items.iter().enumerate()
+            .map(|(i, item)| {
+                let n = start + i as u64;
+                let number = match style {
+                    ListNumberStyle::Example => "@".to_string(),
+                    ListNumberStyle::LowerRoman => roman(n, false),
+                    ListNumberStyle::UpperRoman => roman(n, true),
+                    ListNumberStyle::LowerAlpha => alpha(n, false),
+                    ListNumberStyle::UpperAlpha => alpha(n, true),
+                    _ => n.to_string(),
+                };
+                let marker = match delim {
+                    ListNumberDelim::OneParen => format!("{}) ", number),
+                    ListNumberDelim::TwoParens => format!("({}) ", number),
+                    _ => format!("{}. ", number),
+                };
+                let rest = " ".repeat(marker.chars().count());
+                indent(&self.blocks(item), &marker, &rest)
+            })
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    fn figure(&mut self, attr: &Attr, caption: &Caption, blks: &[Block]) -> String {
+        if let [Plain(ils)] | [Para(ils)] = blks {
+            if let [Image(image_attr, alt, (src, title))] = ils.as_slice() {
+                let caption = caption_inlines(caption);
+                let description = if caption.is_empty() { alt.clone() } else { caption };
+                let mut image_attr = image_attr.clone();
+                if image_attr.id.is_empty() {
+                    image_attr.id = attr.id.clone();
+                }
+                return self.inline(&Image(image_attr, description, (src.clone(), title.clone())));
+            }
+        }
+        let mut attr = attr.clone();
+        attr.classes.insert(0, "figure".to_string());
+        let mut content = self.blocks(blks);
+        if !caption.1.is_empty() {
+            content.push_str("\n\n");
+            content.push_str(&self.blocks(&caption.1));
+        }
+        let fence = ":".repeat(3.max(longest_fence(&content, ':') + 1));
+        format!("{} {}\n{}\n{}", fence, self.attr(&attr), content, fence)
+    }
+
+    fn table(&mut self, attr: &Attr, caption: &Caption, specs: &[ColSpec], t_head: &TableHead, t_bodies: &[TableBody], t_foot: &TableFoot) -> String {
+        let aligns: Vec<&Alignment> = specs.iter().map(|it| &it.0).collect();
+        let head: Vec<&Row> = t_head.1.iter().collect();
+        let body: Vec<&Row> = t_bodies.iter()
+            .flat_map(|it| it.2.iter().chain(it.3.iter()))
+            .chain(t_foot.1.iter())
+            .collect();
+        let simple = head.iter().chain(body.iter()).all(|row| {
+            row.1.len() <= aligns.len() && row.1.iter().all(|Cell(_, _, row_span, col_span, blks)| {
+                *row_span <= 1 && *col_span <= 1 && matches!(blks.as_slice(), [] | [Plain(_)] | [Para(_)])
+            })
+        });
+        let mut result = if simple && head.len() <= 1 {
+            self.pipe_table(&aligns, head.first().copied(), &body)
+        } else {
+            self.grid_table(&aligns, &head, &body)
+        };
+        let caption = self.blocks(&caption.1).replace('\n', " ");
+        if !caption.is_empty() || !is_null_attr(attr) {
+            result.push_str("\n\n: ");
+            result.push_str(&caption);
+            if !is_null_attr(attr) {
+                result.push(' ');
+                result.push_str(&self.attr(attr));
+            }
+        }
+        result
+    }
+
+    fn pipe_table(&mut self, aligns: &[&Alignment], head: Option<&Row>, body: &[&Row]) -> String {
+        let columns = aligns.len();
+        let mut cells = |row: Option<&Row>| -> Vec<String> {
+            let mut cells: Vec<String> = row.map(|row| row.1.iter()
+                .map(|cell| self.blocks(&cell.4).replace('\n', " ").replace('|', "\\|"))
+                .collect()).unwrap_or_default();
+            cells.resize(columns, String::new());
+            cells
+        };
+        let head = cells(head);
+        let body: Vec<Vec<String>> = body.iter().map(|row| cells(Some(row))).collect();
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| body.iter().chain(Some(&head)).map(|row| row[i].chars().count()).max().unwrap_or(0).max(3))
+            .collect();
+        let line = |cells: &[String]| -> String {
+            let cells: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| pad(cell, *width)).collect();
+            format!("| {} |", cells.join(" | "))
+        };
+        let separators: Vec<String> = aligns.iter().zip(&widths)
+            .map(|(align, width)| match align {
+                Alignment::AlignLeft => format!(":{}", "-".repeat(width + 1)),
+                Alignment::AlignRight => format!("{}:", "-".repeat(width + 1)),
+                Alignment::AlignCenter => format!(":{}:", "-".repeat(*width)),
+                Alignment::AlignDefault => "-".repeat(width + 2),
+            }).collect();
+        let mut lines = vec![line(&head), format!("|{}|", separators.join("|"))];
+        lines.extend(body.iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+
+    fn grid_table(&mut self, aligns: &[&Alignment], head: &[&Row], body: &[&Row]) -> String {
+        let columns = aligns.len().max(head.iter().chain(body.iter()).map(|row| row.1.len()).max().unwrap_or(0));
+        let mut cells = |row: &Row| -> Vec<Vec<String>> {
+            let mut cells: Vec<Vec<String>> = row.1.iter()
+                .map(|cell| self.blocks(&cell.4).split('\n').map(str::to_string).collect())
+                .collect();
+            cells.resize(columns, vec![]);
+            cells
+        };
+        let head: Vec<Vec<Vec<String>>> = head.iter().map(|row| cells(row)).collect();
+        let body: Vec<Vec<Vec<String>>> = body.iter().map(|row| cells(row)).collect();
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| head.iter().chain(body.iter())
+                .flat_map(|row| row[i].iter().map(|line| line.chars().count()))
+                .max().unwrap_or(0).max(3))
+            .collect();
+        let separator = |fill: char, with_aligns: bool| -> String {
+            let parts: Vec<String> = widths.iter().enumerate()
+                .map(|(i, width)| {
+                    let align = if with_aligns { aligns.get(i).copied() } else { None };
+                    let (left, right) = match align {
+                        Some(Alignment::AlignLeft) => (':', fill),
+                        Some(Alignment::AlignRight) => (fill, ':'),
+                        Some(Alignment::AlignCenter) => (':', ':'),
+                        _ => (fill, fill),
+                    };
+                    format!("{}{}{}", left, fill.to_string().repeat(*width), right)
+                }).collect();
+            format!("+{}+", parts.join("+"))
+        };
+        let row_lines = |row: &Vec<Vec<String>>| -> Vec<String> {
+            let height = row.iter().map(Vec::len).max().unwrap_or(0).max(1);
+            (0..height)
+                .map(|i| {
+                    let parts: Vec<String> = row.iter().zip(&widths)
+                        .map(|(cell, width)| pad(cell.get(i).map(String::as_str).unwrap_or(""), *width))
+                        .collect();
+                    format!("| {} |", parts.join(" | "))
+                }).collect()
+        };
+        let mut lines = vec![separator('-', head.is_empty())];
+        for row in &head {
+            lines.extend(row_lines(row));
+            lines.push(separator('-', false));
+        }
+        if !head.is_empty() {
+            lines.pop();
+            lines.push(separator('=', true));
+        }
+        for row in &body {
+            lines.extend(row_lines(row));
+            lines.push(separator('-', false));
+        }
+        lines.join("\n")
+    }
+
+    /// Renders the inlines of a paragraph, escaping the words that would start a block at the
+    /// beginning of a line.
+    fn lines(&mut self, inlines: &[Inline]) -> String {
+        let mut result = String::new();
+        let mut line_start = true;
+        for (i, inline) in inlines.iter().enumerate() {
+            match inline {
+                Str(text) if line_start => {
+                    let ends_line = matches!(inlines.get(i + 1), None | Some(Space) | Some(SoftBreak) | Some(LineBreak));
+                    result.push_str(&escape_line_start(text, ends_line));
+                },
+                _ => result.push_str(&self.inline(inline)),
+            }
+            line_start = matches!(inline, SoftBreak | LineBreak);
+        }
+        result
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) -> String {
+        let mut result = String::new();
+        for inline in inlines {
+            result.push_str(&self.inline(inline));
+        }
+        result
+    }
+
+    fn inline(&mut self, inline: &Inline) -> String {
+        match inline {
+            Str(text) => escape(text),
+            Emph(ils) => format!("*{}*", self.inlines(ils)),
+            Underline(ils) => format!("[{}]{{.underline}}", self.inlines(ils)),
+            Strong(ils) => format!("**{}**", self.inlines(ils)),
+            Strikeout(ils) => format!("~~{}~~", self.inlines(ils)),
+            Superscript(ils) => format!("^{}^", self.inlines(ils).replace(' ', "\\ ")),
+            Subscript(ils) => format!("~{}~", self.inlines(ils).replace(' ', "\\ ")),
+            SmallCaps(ils) => format!("[{}]{{.smallcaps}}", self.inlines(ils)),
+            Quoted(QuoteType::SingleQuote, ils) => format!("'{}'", self.inlines(ils)),
+            Quoted(QuoteType::DoubleQuote, ils) => format!("\"{}\"", self.inlines(ils)),
+            Cite(citations, _) => self.citations(citations),
+            Code(attr, code) => {
+                let fence = "`".repeat(longest_run(code, '`') + 1);
+                let padding = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+                let attr = if is_null_attr(attr) { String::new() } else { self.attr(attr) };
+                format!("{}{}{}{}{}{}", fence, padding, code, padding, fence, attr)
+            },
+            Space => " ".to_string(),
+            SoftBreak => "\n".to_string(),
+            LineBreak => "\\\n".to_string(),
+            Math(MathType::InlineMath, math) => format!("${}$", math),
+            Math(MathType::DisplayMath, math) => format!("$${}$$", math),
+            RawInline(format, text) if format == "markdown" => text.clone(),
+            RawInline(format, text) => {
+                let fence = "`".repeat(longest_run(text, '`') + 1);
+                format!("{}{}{}{{={}}}", fence, text, fence, format)
+            },
+            Link(attr, ils, (url, title)) => {
+                if title.is_empty() && is_autolink(attr, ils, url) {
+                    return format!("<{}>", url.trim_start_matches("mailto:"));
+                }
+                format!("[{}]({}){}", self.inlines(ils), target(url, title), self.optional_attr(attr))
+            },
+            Image(attr, ils, (url, title)) => format!("![{}]({}){}", self.inlines(ils), target(url, title), self.optional_attr(attr)),
+            Note(blks) => {
+                self.notes.push(String::new());
+                let n = self.notes.len();
+                self.notes[n - 1] = self.blocks(blks);
+                format!("[^{}]", n)
+            },
+            Span(attr, ils) => format!("[{}]{}", self.inlines(ils), self.attr(attr)),
+        }
+    }
+
+    /// Writes the citations in brackets, except a first citation in `AuthorInText` mode, written
+    /// before the brackets holding its suffix and the other citations.
+    fn citations(&mut self, citations: &[Citation]) -> String {
+        match citations.split_first() {
+            Some((first, rest)) if matches!(first.citation_mode, CitationMode::AuthorInText) => {
+                let suffix = self.inlines(&first.citation_suffix);
+                let rest: Vec<String> = rest.iter().map(|it| self.citation(it)).collect();
+                if rest.is_empty() && suffix.trim().is_empty() {
+                    format!("@{}", first.citation_id)
+                } else if rest.is_empty() {
+                    format!("@{} [{}]", first.citation_id, suffix.trim())
+                } else {
+                    format!("@{} [{}; {}]", first.citation_id, suffix.trim(), rest.join("; "))
+                }
+            },
+            _ => {
+                let citations: Vec<String> = citations.iter().map(|it| self.citation(it)).collect();
+                format!("[{}]", citations.join("; "))
+            },
+        }
+    }
+
+    fn citation(&mut self, citation: &Citation) -> String {
+        let prefix = self.inlines(&citation.citation_prefix);
+        let suffix = self.inlines(&citation.citation_suffix);
+        let mut result = String::new();
+        if !prefix.trim().is_empty() {
+            result.push_str(prefix.trim());
+            result.push(' ');
+        }
+        if let CitationMode::SuppressAuthor = citation.citation_mode {
+            result.push('-');
+        }
+        result.push('@');
+        result.push_str(&citation.citation_id);
+        if suffix.starts_with(|c: char| c.is_alphanumeric()) {
+            result.push(' ');
+        }
+        result.push_str(suffix.trim_end());
+        result
+    }
+
+    fn attr(&self, attr: &Attr) -> String {
+        let mut parts = Vec::new();
+        if !attr.id.is_empty() {
+            parts.push(format!("#{}", attr.id));
+        }
+        parts.extend(attr.classes.iter().map(|it| format!(".{}", it)));
+        parts.extend(attr.attributes.iter().map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\""))));
+        format!("{{{}}}", parts.join(" "))
+    }
+
+    fn optional_attr(&self, attr: &Attr) -> String {
+        if is_null_attr(attr) { String::new() } else { self.attr(attr) }
+    }
+
+    fn yaml_entries(&mut self, meta: &Meta) -> String {
+        meta.iter()
+            .map(|(key, value)| match value {
+                MetaMap(map) if !map.is_empty() => format!("{}:\n{}", key, indent(&self.yaml_entries(map), "  ", "  ")),
+                MetaList(values) if !values.is_empty() => format!("{}:\n{}", key, self.yaml_list(values)),
+                _ => format!("{}: {}", key, self.yaml_scalar(value)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn yaml_list(&mut self, values: &[MetaValue]) -> String {
+        values.iter()
+            .map(|value| match value {
+                MetaMap(map) if !map.is_empty() => indent(&self.yaml_entries(map), "- ", "  "),
+                MetaList(values) if !values.is_empty() => indent(&self.yaml_list(values), "- ", "  "),
+                _ => format!("- {}", self.yaml_scalar(value)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn yaml_scalar(&mut self, value: &MetaValue) -> String {
+        match value {
+            MetaMap(_) => "{}".to_string(),
+            MetaList(_) => "[]".to_string(),
+            MetaBool(b) => b.to_string(),
+            MetaString(text) => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+            MetaInlines(ils) => format!("'{}'", self.inlines(ils).replace('\'', "''").replace('\n', " ")),
+            MetaBlocks(blks) => format!("|\n{}", indent(&self.blocks(blks), "  ", "  ")),
+        }
+    }
+}
+
+fn caption_inlines(caption: &Caption) -> Vec<Inline> {
+    let mut result = Vec::new();
+    for block in &caption.1 {
+        if let Plain(ils) | Para(ils) = block {
+            if !result.is_empty() {
+                result.push(Space);
+            }
+            result.extend(ils.iter().cloned());
+        }
+    }
+    result
+}
+
+fn is_autolink(attr: &Attr, ils: &[Inline], url: &str) -> bool {
+    let plain = attr.id.is_empty() && attr.attributes.is_empty() && match attr.classes.as_slice() {
+        [] => true,
+        [class] => class == "uri" || class == "email",
+        _ => false
+    };
+    match ils {
+        [Str(text)] => plain && (text == url || format!("mailto:{}", text) == url),
+        _ => false
+    }
+}
+
+fn target(url: &str, title: &str) -> String {
+    let url = if url.contains([' ', '(', ')']) { format!("<{}>", url) } else { url.to_string() };
+    if title.is_empty() {
+        url
+    } else {
+        format!("{} \"{}\"", url, title.replace('"', "\\\""))
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '$' | '~' | '^' => result.push('\\'),
+            '@' if i == 0 => result.push('\\'),
+            _ => {}
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Escapes `text` as the first word of a line, whose list, header, quote or line block marker
+/// would otherwise start a block; `ends_word` tells whether a space or the end of the line
+/// follows `text`.
+fn escape_line_start(text: &str, ends_word: bool) -> String {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    let marker = match text.chars().next() {
+        Some('#') => text.chars().take_while(|it| *it == '#').count(),
+        Some('-') | Some('+') | Some('|') | Some('=') => 1,
+        _ if digits > 0 && text[digits..].starts_with(['.', ')']) => digits + 1,
+        _ => 0,
+    };
+    let rest = &text[marker..];
+    let setext = ends_word && (text.chars().all(|it| it == '-') || text.chars().all(|it| it == '='));
+    if marker == 0 || !(rest.starts_with(' ') || rest.is_empty() && ends_word || setext) {
+        return escape(text);
+    }
+    if digits > 0 {
+        format!("{}\\{}", &text[..digits], escape(&text[digits..]))
+    } else {
+        format!("\\{}", escape(text))
+    }
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for it in text.chars() {
+        if it == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+fn longest_fence(text: &str, c: char) -> usize {
+    text.lines()
+        .map(|line| line.chars().take_while(|it| *it == c).count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn pad(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    format!("{}{}", text, " ".repeat(width.saturating_sub(len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attr;
+    use crate::definition::{Alignment, Attr, Caption, Cell, Citation, CitationMode, ColSpec, ColWidth, Inline, ListNumberDelim, ListNumberStyle, MathType, QuoteType, Row, TableBody, TableFoot, TableHead};
+    use crate::definition::Block::*;
+    use crate::definition::Inline::*;
+    use super::{write_blocks, write_inlines};
+
+    fn str(text: &str) -> Inline {
+        Str(text.to_string())
+    }
+
+    fn words(text: &str) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+        for word in text.split(' ') {
+            if !inlines.is_empty() {
+                inlines.push(Space);
+            }
+            inlines.push(str(word));
+        }
+        inlines
+    }
+
+    /// Writes `blocks` and checks the CommonMark reader reads them back.
+    #[cfg(feature = "commonmark")]
+    fn round_trip(blocks: Vec<crate::definition::Block>) {
+        use crate::readers::commonmark::{read, Identifiers, Options};
+        let markdown = write_blocks(&blocks);
+        let read = read(&markdown, &Options { gfm: true, attributes: true, identifiers: Identifiers::None });
+        assert_eq!(serde_json::to_value(&read.blocks).unwrap(), serde_json::to_value(&blocks).unwrap(), "written as:\n{}", markdown);
+    }
+
+    fn cell(text: &str) -> Cell {
+        Cell(Attr::default(), Alignment::AlignDefault, 1, 1, vec![Plain(words(text))])
+    }
+
+    fn citation(id: &str, mode: CitationMode, prefix: Vec<Inline>, suffix: Vec<Inline>) -> Citation {
+        Citation { citation_id: id.to_string(), citation_prefix: prefix, citation_suffix: suffix, citation_mode: mode, citation_note_num: 0, citation_hash: 0 }
+    }
+
+    #[cfg(feature = "commonmark")]
+    #[test]
+    fn round_trip_blocks() {
+        round_trip(vec![Para(words("a paragraph"))]);
+        round_trip(vec![Header(2, Attr::default(), words("A header"))]);
+        round_trip(vec![Header(1, attr!("intro"), words("Intro"))]);
+        round_trip(vec![CodeBlock(attr!(""; "rust"), "fn main() {}".into())]);
+        round_trip(vec![CodeBlock(Attr::default(), "```\nfenced\n```".into())]);
+        round_trip(vec![BlockQuote(vec![Para(words("quoted")), BlockQuote(vec![Para(words("nested"))])])]);
+        round_trip(vec![BulletList(vec![vec![Plain(words("tight"))], vec![Plain(words("list"))]])]);
+        round_trip(vec![BulletList(vec![vec![Para(words("loose"))], vec![Para(words("list"))]])]);
+        round_trip(vec![OrderedList((3, ListNumberStyle::Decimal, ListNumberDelim::Period), vec![vec![Plain(words("three"))], vec![Plain(words("four"))]])]);
+        round_trip(vec![HorizontalRule]);
+        round_trip(vec![Table(
+            Attr::default(),
+            Caption(None, vec![]),
+            vec![ColSpec(Alignment::AlignLeft, ColWidth::ColWidthDefault), ColSpec(Alignment::AlignRight, ColWidth::ColWidthDefault)],
+            TableHead(Attr::default(), vec![Row(Attr::default(), vec![cell("a"), cell("b")])]),
+            vec![TableBody(Attr::default(), 0, vec![], vec![Row(Attr::default(), vec![cell("1"), cell("x | y")])])],
+            TableFoot(Attr::default(), vec![]),
+        )]);
+    }
+
+    #[cfg(feature = "commonmark")]
+    #[test]
+    fn round_trip_inlines() {
+        round_trip(vec![Para(vec![Emph(words("emphasis")), Space, Strong(words("strong")), Space, Strikeout(words("struck"))])]);
+        round_trip(vec![Para(vec![Code(Attr::default(), "a `tick`".into()), Space, Code(Attr::default(), "`".into())])]);
+        round_trip(vec![Para(vec![str("line"), LineBreak, str("break"), SoftBreak, str("soft")])]);
+        round_trip(vec![Para(vec![Link(Attr::default(), words("a link"), ("https://example.com".into(), "title".into()))])]);
+        round_trip(vec![Para(vec![Link(attr!(""; "uri"), vec![str("https://example.com")], ("https://example.com".into(), "".into()))])]);
+        round_trip(vec![Para(vec![Link(attr!(""; "email"), vec![str("me@example.com")], ("mailto:me@example.com".into(), "".into()))])]);
+        round_trip(vec![Para(vec![Image(Attr::default(), words("alt"), ("image.png".into(), "".into()))])]);
+        round_trip(vec![Para(vec![str("text"), Note(vec![Para(words("a note"))])])]);
+        round_trip(vec![Para(words("*not* [a link](x) `code` <html> 2^3^ ~sub~ $x$ \\ _"))]);
+    }
+
+    #[cfg(feature = "commonmark")]
+    #[test]
+    fn block_markers_at_line_start_are_escaped() {
+        for text in ["#", "##", "-", "+", "*", ">", "|", "1.", "12)", "---", "==="] {
+            round_trip(vec![Para(vec![str(text), Space, str("x")])]);
+            round_trip(vec![Para(vec![str("x"), SoftBreak, str(text)])]);
+        }
+        round_trip(vec![Para(words("# not a header"))]);
+        round_trip(vec![Para([vec![str("x"), SoftBreak], words("- not a list")].concat())]);
+        round_trip(vec![BulletList(vec![vec![Plain(words("1. not a nested list"))]])]);
+    }
+
+    #[test]
+    fn block_markers_are_escaped_only_at_line_start() {
+        assert_eq!(write_blocks(&[Para(words("# 1. - + | ---"))]), "\\# 1. - + | ---");
+        assert_eq!(write_blocks(&[Para(words("1. x"))]), "1\\. x");
+        assert_eq!(write_blocks(&[Para(words("#hashtag -dash 1.5"))]), "#hashtag -dash 1.5");
+        assert_eq!(write_blocks(&[Header(1, Attr::default(), words("# x"))]), "# # x");
+    }
+
+    #[test]
+    fn pandoc_blocks() {
+        assert_eq!(write_blocks(&[LineBlock(vec![words("one"), words("two  lines")])]), "| one\n| two  lines");
+        assert_eq!(write_blocks(&[RawBlock("html".into(), "<hr/>".into())]), "```{=html}\n<hr/>\n```");
+        assert_eq!(write_blocks(&[RawBlock("markdown".into(), "*raw*".into())]), "*raw*");
+        assert_eq!(
+            write_blocks(&[DefinitionList(vec![crate::definition::Definition(words("term"), vec![vec![Plain(words("definition"))]])])]),
+            "term\n:   definition"
+        );
+        assert_eq!(write_blocks(&[Div(attr!("d"; "note"), vec![Para(words("inside"))])]), "::: {#d .note}\ninside\n:::");
+        assert_eq!(
+            write_blocks(&[Div(Attr::default(), vec![Div(attr!(""; "inner"), vec![Para(words("x"))])])]),
+            ":::: {}\n::: {.inner}\nx\n:::\n::::"
+        );
+        assert_eq!(write_blocks(&[OrderedList((1, ListNumberStyle::LowerAlpha, ListNumberDelim::OneParen), vec![vec![Plain(words("a"))]])]), "a) a");
+        assert_eq!(write_blocks(&[OrderedList((4, ListNumberStyle::UpperRoman, ListNumberDelim::TwoParens), vec![vec![Plain(words("a"))]])]), "(IV) a");
+    }
+
+    #[test]
+    fn figures() {
+        let image = Image(Attr::default(), vec![], ("image.png".into(), "".into()));
+        let figure = Figure(attr!("fig"), Caption(None, vec![Plain(words("A caption"))]), vec![Plain(vec![image])]);
+        assert_eq!(write_blocks(&[figure]), "![A caption](image.png){#fig}");
+        let figure = Figure(attr!("fig"), Caption(None, vec![Plain(words("A caption"))]), vec![Para(words("content"))]);
+        assert_eq!(write_blocks(&[figure]), "::: {#fig .figure}\ncontent\n\nA caption\n:::");
+    }
+
+    #[test]
+    fn grid_tables() {
+        let multi_line = Cell(Attr::default(), Alignment::AlignDefault, 1, 1, vec![Para(words("a")), Para(words("b"))]);
+        let table = Table(
+            Attr::default(),
+            Caption(None, vec![Plain(words("Caption"))]),
+            vec![ColSpec(Alignment::AlignCenter, ColWidth::ColWidthDefault)],
+            TableHead(Attr::default(), vec![Row(Attr::default(), vec![cell("head")])]),
+            vec![TableBody(Attr::default(), 0, vec![], vec![Row(Attr::default(), vec![multi_line])])],
+            TableFoot(Attr::default(), vec![]),
+        );
+        assert_eq!(write_blocks(&[table]), "+------+\n| head |\n+:====:+\n| a    |\n|      |\n| b    |\n+------+\n\n: Caption");
+    }
+
+    #[test]
+    fn pandoc_inlines() {
+        assert_eq!(write_inlines(&[Underline(words("u"))]), "[u]{.underline}");
+        assert_eq!(write_inlines(&[SmallCaps(words("sc"))]), "[sc]{.smallcaps}");
+        assert_eq!(write_inlines(&[Superscript(words("a b"))]), "^a\\ b^");
+        assert_eq!(write_inlines(&[Subscript(words("2"))]), "~2~");
+        assert_eq!(write_inlines(&[Quoted(QuoteType::DoubleQuote, words("q")), Quoted(QuoteType::SingleQuote, words("s"))]), "\"q\"'s'");
+        assert_eq!(write_inlines(&[Math(MathType::InlineMath, "x^2".into()), Math(MathType::DisplayMath, "y".into())]), "$x^2$$$y$$");
+        assert_eq!(write_inlines(&[RawInline("tex".into(), "\\LaTeX".into())]), "`\\LaTeX`{=tex}");
+        assert_eq!(write_inlines(&[Span(attr!("s"; "c"; "k" => "v \"q\""), words("x"))]), "[x]{#s .c k=\"v \\\"q\\\"\"}");
+        assert_eq!(write_inlines(&[Code(attr!(""; "rust"), "x".into())]), "`x`{.rust}");
+        assert_eq!(write_inlines(&[Link(attr!(""; "c"), words("l"), ("a b".into(), "t \"q\"".into()))]), "[l](<a b> \"t \\\"q\\\"\"){.c}");
+        assert_eq!(write_inlines(&[str("@not"), Space, str("a@cite")]), "\\@not a@cite");
+    }
+
+    #[test]
+    fn citations() {
+        let normal = citation("doe", CitationMode::NormalCitation, words("see"), vec![str(","), Space, str("p."), Space, str("3")]);
+        let suppress = citation("roe", CitationMode::SuppressAuthor, vec![], vec![]);
+        assert_eq!(write_inlines(&[Cite(vec![normal.clone(), suppress.clone()], vec![])]), "[see @doe, p. 3; -@roe]");
+
+        let in_text = citation("smith", CitationMode::AuthorInText, vec![], vec![]);
+        assert_eq!(write_inlines(&[Cite(vec![in_text.clone()], vec![])]), "@smith");
+        let with_suffix = citation("smith", CitationMode::AuthorInText, vec![], words("p. 33"));
+        assert_eq!(write_inlines(&[Cite(vec![with_suffix.clone()], vec![])]), "@smith [p. 33]");
+        assert_eq!(write_inlines(&[Cite(vec![with_suffix, suppress.clone()], vec![])]), "@smith [p. 33; -@roe]");
+        assert_eq!(write_inlines(&[Cite(vec![in_text, normal], vec![])]), "@smith [; see @doe, p. 3]");
+    }
+}
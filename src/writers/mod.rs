@@ -0,0 +1,61 @@
+pub mod html;
+pub mod markdown;
+pub mod plain;
+
+use crate::definition::{Attr, Block};
+use crate::definition::Block::Para;
+
+pub(crate) fn is_null_attr(attr: &Attr) -> bool {
+    attr.id.is_empty() && attr.classes.is_empty() && attr.attributes.is_empty()
+}
+
+/// Whether the items of a list are rendered without blank lines in between.
+pub(crate) fn is_tight(items: &[Vec<Block>]) -> bool {
+    items.iter().all(|item| !item.iter().any(|it| matches!(it, Para(_))))
+}
+
+/// Prefixes the first line of `text` with `first` and the next ones with `rest`; blank lines
+/// are left unindented.
+pub(crate) fn indent(text: &str, first: &str, rest: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        let prefix = if i == 0 { first } else { rest };
+        if line.is_empty() {
+            result.push_str(prefix.trim_end());
+        } else {
+            result.push_str(prefix);
+            result.push_str(line);
+        }
+    }
+    result
+}
+
+pub(crate) fn roman(mut n: u64, upper: bool) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"),
+        (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS.iter() {
+        while n >= *value {
+            result.push_str(numeral);
+            n -= value;
+        }
+    }
+    if upper { result.to_uppercase() } else { result }
+}
+
+pub(crate) fn alpha(n: u64, upper: bool) -> String {
+    let mut n = n.max(1);
+    let mut result = Vec::new();
+    while n > 0 {
+        n -= 1;
+        result.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    let result: String = result.into_iter().rev().collect();
+    if upper { result.to_uppercase() } else { result }
+}
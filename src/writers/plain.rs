@@ -0,0 +1,373 @@
+//! Plain-text writer.
+//!
+//! ```
+//! use pandoc::definition::Block;
+//! use pandoc::definition::Inline::{Space, Str, Strong};
+//! use pandoc::writers::plain;
+//!
+//! let para = Block::Para(vec![Str("Hello".into()), Space, Strong(vec![Str("world".into())])]);
+//! let options = plain::Options { columns: Some(8) };
+//! assert_eq!(plain::write_blocks(&[para], &options), "Hello\nworld");
+//! ```
+
+use std::mem;
+use crate::definition::{Alignment, Block, Caption, ColSpec, Inline, ListAttributes, ListNumberDelim, ListNumberStyle, Pandoc, QuoteType, Row, TableBody, TableFoot, TableHead};
+use crate::definition::Block::{BlockQuote, BulletList, CodeBlock, DefinitionList, Div, Figure, Header, HorizontalRule, LineBlock, OrderedList, Para, Plain, RawBlock, Table};
+use crate::definition::Inline::{Cite, Code, Emph, Image, LineBreak, Link, Math, Note, Quoted, RawInline, SmallCaps, SoftBreak, Space, Span, Str, Strikeout, Strong, Subscript, Superscript, Underline};
+use crate::writers::{alpha, indent, is_tight, roman};
+
+/// Writer settings.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Line length paragraphs are wrapped at, `None` keeping each paragraph on one line.
+    pub columns: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { columns: Some(72) }
+    }
+}
+
+pub fn write(pandoc: &Pandoc, options: &Options) -> String {
+    write_blocks(&pandoc.blocks, options)
+}
+
+pub fn write_blocks(blocks: &[Block], options: &Options) -> String {
+    let mut writer = Writer::default();
+    let result = writer.blocks(blocks, options.columns);
+    result + &writer.notes()
+}
+
+pub fn write_inlines(inlines: &[Inline], options: &Options) -> String {
+    let mut writer = Writer::default();
+    let result = writer.inlines(inlines, options.columns);
+    result + &writer.notes()
+}
+
+#[derive(Default)]
+struct Writer {
+    notes: Vec<String>,
+}
+
+impl Writer {
+    fn notes(&self) -> String {
+        self.notes.iter().enumerate()
+            .map(|(i, note)| {
+                let marker = format!("[{}] ", i + 1);
+                let rest = " ".repeat(marker.len());
+                format!("\n\n{}", indent(note, &marker, &rest))
+            }).collect()
+    }
+
+    fn blocks(&mut self, blocks: &[Block], width: Option<usize>) -> String {
+        let mut result = String::new();
+        let mut previous: Option<&Block> = None;
+        for block in blocks {
+            let text = self.block(block, width);
+            if text.is_empty() {
+                continue;
+            }
+            match previous {
+                Some(Plain(_)) => result.push('\n'),
+                Some(_) => result.push_str("\n\n"),
+                None => {}
+            }
+            result.push_str(&text);
+            previous = Some(block);
+        }
+        result
+    }
+
+    fn block(&mut self, block: &Block, width: Option<usize>) -> String {
+        match block {
+            Plain(ils) | Para(ils) | Header(_, _, ils) => self.inlines(ils, width),
+            LineBlock(ilss) => ilss.iter()
+                .map(|ils| self.inlines(ils, None))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            CodeBlock(_, code) => indent(code.trim_end_matches('\n'), "    ", "    "),
+            RawBlock(format, text) if format == "plain" => text.clone(),
+            RawBlock(..) => String::new(),
+            BlockQuote(blks) => indent(&self.blocks(blks, narrow(width, 2)), "  ", "  "),
+            OrderedList(list_attributes, items) => self.ordered_list(list_attributes, items, width),
+            BulletList(items) => {
+                let separator = if is_tight(items) { "\n" } else { "\n\n" };
+                items.iter()
+                    .map(|item| indent(&self.blocks(item, narrow(width, 4)), "-   ", "    "))
+                    .collect::<Vec<_>>()
+                    .join(separator)
+            },
+            DefinitionList(definitions) => definitions.iter()
+                .map(|definition| {
+                    let term = self.inlines(&definition.0, None);
+                    let definitions: Vec<String> = definition.1.iter()
+                        .map(|blks| indent(&self.blocks(blks, narrow(width, 4)), "    ", "    "))
+                        .collect();
+                    format!("{}\n\n{}", term, definitions.join("\n\n"))
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            HorizontalRule => "-".repeat(width.unwrap_or(72).min(72)),
+            Table(_, caption, specs, t_head, t_bodies, t_foot) => self.table(caption, specs, t_head, t_bodies, t_foot, width),
+            Figure(_, caption, blks) => {
+                let content = self.blocks(blks, width);
+                let caption = self.blocks(&caption.1, width);
+                [content, caption].iter().filter(|it| !it.is_empty()).cloned().collect::<Vec<_>>().join("\n\n")
+            },
+            Div(_, blks) => self.blocks(blks, width),
+        }
+    }
+
+    fn ordered_list(&mut self, (start, style, delim): &ListAttributes, items: &[Vec<Block>], width: Option<usize>) -> String {
+        let separator = if is_tight(items) { "\n" } else { "\n\n" };
+        let markers: Vec<String> = (0..items.len() as u64)
+            .map(|i| {
+                let n = start + i;
+                let number = match style {
+                    ListNumberStyle::LowerRoman => roman(n, false),
+                    ListNumberStyle::UpperRoman => roman(n, true),
+                    ListNumberStyle::LowerAlpha => alpha(n, false),
+                    ListNumberStyle::UpperAlpha => alpha(n, true),
+                    _ => n.to_string(),
+                };
+                match delim {
+                    ListNumberDelim::OneParen => format!("{})", number),
+                    ListNumberDelim::TwoParens => format!("({})", number),
+                    _ => format!("{}.", number),
+                }
+            }).collect();
+        let marker_width = markers.iter().map(|it| it.chars().count() + 1).max().unwrap_or(0).max(4);
+        let rest = " ".repeat(marker_width);
+        items.iter().zip(markers)
+            .map(|(item, marker)| {
+                let marker = format!("{}{}", marker, " ".repeat(marker_width - marker.chars().count()));
+                indent(&self.blocks(item, narrow(width, marker_width)), &marker, &rest)
+            })
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Lays the table out as a simple table: spans are ignored and each cell is kept on one line.
+    fn table(&mut self, caption: &Caption, specs: &[ColSpec], t_head: &TableHead, t_bodies: &[TableBody], t_foot: &TableFoot, width: Option<usize>) -> String {
+        let mut cells = |rows: &mut dyn Iterator<Item = &Row>| -> Vec<Vec<String>> {
+            rows.map(|row| row.1.iter().map(|cell| self.blocks(&cell.4, None).replace('\n', " ")).collect())
+                .collect()
+        };
+        let head = cells(&mut t_head.1.iter());
+        let body = cells(&mut t_bodies.iter()
+            .flat_map(|it| it.2.iter().chain(it.3.iter()))
+            .chain(t_foot.1.iter()));
+        let columns = head.iter().chain(body.iter()).map(Vec::len).max().unwrap_or(0).max(specs.len());
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| head.iter().chain(body.iter())
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.chars().count())
+                .max().unwrap_or(0).max(3))
+            .collect();
+        let line = |row: &Vec<String>| -> String {
+            let cells: Vec<String> = widths.iter().enumerate()
+                .map(|(i, width)| {
+                    let cell = row.get(i).map(String::as_str).unwrap_or("");
+                    align(cell, *width, specs.get(i).map_or(&Alignment::AlignDefault, |it| &it.0))
+                }).collect();
+            cells.join("  ").trim_end().to_string()
+        };
+        let rule = widths.iter().map(|it| "-".repeat(*it)).collect::<Vec<_>>().join("  ");
+        let mut lines = Vec::new();
+        if head.is_empty() {
+            lines.push(rule.clone());
+        } else {
+            lines.extend(head.iter().map(line));
+        }
+        lines.push(rule.clone());
+        lines.extend(body.iter().map(line));
+        if head.is_empty() {
+            lines.push(rule);
+        }
+        let caption = self.blocks(&caption.1, width);
+        if !caption.is_empty() {
+            lines.push(String::new());
+            lines.push(caption);
+        }
+        lines.join("\n")
+    }
+
+    /// Writes `inlines` filling lines up to `width` columns.
+    fn inlines(&mut self, inlines: &[Inline], width: Option<usize>) -> String {
+        let mut tokens = Vec::new();
+        self.tokens(inlines, width, &mut tokens);
+        wrap(&tokens, width)
+    }
+
+    fn tokens(&mut self, inlines: &[Inline], width: Option<usize>, tokens: &mut Vec<Token>) {
+        for inline in inlines {
+            match inline {
+                Str(text) | Code(_, text) | Math(_, text) => tokens.push(Token::Text(text.clone())),
+                Emph(ils) | Underline(ils) | Strong(ils) | Strikeout(ils) | Superscript(ils) | Subscript(ils) | SmallCaps(ils)
+                | Cite(_, ils) | Link(_, ils, _) | Image(_, ils, _) | Span(_, ils) => self.tokens(ils, width, tokens),
+                Quoted(quote_type, ils) => {
+                    let (open, close) = match quote_type {
+                        QuoteType::SingleQuote => ("‘", "’"),
+                        QuoteType::DoubleQuote => ("“", "”"),
+                    };
+                    tokens.push(Token::Text(open.to_string()));
+                    self.tokens(ils, width, tokens);
+                    tokens.push(Token::Text(close.to_string()));
+                },
+                Space | SoftBreak => tokens.push(Token::Space),
+                LineBreak => tokens.push(Token::LineBreak),
+                RawInline(format, text) if format == "plain" => for (i, line) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        tokens.push(Token::LineBreak);
+                    }
+                    tokens.push(Token::Text(line.to_string()));
+                },
+                RawInline(..) => {}
+                Note(blks) => {
+                    self.notes.push(String::new());
+                    let n = self.notes.len();
+                    self.notes[n - 1] = self.blocks(blks, narrow(width, 4));
+                    tokens.push(Token::Text(format!("[{}]", n)));
+                },
+            }
+        }
+    }
+}
+
+/// Written inlines, before they are laid out in lines: a line is only wrapped at a space, so that
+/// the text of an element, e.g. some code, is kept whole.
+enum Token {
+    Text(String),
+    Space,
+    LineBreak,
+}
+
+fn narrow(width: Option<usize>, by: usize) -> Option<usize> {
+    width.map(|it| it.saturating_sub(by).max(1))
+}
+
+/// Greedily fills lines up to `width` columns, keeping the explicit line breaks. The words are the
+/// texts between two spaces, whatever spaces they contain.
+fn wrap(tokens: &[Token], width: Option<usize>) -> String {
+    let mut lines = vec![Vec::new()];
+    let mut word = String::new();
+    for token in tokens {
+        match token {
+            Token::Text(text) => word.push_str(text),
+            Token::Space => lines.last_mut().unwrap().push(mem::take(&mut word)),
+            Token::LineBreak => {
+                lines.last_mut().unwrap().push(mem::take(&mut word));
+                lines.push(Vec::new());
+            },
+        }
+    }
+    lines.last_mut().unwrap().push(word);
+    let width = match width {
+        Some(width) => width,
+        None => return lines.iter().map(|words| words.join(" ")).collect::<Vec<_>>().join("\n"),
+    };
+    let mut result = String::new();
+    for (i, words) in lines.iter().enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        let mut length = 0;
+        for word in words.iter().filter(|it| !it.is_empty()) {
+            let word_length = word.chars().count();
+            if length > 0 && length + 1 + word_length > width {
+                result.push('\n');
+                length = 0;
+            } else if length > 0 {
+                result.push(' ');
+                length += 1;
+            }
+            result.push_str(word);
+            length += word_length;
+        }
+    }
+    result
+}
+
+fn align(text: &str, width: usize, alignment: &Alignment) -> String {
+    let padding = width.saturating_sub(text.chars().count());
+    match alignment {
+        Alignment::AlignRight => format!("{}{}", " ".repeat(padding), text),
+        Alignment::AlignCenter => format!("{}{}{}", " ".repeat(padding / 2), text, " ".repeat(padding - padding / 2)),
+        _ => format!("{}{}", text, " ".repeat(padding)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::definition::{Attr, Block, Inline};
+    use crate::definition::Block::{BlockQuote, BulletList, CodeBlock, Header, Para, Plain, RawBlock};
+    use crate::definition::Inline::{Code, Emph, LineBreak, Note, SoftBreak, Space, Str};
+    use super::{write_blocks, write_inlines, Options};
+
+    fn words(text: &str) -> Vec<Inline> {
+        let mut inlines = Vec::new();
+        for (i, word) in text.split(' ').enumerate() {
+            if i > 0 {
+                inlines.push(Space);
+            }
+            inlines.push(Str(word.into()));
+        }
+        inlines
+    }
+
+    fn columns(columns: usize) -> Options {
+        Options { columns: Some(columns) }
+    }
+
+    #[test]
+    fn lines_are_wrapped_between_words() {
+        let blocks = [Para(words("one two three four five"))];
+        assert_eq!(write_blocks(&blocks, &columns(9)), "one two\nthree\nfour five");
+        assert_eq!(write_blocks(&blocks, &Options { columns: None }), "one two three four five");
+        let inlines = vec![Str("one".into()), SoftBreak, Emph(vec![Str("two".into()), Space, Str("three".into())]), Str(",".into())];
+        assert_eq!(write_inlines(&inlines, &columns(7)), "one two\nthree,");
+    }
+
+    #[test]
+    fn code_and_spaces_in_texts_are_kept_whole() {
+        let inlines = vec![Str("a".into()), Space, Code(Attr::default(), "let  x = 1;".into()), Space, Str("b  c".into())];
+        assert_eq!(write_inlines(&inlines, &columns(5)), "a\nlet  x = 1;\nb  c");
+        assert_eq!(write_inlines(&inlines, &columns(80)), "a let  x = 1; b  c");
+    }
+
+    #[test]
+    fn line_breaks_start_a_new_line() {
+        let inlines = [words("a b"), vec![LineBreak], words("c d e")].concat();
+        assert_eq!(write_inlines(&inlines, &columns(3)), "a b\nc d\ne");
+        assert_eq!(write_inlines(&inlines, &Options { columns: None }), "a b\nc d e");
+    }
+
+    #[test]
+    fn blocks_are_separated_by_a_blank_line_unless_plain() {
+        let blocks = vec![
+            Plain(words("plain")),
+            Para(words("para")),
+            RawBlock("html".into(), "<hr />".into()),
+            Header(1, Attr::default(), words("header")),
+            CodeBlock(Attr::default(), "code\n".into()),
+            RawBlock("plain".into(), "raw".into()),
+        ];
+        assert_eq!(write_blocks(&blocks, &Options::default()), "plain\npara\n\nheader\n\n    code\n\nraw");
+    }
+
+    #[test]
+    fn nested_blocks_are_indented_within_the_width() {
+        let blocks = vec![
+            BlockQuote(vec![Para(words("a b c"))]),
+            BulletList(vec![vec![Plain(words("d e f"))], vec![Plain(words("g"))]]),
+        ];
+        assert_eq!(write_blocks(&blocks, &columns(5)), "  a b\n  c\n\n-   d\n    e\n    f\n-   g");
+    }
+
+    #[test]
+    fn notes_follow_the_text() {
+        let blocks: Vec<Block> = vec![Para(vec![Str("a".into()), Note(vec![Para(words("first note"))]), Space, Str("b".into()), Note(vec![Para(words("second"))])])];
+        assert_eq!(write_blocks(&blocks, &Options::default()), "a[1] b[2]\n\n[1] first note\n\n[2] second");
+    }
+}